@@ -1,14 +1,57 @@
+use crate::{journald::source::JournalError, policy::PolicyDenial, polkit::PolkitDenial};
 use actix_web::{
-  http::{header::ContentType, StatusCode},
+  http::{
+    header::{self, ContentType},
+    StatusCode,
+  },
   HttpResponse, ResponseError,
 };
 use dbus::Error as DBusError;
 use derive_more::{Display, Error};
 use serde::Serialize;
+use std::io::{Error as IoError, ErrorKind};
 
 #[derive(Debug, Display, Error)]
 pub enum ApiError {
   DBus(DBusError),
+  Io(IoError),
+  Journal(JournalError),
+
+  /// Request contained invalid data, message says what was wrong
+  #[display(fmt = "{}", _0)]
+  Validation(#[error(not(source))] String),
+
+  /// Waiting for something (i.e. a job to finish) took longer than allowed
+  #[display(fmt = "{}", _0)]
+  Timeout(#[error(not(source))] String),
+
+  /// Requested thing (i.e. a unit file) doesn't exist
+  #[display(fmt = "{}", _0)]
+  NotFound(#[error(not(source))] String),
+
+  /// Request would overwrite something (i.e. an existing unit file)
+  #[display(fmt = "{}", _0)]
+  Conflict(#[error(not(source))] String),
+
+  /// Request has no valid credentials
+  #[display(fmt = "{}", _0)]
+  Unauthorized(#[error(not(source))] String),
+
+  /// Too many failed logins, has to wait this many seconds before trying again
+  #[display(fmt = "Too many failed login attempts, try again in {} seconds", _0)]
+  TooManyRequests(#[error(not(source))] u64),
+
+  /// Request isn't allowed, i.e. a power action without a valid confirmation token
+  #[display(fmt = "{}", _0)]
+  Forbidden(#[error(not(source))] String),
+
+  /// Policy doesn't allow the identity the action
+  #[display(fmt = "{}", _0)]
+  Policy(#[error(not(source))] PolicyDenial),
+
+  /// polkit doesn't authorize the Unix account behind the request
+  #[display(fmt = "{}", _0)]
+  Polkit(#[error(not(source))] PolkitDenial),
 }
 
 #[derive(Serialize)]
@@ -29,6 +72,93 @@ impl ApiError {
   pub fn error_data(&self) -> ApiErrorData {
     match &self {
       ApiError::DBus(err) => err.to_error_data(),
+      ApiError::Io(err) => ApiErrorData {
+        status: match err.kind() {
+          ErrorKind::NotFound => StatusCode::NOT_FOUND,
+          ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+          ErrorKind::AlreadyExists => StatusCode::CONFLICT,
+          _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+        .as_u16(),
+        error_type: ErrorType {
+          namespace: "Io".to_owned(),
+          inner: Some(format!("{:?}", err.kind())),
+        },
+        message: Some(err.to_string()),
+      },
+      ApiError::Journal(err) => err.to_error_data(),
+      ApiError::Validation(message) => ApiErrorData {
+        status: StatusCode::BAD_REQUEST.as_u16(),
+        error_type: ErrorType {
+          namespace: "Validation".to_owned(),
+          inner: None,
+        },
+        message: Some(message.to_owned()),
+      },
+      ApiError::Timeout(message) => ApiErrorData {
+        status: StatusCode::GATEWAY_TIMEOUT.as_u16(),
+        error_type: ErrorType {
+          namespace: "Timeout".to_owned(),
+          inner: None,
+        },
+        message: Some(message.to_owned()),
+      },
+      ApiError::NotFound(message) => ApiErrorData {
+        status: StatusCode::NOT_FOUND.as_u16(),
+        error_type: ErrorType {
+          namespace: "NotFound".to_owned(),
+          inner: None,
+        },
+        message: Some(message.to_owned()),
+      },
+      ApiError::Conflict(message) => ApiErrorData {
+        status: StatusCode::CONFLICT.as_u16(),
+        error_type: ErrorType {
+          namespace: "Conflict".to_owned(),
+          inner: None,
+        },
+        message: Some(message.to_owned()),
+      },
+      ApiError::Unauthorized(message) => ApiErrorData {
+        status: StatusCode::UNAUTHORIZED.as_u16(),
+        error_type: ErrorType {
+          namespace: "Unauthorized".to_owned(),
+          inner: None,
+        },
+        message: Some(message.to_owned()),
+      },
+      ApiError::TooManyRequests(_) => ApiErrorData {
+        status: StatusCode::TOO_MANY_REQUESTS.as_u16(),
+        error_type: ErrorType {
+          namespace: "TooManyRequests".to_owned(),
+          inner: None,
+        },
+        message: Some(self.to_string()),
+      },
+      ApiError::Forbidden(message) => ApiErrorData {
+        status: StatusCode::FORBIDDEN.as_u16(),
+        error_type: ErrorType {
+          namespace: "Forbidden".to_owned(),
+          inner: None,
+        },
+        message: Some(message.to_owned()),
+      },
+      ApiError::Policy(denial) => ApiErrorData {
+        status: StatusCode::FORBIDDEN.as_u16(),
+        error_type: ErrorType {
+          namespace: "Policy".to_owned(),
+          inner: Some(denial.action.to_owned()),
+        },
+        message: Some(denial.to_string()),
+      },
+      ApiError::Polkit(denial) => ApiErrorData {
+        status: StatusCode::FORBIDDEN.as_u16(),
+        error_type: ErrorType {
+          namespace: "Polkit".to_owned(),
+          inner: Some(denial.action_id.to_owned()),
+        },
+        message: Some(denial.to_string()),
+      },
       #[allow(unreachable_patterns)]
       _ => ApiErrorData {
         status: StatusCode::INTERNAL_SERVER_ERROR.as_u16(),
@@ -48,16 +178,34 @@ impl From<DBusError> for ApiError {
   }
 }
 
+impl From<IoError> for ApiError {
+  fn from(err: IoError) -> Self {
+    Self::Io(err)
+  }
+}
+
+impl From<JournalError> for ApiError {
+  fn from(err: JournalError) -> Self {
+    Self::Journal(err)
+  }
+}
+
 impl ResponseError for ApiError {
   fn error_response(&self) -> HttpResponse<actix_web::body::BoxBody> {
     let error_data = self.error_data();
     let serialized = serde_json::to_string(&error_data).unwrap_or("{}".to_owned());
 
-    HttpResponse::build(
+    let mut response = HttpResponse::build(
       StatusCode::from_u16(error_data.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-    )
-    .insert_header(ContentType::json())
-    .body(serialized)
+    );
+    if let ApiError::Unauthorized(_) = self {
+      response.insert_header((header::WWW_AUTHENTICATE, "Bearer"));
+    }
+    if let ApiError::TooManyRequests(seconds) = self {
+      response.insert_header((header::RETRY_AFTER, seconds.to_string()));
+    }
+
+    response.insert_header(ContentType::json()).body(serialized)
   }
 }
 
@@ -77,19 +225,34 @@ trait ToErrorData {
 
 impl ToErrorData for DBusError {
   fn to_error_data(&self) -> ApiErrorData {
-    match self.name() {
+    let status = match self.name() {
       Some(name) => match name {
-        "org.freedesktop.DBus.Error.InvalidArgs" => ApiErrorData {
-          status: StatusCode::BAD_REQUEST.as_u16(),
-          error_type: ErrorType {
-            namespace: "DBus".to_owned(),
-            inner: Some(name.to_owned()),
-          },
-          message: self.message().map(str::to_string),
-        },
-        _ => self.unknown(),
+        "org.freedesktop.DBus.Error.InvalidArgs"
+        | "org.freedesktop.systemd1.JobTypeNotApplicable"
+        | "org.freedesktop.systemd1.OnlyByDependency"
+        | "org.freedesktop.systemd1.UnitMasked"
+        | "org.freedesktop.login1.SleepVerbNotSupported" => StatusCode::BAD_REQUEST,
+        "org.freedesktop.systemd1.NoSuchUnit" | "org.freedesktop.systemd1.NoSuchJob" => {
+          StatusCode::NOT_FOUND
+        }
+        "org.freedesktop.DBus.Error.AccessDenied"
+        | "org.freedesktop.DBus.Error.InteractiveAuthorizationRequired" => StatusCode::FORBIDDEN,
+        "org.freedesktop.systemd1.TransactionIsDestructive"
+        | "org.freedesktop.systemd1.TransactionJobsConflicting"
+        | "org.freedesktop.systemd1.UnitExists"
+        | "org.freedesktop.login1.OperationInProgress" => StatusCode::CONFLICT,
+        _ => return self.unknown(),
       },
-      None => self.unknown(),
+      None => return self.unknown(),
+    };
+
+    ApiErrorData {
+      status: status.as_u16(),
+      error_type: ErrorType {
+        namespace: "DBus".to_owned(),
+        inner: self.name().map(str::to_string),
+      },
+      message: self.message().map(str::to_string),
     }
   }
 
@@ -104,3 +267,23 @@ impl ToErrorData for DBusError {
     }
   }
 }
+
+impl ToErrorData for JournalError {
+  fn to_error_data(&self) -> ApiErrorData {
+    let (status, inner) = match self {
+      JournalError::Io(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Io"),
+      JournalError::Corrupted(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Corrupted"),
+      JournalError::Unsupported(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Unsupported"),
+      JournalError::InvalidCursor(_) => (StatusCode::BAD_REQUEST, "InvalidCursor"),
+    };
+
+    ApiErrorData {
+      status: status.as_u16(),
+      error_type: ErrorType {
+        namespace: "Journal".to_owned(),
+        inner: Some(inner.to_owned()),
+      },
+      message: Some(self.to_string()),
+    }
+  }
+}
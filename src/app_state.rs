@@ -1,6 +1,31 @@
-use crate::DBusInterface;
-use std::sync::Mutex;
+use crate::{
+  audit::Audit, auth::Auth, journald::source::JournalSource, policy::Policy, polkit::Polkit,
+  power::confirmation::Confirmations, systemd::events::SystemdEvent, DBusInterface,
+};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
 
 pub struct AppState<'a> {
   pub dbus: Mutex<DBusInterface<'a>>,
+
+  /// Signals received from systemd, call `subscribe()` to get a receiver
+  pub systemd_events: broadcast::Sender<SystemdEvent>,
+
+  /// Where logs are read from
+  pub journal: Arc<dyn JournalSource + Send + Sync>,
+
+  /// Who may use the API
+  pub auth: Auth,
+
+  /// What authenticated identities may do
+  pub policy: Policy,
+
+  /// Whether polkit decides for the Unix account behind requests
+  pub polkit: Polkit,
+
+  /// Records of state-changing requests
+  pub audit: Audit,
+
+  /// Tokens issued for confirming power actions
+  pub power_confirmations: Confirmations,
 }
@@ -0,0 +1,76 @@
+//! Audit records as JSON lines, rotated by size like logrotate would
+
+use std::{
+  fs::{self, File, OpenOptions},
+  io::{self, Write},
+  os::unix::fs::OpenOptionsExt,
+};
+
+use super::AuditRecord;
+
+pub struct AuditFile {
+  path: String,
+  max_size: u64,
+  keep: usize,
+  file: File,
+  size: u64,
+}
+
+impl AuditFile {
+  pub fn open(path: &str, max_size: u64, keep: usize) -> io::Result<AuditFile> {
+    let file = Self::open_file(path)?;
+    let size = file.metadata()?.len();
+
+    Ok(AuditFile {
+      path: path.to_owned(),
+      max_size,
+      keep,
+      file,
+      size,
+    })
+  }
+
+  /// Records hold parameters of requests, so only the owner may read them
+  fn open_file(path: &str) -> io::Result<File> {
+    OpenOptions::new()
+      .create(true)
+      .append(true)
+      .mode(0o600)
+      .open(path)
+  }
+
+  pub fn write(&mut self, record: &AuditRecord) -> io::Result<()> {
+    let mut line = serde_json::to_vec(record)?;
+    line.push(b'\n');
+
+    if self.size > 0 && self.size + line.len() as u64 > self.max_size {
+      self.rotate()?;
+    }
+
+    self.file.write_all(&line)?;
+    self.size += line.len() as u64;
+    Ok(())
+  }
+
+  /// Shifts `file.1` to `file.2` and so on, dropping the oldest, then starts a new file
+  fn rotate(&mut self) -> io::Result<()> {
+    let rotated = |index: usize| format!("{}.{}", self.path, index);
+
+    match self.keep {
+      0 => fs::remove_file(&self.path)?,
+      keep => {
+        for index in (1..keep).rev() {
+          match fs::rename(rotated(index), rotated(index + 1)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(err),
+            _ => {}
+          }
+        }
+        fs::rename(&self.path, rotated(1))?;
+      }
+    }
+
+    self.file = Self::open_file(&self.path)?;
+    self.size = 0;
+    Ok(())
+  }
+}
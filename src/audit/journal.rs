@@ -0,0 +1,96 @@
+//! Writes audit records to journald with its native protocol, so every part of a record is a
+//! field of its own, see systemd.journal-fields(7) and sd_journal_send(3)
+
+use std::{io, os::unix::net::UnixDatagram};
+
+use super::{AuditRecord, Outcome, MESSAGE_ID, SYSLOG_IDENTIFIER};
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+/// Longest `DRAGOND_PARAMETERS` value, longer ones are cut so the datagram fits
+const MAX_PARAMETERS_LENGTH: usize = 8 * 1024;
+
+/// Syslog priorities
+const PRIORITY_WARNING: u8 = 4;
+const PRIORITY_NOTICE: u8 = 5;
+
+pub struct JournalWriter {
+  socket: UnixDatagram,
+}
+
+impl JournalWriter {
+  pub fn new() -> io::Result<JournalWriter> {
+    Ok(JournalWriter {
+      socket: UnixDatagram::unbound()?,
+    })
+  }
+
+  pub fn write(&self, record: &AuditRecord) -> io::Result<()> {
+    let mut parameters = record.parameters.to_string();
+    if parameters.len() > MAX_PARAMETERS_LENGTH {
+      let mut end = MAX_PARAMETERS_LENGTH;
+      while !parameters.is_char_boundary(end) {
+        end -= 1;
+      }
+      parameters.truncate(end);
+      parameters.push_str("...");
+    }
+
+    let priority = match record.outcome {
+      Outcome::Success => PRIORITY_NOTICE,
+      Outcome::Denied | Outcome::Failure => PRIORITY_WARNING,
+    };
+
+    let mut datagram = Vec::new();
+    let mut field = |name: &str, value: Option<&str>| {
+      if let Some(value) = value {
+        append_field(&mut datagram, name, value.as_bytes());
+      }
+    };
+
+    field("MESSAGE", Some(&record.message()));
+    field("MESSAGE_ID", Some(MESSAGE_ID));
+    field("PRIORITY", Some(&priority.to_string()));
+    field("SYSLOG_IDENTIFIER", Some(SYSLOG_IDENTIFIER));
+    field("DRAGOND_ACTOR", record.actor.as_deref());
+    field(
+      "DRAGOND_ACTOR_KIND",
+      record.actor_kind.map(|kind| kind.as_str()),
+    );
+    field("DRAGOND_USER", record.user.as_deref());
+    field("DRAGOND_SOURCE", Some(&record.source));
+    field("DRAGOND_FORWARDED_FOR", record.forwarded_for.as_deref());
+    field("DRAGOND_METHOD", Some(&record.method));
+    field("DRAGOND_ROUTE", Some(&record.route));
+    field("DRAGOND_ACTION", record.action);
+    field("DRAGOND_UNIT", record.unit.as_deref());
+    field("DRAGOND_PARAMETERS", Some(&parameters));
+    field(
+      "DRAGOND_JOB_ID",
+      record.job_id.map(|id| id.to_string()).as_deref(),
+    );
+    field("DRAGOND_STATUS", Some(&record.status.to_string()));
+    field("DRAGOND_OUTCOME", Some(record.outcome.as_str()));
+    field("DRAGOND_ERROR", record.error.as_deref());
+
+    self.socket.send_to(&datagram, JOURNAL_SOCKET)?;
+    Ok(())
+  }
+}
+
+/// `NAME=value\n`, or for values with new lines `NAME\n`, the little endian 64 bit length,
+/// the value and `\n`
+fn append_field(datagram: &mut Vec<u8>, name: &str, value: &[u8]) {
+  datagram.extend_from_slice(name.as_bytes());
+
+  match value.contains(&b'\n') {
+    true => {
+      datagram.push(b'\n');
+      datagram.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    }
+    false => datagram.push(b'='),
+  }
+
+  datagram.extend_from_slice(value);
+  datagram.push(b'\n');
+}
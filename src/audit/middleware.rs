@@ -0,0 +1,285 @@
+use std::{
+  future::{ready, Future, Ready},
+  pin::Pin,
+  rc::Rc,
+};
+
+use actix_web::{
+  body::{self, BoxBody, MessageBody},
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  http::{
+    header::{self, ContentType},
+    Method, StatusCode,
+  },
+  web::{self, Bytes, Query},
+  Error, HttpMessage,
+};
+use serde_json::{Map, Value};
+
+use crate::{
+  api_errors::ApiError,
+  auth::SharedIdentity,
+  journald::time,
+  policy::actions::{self, Classification},
+  server, AppState,
+};
+
+use super::{AuditRecord, Outcome};
+
+/// Bodies larger than this are left out of records
+const MAX_RECORDED_BODY: usize = 64 * 1024;
+
+/// Records every request changing state. Has to wrap `Authentication`, so requests it refuses
+/// are recorded too. The body is copied while the handler reads it, so only what the handler
+/// accepted is held.
+pub struct AuditLog;
+
+impl<S, B> Transform<S, ServiceRequest> for AuditLog
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<BoxBody>;
+  type Error = Error;
+  type Transform = AuditLogMiddleware<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ready(Ok(AuditLogMiddleware {
+      service: Rc::new(service),
+    }))
+  }
+}
+
+pub struct AuditLogMiddleware<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AuditLogMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  B: MessageBody + 'static,
+{
+  type Response = ServiceResponse<BoxBody>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  forward_ready!(service);
+
+  fn call(&self, mut req: ServiceRequest) -> Self::Future {
+    let service = Rc::clone(&self.service);
+
+    Box::pin(async move {
+      let state = req
+        .app_data::<web::Data<AppState<'static>>>()
+        .expect("AppState is registered as app data")
+        .clone();
+
+      let (action, unit) = match actions::classify(&req) {
+        Classification::Action(action) => (Some(action.action), action.unit().map(str::to_owned)),
+        _ => (None, None),
+      };
+      let changes_state = matches!(
+        *req.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+      ) && !action.is_some_and(|action| action.ends_with(".read"));
+
+      if !state.audit.enabled || !changes_state {
+        return service
+          .call(req)
+          .await
+          .map(ServiceResponse::map_into_boxed_body);
+      }
+
+      let body = server::copy_body(&mut req, MAX_RECORDED_BODY);
+      let query_string = req.query_string().to_owned();
+      let identity = SharedIdentity::default();
+      req.extensions_mut().insert(identity.clone());
+      let forwarded = req.headers().contains_key(header::FORWARDED)
+        || req.headers().contains_key("X-Forwarded-For");
+      let now = time::now_usec();
+
+      let mut record = AuditRecord {
+        realtime_timestamp: now,
+        timestamp: time::format_rfc3339(now),
+        actor: None,
+        actor_kind: None,
+        user: None,
+        source: req
+          .peer_addr()
+          .map(|address| address.ip().to_string())
+          .unwrap_or_else(|| "unix".to_owned()),
+        forwarded_for: match forwarded {
+          true => req
+            .connection_info()
+            .realip_remote_addr()
+            .map(str::to_owned),
+          false => None,
+        },
+        method: req.method().to_string(),
+        route: req.path().to_owned(),
+        action,
+        unit,
+        parameters: Value::Null,
+        job_id: None,
+        status: 0,
+        outcome: Outcome::Success,
+        error: None,
+      };
+
+      let result = service.call(req).await;
+
+      let response = match result {
+        Ok(response) => {
+          let (response, body) = buffer_json_body(response).await?;
+          record.status = response.status().as_u16();
+          if let Some(body) = body {
+            match response.status().is_success() {
+              true => record.job_id = job_id(&body),
+              false => record.error = body["message"].as_str().map(str::to_owned),
+            }
+          }
+          Ok(response)
+        }
+        Err(err) => {
+          record.status = err.error_response().status().as_u16();
+          record.error = Some(err.to_string());
+          Err(err)
+        }
+      };
+
+      if let Some(identity) = identity.get() {
+        record.actor = Some(identity.name);
+        record.actor_kind = Some(identity.kind);
+        record.user = identity.user;
+      }
+      record.parameters = parameters(&query_string, body.take());
+
+      record.outcome = match StatusCode::from_u16(record.status) {
+        Ok(status) if status.is_success() || status.is_redirection() => Outcome::Success,
+        Ok(StatusCode::UNAUTHORIZED) | Ok(StatusCode::FORBIDDEN) => Outcome::Denied,
+        _ => Outcome::Failure,
+      };
+      state.audit.record(&record);
+
+      response
+    })
+  }
+}
+
+/// Reads JSON response bodies, which carry the job id or the error message. Other responses,
+/// like event streams, are passed on untouched.
+async fn buffer_json_body<B: MessageBody + 'static>(
+  response: ServiceResponse<B>,
+) -> Result<(ServiceResponse<BoxBody>, Option<Value>), Error> {
+  let is_json = response
+    .headers()
+    .get(header::CONTENT_TYPE)
+    .is_some_and(|value| value == ContentType::json().0.essence_str());
+  if !is_json {
+    return Ok((response.map_into_boxed_body(), None));
+  }
+
+  let (req, response) = response.into_parts();
+  let (response, body) = response.into_parts();
+  let bytes = body::to_bytes(body)
+    .await
+    .map_err(|err| ApiError::Io(std::io::Error::other(err.into().to_string())))?;
+
+  let value = serde_json::from_slice(&bytes).ok();
+  let response = response.set_body(bytes).map_into_boxed_body();
+  Ok((ServiceResponse::new(req, response), value))
+}
+
+/// Query parameters and the body. Values of fields which look like secrets are replaced.
+fn parameters(query_string: &str, body: Option<Bytes>) -> Value {
+  let mut query = Map::new();
+  if let Ok(pairs) = Query::<Vec<(String, String)>>::from_query(query_string) {
+    for (name, value) in pairs.into_inner() {
+      match query.get_mut(&name) {
+        Some(Value::Array(values)) => values.push(Value::String(value)),
+        Some(previous) => *previous = Value::Array(vec![previous.take(), Value::String(value)]),
+        None => {
+          query.insert(name, Value::String(value));
+        }
+      }
+    }
+  }
+
+  let mut body = match body {
+    None => Value::String("[too large to record]".to_owned()),
+    Some(body) if body.is_empty() => Value::Null,
+    Some(body) => serde_json::from_slice(&body)
+      .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(&body).into_owned())),
+  };
+  redact(&mut body);
+
+  let mut parameters = Map::new();
+  parameters.insert("query".to_owned(), Value::Object(query));
+  parameters.insert("body".to_owned(), body);
+  Value::Object(parameters)
+}
+
+fn redact(value: &mut Value) {
+  match value {
+    Value::Object(fields) => {
+      for (name, value) in fields.iter_mut() {
+        let name = name.to_ascii_lowercase();
+        match name.contains("password") || name.contains("token") {
+          true => *value = Value::String("[redacted]".to_owned()),
+          false => redact(value),
+        }
+      }
+    }
+    Value::Array(values) => values.iter_mut().for_each(redact),
+    _ => {}
+  }
+}
+
+/// Id of the job in responses with a job, like unit jobs, transient units and power actions
+fn job_id(body: &Value) -> Option<u32> {
+  [body, &body["job"]]
+    .into_iter()
+    .filter(|job| {
+      job["objectPath"]
+        .as_str()
+        .is_some_and(|path| path.starts_with("/org/freedesktop/systemd1/job/"))
+    })
+    .find_map(|job| job["id"].as_u64())
+    .and_then(|id| u32::try_from(id).ok())
+}
+
+#[cfg(test)]
+mod tests {
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn records_parameters_without_secrets() {
+    let body =
+      Bytes::from(r#"{"username":"root","password":"hunter2","nested":[{"apiToken":"x"}]}"#);
+    assert_eq!(
+      parameters("unit=a&unit=b&mode=fail", Some(body)),
+      json!({
+        "query": {"unit": ["a", "b"], "mode": "fail"},
+        "body": {"username": "root", "password": "[redacted]", "nested": [{"apiToken": "[redacted]"}]},
+      })
+    );
+
+    assert_eq!(parameters("", Some(Bytes::new()))["body"], Value::Null);
+    assert_eq!(parameters("", None)["body"], "[too large to record]");
+  }
+
+  #[test]
+  fn finds_job_ids() {
+    let job = json!({"id": 42, "objectPath": "/org/freedesktop/systemd1/job/42"});
+    assert_eq!(job_id(&job), Some(42));
+    assert_eq!(job_id(&json!({"unit": "a.service", "job": job})), Some(42));
+    assert_eq!(
+      job_id(&json!({"id": 42, "objectPath": "/org/freedesktop/systemd1/unit/a"})),
+      None
+    );
+  }
+}
@@ -0,0 +1,157 @@
+//! Audit log of state-changing requests: who did what to which unit, from where, and how it
+//! ended. Records go to the journal and optionally to a JSON lines file.
+
+pub mod file;
+pub mod journal;
+pub mod middleware;
+pub mod routes;
+
+use std::sync::Mutex;
+
+use serde::Serialize;
+
+use crate::{auth::dto::IdentityKind, config::AuditConfig};
+
+use self::{file::AuditFile, journal::JournalWriter};
+
+/// `SYSLOG_IDENTIFIER` of audit records
+pub const SYSLOG_IDENTIFIER: &str = "dragond-audit";
+
+/// `MESSAGE_ID` of audit records, tells them apart from other messages using the identifier
+pub const MESSAGE_ID: &str = "4aed2f4bdfca422facaa27f73ed9431d";
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Outcome {
+  Success,
+
+  /// Refused by the policy or polkit
+  Denied,
+  Failure,
+}
+
+impl Outcome {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      Outcome::Success => "success",
+      Outcome::Denied => "denied",
+      Outcome::Failure => "failure",
+    }
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditRecord {
+  /// Microseconds since the epoch
+  pub realtime_timestamp: u64,
+  pub timestamp: String,
+
+  /// Identity name, `None` when authentication is off or refused the request
+  pub actor: Option<String>,
+  pub actor_kind: Option<IdentityKind>,
+
+  /// Unix account the request acted as
+  pub user: Option<String>,
+
+  /// Peer address, `unix` for Unix sockets
+  pub source: String,
+
+  /// Client address a proxy forwarded the request for, when it differs from the peer
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub forwarded_for: Option<String>,
+
+  pub method: String,
+  pub route: String,
+
+  /// Policy action, see `policy::actions`
+  pub action: Option<&'static str>,
+  pub unit: Option<String>,
+
+  /// Query and body, with secrets redacted
+  pub parameters: serde_json::Value,
+
+  /// Id of the systemd job the request enqueued
+  pub job_id: Option<u32>,
+
+  /// HTTP status of the response
+  pub status: u16,
+  pub outcome: Outcome,
+
+  /// Error message of failed and denied requests
+  pub error: Option<String>,
+}
+
+impl AuditRecord {
+  /// One line summary, used as `MESSAGE` in the journal
+  pub fn message(&self) -> String {
+    let mut message = format!(
+      "{} {} {}: {}",
+      self.actor.as_deref().unwrap_or("anonymous"),
+      self.method,
+      self.route,
+      self.outcome.as_str()
+    );
+    if let Some(job_id) = self.job_id {
+      message.push_str(&format!(", job {}", job_id));
+    }
+    if let Some(error) = &self.error {
+      message.push_str(&format!(", {}", error));
+    }
+    message
+  }
+}
+
+pub struct Audit {
+  pub enabled: bool,
+  journal: Option<JournalWriter>,
+  file: Option<Mutex<AuditFile>>,
+}
+
+impl Audit {
+  pub fn new(config: &AuditConfig) -> Result<Audit, String> {
+    if !config.enabled {
+      return Ok(Audit {
+        enabled: false,
+        journal: None,
+        file: None,
+      });
+    }
+
+    let journal = match config.journal {
+      true => {
+        Some(JournalWriter::new().map_err(|err| format!("Can't create journal socket: {}", err))?)
+      }
+      false => None,
+    };
+    let file = match &config.file {
+      Some(path) => Some(Mutex::new(
+        AuditFile::open(path, config.file_max_size, config.file_keep)
+          .map_err(|err| format!("Can't open audit log {}: {}", path, err))?,
+      )),
+      None => None,
+    };
+
+    Ok(Audit {
+      enabled: true,
+      journal,
+      file,
+    })
+  }
+
+  /// Writes the record everywhere it's configured to go. Failures are logged, the request
+  /// already happened and failing it now wouldn't undo anything.
+  pub fn record(&self, record: &AuditRecord) {
+    if let Some(journal) = &self.journal {
+      if let Err(err) = journal.write(record) {
+        error!("Can't write audit record to the journal: {}", err);
+      }
+    }
+
+    if let Some(file) = &self.file {
+      if let Err(err) = file.lock().unwrap().write(record) {
+        error!("Can't write audit record to the audit log: {}", err);
+      }
+    }
+  }
+}
@@ -0,0 +1,61 @@
+use crate::{
+  api_errors::ApiError,
+  audit::{MESSAGE_ID, SYSLOG_IDENTIFIER},
+  journald::{query::LogsQuery, routes},
+  AppState,
+};
+use actix_web::{get, web, web::Query, HttpRequest, Responder};
+
+#[derive(Deserialize)]
+struct AuditQuery {
+  /// Identity name
+  actor: Option<String>,
+  unit: Option<String>,
+
+  /// Policy action, i.e. `unit.restart`
+  action: Option<String>,
+
+  /// `success`, `denied` or `failure`
+  outcome: Option<String>,
+}
+
+/// Reads audit records from the journal. Takes the same filters as `/journald/logs`, besides
+/// the ones of `AuditQuery`. Any local process can send messages looking like records, so only
+/// ones journald stamped with dragond's account and process name are returned. Processes
+/// running as the same account and name can still forge them, the file sink is the copy
+/// other local processes can't add to.
+#[get("")]
+async fn audit_log(
+  state: web::Data<AppState<'static>>,
+  req: HttpRequest,
+  params: Query<LogsQuery>,
+  audit_params: Query<AuditQuery>,
+) -> Result<impl Responder, ApiError> {
+  let audit_params = audit_params.into_inner();
+  let pair = |field: &str, value: &str| (field.to_owned(), value.to_owned());
+
+  // Fields starting with _ are set by journald, clients can't send them
+  let uid = unsafe { libc::geteuid() }.to_string();
+  let mut matches = vec![
+    pair("SYSLOG_IDENTIFIER", SYSLOG_IDENTIFIER),
+    pair("MESSAGE_ID", MESSAGE_ID),
+    pair("_UID", &uid),
+  ];
+  if let Ok(comm) = std::fs::read_to_string("/proc/self/comm") {
+    matches.push(pair("_COMM", comm.trim_end()));
+  }
+  let filters = [
+    ("DRAGOND_ACTOR", audit_params.actor),
+    ("DRAGOND_UNIT", audit_params.unit),
+    ("DRAGOND_ACTION", audit_params.action),
+    ("DRAGOND_OUTCOME", audit_params.outcome),
+  ];
+  for (field, value) in filters {
+    if let Some(value) = value {
+      matches.push(pair(field, &value));
+    }
+  }
+
+  let query = routes::journal_query(&state, &req, params.into_inner(), vec![matches]).await?;
+  routes::entries_response(&state, query).await
+}
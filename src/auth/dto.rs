@@ -0,0 +1,66 @@
+use serde::Serialize;
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IdentityKind {
+  /// Static token from the config
+  Token,
+
+  /// Session from PAM login
+  Session,
+
+  /// TLS client certificate from the config
+  Certificate,
+}
+
+impl IdentityKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      IdentityKind::Token => "token",
+      IdentityKind::Session => "session",
+      IdentityKind::Certificate => "certificate",
+    }
+  }
+}
+
+/// Who made a request, stored in request extensions by the authentication middleware
+#[derive(Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Identity {
+  /// Token name or user name
+  pub name: String,
+  pub kind: IdentityKind,
+
+  /// Unix account the request acts as, the user for sessions and the configured one for tokens
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub user: Option<String>,
+
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub session_id: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct LoginRequest {
+  pub username: String,
+  pub password: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionDto {
+  pub id: String,
+  pub user: String,
+
+  /// Microseconds since the epoch
+  pub created_at: u64,
+  pub expires_at: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoginDto {
+  /// Send it as `Authorization: Bearer <token>`, it isn't shown again. Event streams also
+  /// accept the session cookie set along with it.
+  pub token: String,
+  pub session: SessionDto,
+}
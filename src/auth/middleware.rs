@@ -0,0 +1,145 @@
+use std::{
+  future::{ready, Future, Ready},
+  pin::Pin,
+  rc::Rc,
+};
+
+use actix_web::{
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  http::{header, Method},
+  web, Error, HttpMessage,
+};
+
+use crate::{
+  api_errors::ApiError,
+  auth::{ClientCertificate, SharedIdentity, SESSION_COOKIE},
+  AppState,
+};
+
+/// Requests which don't need a token, everything else does
+fn is_public(req: &ServiceRequest) -> bool {
+  req.method() == Method::POST && req.path() == "/auth/login"
+}
+
+/// Server-Sent Events streams, browsers open them with EventSource which can't send an
+/// `Authorization` header
+fn is_event_stream(req: &ServiceRequest) -> bool {
+  let path = req.path().trim_end_matches('/');
+  req.method() == Method::GET
+    && (path == "/systemd/events" || path.starts_with("/journald/") && path.ends_with("/follow"))
+}
+
+/// Rejects requests without a valid `Authorization: Bearer` token or client certificate and
+/// stores the `Identity` of the others in request extensions. Event streams also take the
+/// session cookie set by login. Other requests don't, so other sites can't make the browser
+/// send requests changing anything.
+pub struct Authentication;
+
+impl<S, B> Transform<S, ServiceRequest> for Authentication
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Transform = AuthenticationMiddleware<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ready(Ok(AuthenticationMiddleware {
+      service: Rc::new(service),
+    }))
+  }
+}
+
+pub struct AuthenticationMiddleware<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AuthenticationMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let service = Rc::clone(&self.service);
+
+    Box::pin(async move {
+      let state = req
+        .app_data::<web::Data<AppState<'static>>>()
+        .expect("AppState is registered as app data");
+
+      if state.auth.enabled && !is_public(&req) {
+        let cookie = match is_event_stream(&req) {
+          true => req.cookie(SESSION_COOKIE),
+          false => None,
+        };
+        let token = req
+          .headers()
+          .get(header::AUTHORIZATION)
+          .and_then(|value| value.to_str().ok())
+          .and_then(|value| value.strip_prefix("Bearer "))
+          .map(str::trim)
+          .or_else(|| cookie.as_ref().map(|cookie| cookie.value()));
+
+        let identity = match token {
+          Some(token) => state.auth.authenticate(token).ok_or_else(|| {
+            ApiError::Unauthorized("Token is invalid, expired or revoked".to_owned())
+          })?,
+          None => match req.conn_data::<ClientCertificate>() {
+            Some(certificate) => state
+              .auth
+              .authenticate_certificate(certificate)
+              .ok_or_else(|| ApiError::Unauthorized("Client certificate isn't known".to_owned()))?,
+            None => {
+              return Err(
+                ApiError::Unauthorized("Missing Authorization: Bearer header".to_owned()).into(),
+              )
+            }
+          },
+        };
+        if let Some(shared) = req.extensions().get::<SharedIdentity>() {
+          *shared.0.borrow_mut() = Some(identity.clone());
+        }
+        req.extensions_mut().insert(identity);
+      }
+
+      service.call(req).await
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::test::TestRequest;
+
+  use super::*;
+
+  #[test]
+  fn recognizes_event_streams() {
+    let stream =
+      |request: TestRequest, uri: &str| is_event_stream(&request.uri(uri).to_srv_request());
+
+    assert!(stream(TestRequest::get(), "/systemd/events"));
+    assert!(stream(
+      TestRequest::get(),
+      "/systemd/events/?units=a.service"
+    ));
+    assert!(stream(TestRequest::get(), "/journald/logs/follow"));
+    assert!(stream(
+      TestRequest::get(),
+      "/journald/unit-logs/sshd/follow"
+    ));
+    assert!(!stream(TestRequest::post(), "/systemd/events"));
+    assert!(!stream(TestRequest::get(), "/journald/logs"));
+    assert!(!stream(TestRequest::get(), "/systemd/units/follow"));
+    assert!(!stream(TestRequest::get(), "/auth/whoami"));
+  }
+}
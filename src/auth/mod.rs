@@ -0,0 +1,406 @@
+//! Who is making a request. Requests carry a bearer token, either a static one from the
+//! config or a session token from PAM login, or come over a connection with a TLS client
+//! certificate from the config.
+
+pub mod dto;
+pub mod middleware;
+pub mod pam;
+pub mod routes;
+pub mod throttle;
+
+use std::{
+  cell::RefCell, collections::HashMap, ffi::CString, fs::File, io::Read, ptr, rc::Rc, sync::Mutex,
+};
+
+use sha2::{Digest, Sha256};
+
+use crate::{api_errors::ApiError, config::AuthConfig, journald::time};
+
+use self::{
+  dto::{Identity, IdentityKind, SessionDto},
+  throttle::LoginThrottle,
+};
+
+/// Cookie holding the session token, browsers send it along with EventSource requests which
+/// can't have an `Authorization` header
+pub const SESSION_COOKIE: &str = "dragond_session";
+
+/// A static token from the config
+struct ApiToken {
+  name: String,
+  user: Option<String>,
+  hash: [u8; 32],
+
+  /// Microseconds since the epoch
+  expires: Option<u64>,
+  revoked: bool,
+}
+
+/// A TLS client certificate from the config
+struct ApiCertificate {
+  name: String,
+  user: Option<String>,
+  fingerprint: [u8; 32],
+}
+
+/// Client certificate of a TLS connection, stored in connection data when the connection is
+/// accepted
+pub struct ClientCertificate {
+  /// SHA-256 of the DER certificate
+  pub fingerprint: [u8; 32],
+}
+
+/// Put in request extensions by middlewares wrapping `Authentication`, which fills it with the
+/// identity it found. Unlike the request, it's still there when an inner service fails.
+#[derive(Clone, Default)]
+pub struct SharedIdentity(Rc<RefCell<Option<Identity>>>);
+
+impl SharedIdentity {
+  pub fn get(&self) -> Option<Identity> {
+    self.0.borrow().clone()
+  }
+}
+
+struct Session {
+  id: String,
+  user: String,
+  created_at: u64,
+  expires_at: u64,
+}
+
+impl Session {
+  fn to_dto(&self) -> SessionDto {
+    SessionDto {
+      id: self.id.clone(),
+      user: self.user.clone(),
+      created_at: self.created_at,
+      expires_at: self.expires_at,
+    }
+  }
+}
+
+pub struct Auth {
+  pub enabled: bool,
+  session_ttl: u64,
+  pam_service: String,
+  pam_allowed_users: Vec<String>,
+  pam_allowed_groups: Vec<String>,
+  tokens: Vec<ApiToken>,
+  certificates: Vec<ApiCertificate>,
+
+  /// Sessions by SHA-256 of their token, so tokens themselves aren't kept around
+  sessions: Mutex<HashMap<[u8; 32], Session>>,
+  throttle: LoginThrottle,
+}
+
+impl Auth {
+  pub fn new(config: &AuthConfig) -> Result<Auth, String> {
+    let mut tokens: Vec<ApiToken> = Vec::new();
+
+    for token in &config.tokens {
+      if tokens.iter().any(|other| other.name == token.name) {
+        return Err(format!("Token name {} is used more than once", token.name));
+      }
+
+      let hash = match (&token.token, &token.token_sha256) {
+        (Some(token), None) => hash(token),
+        (None, Some(hex)) => parse_sha256(hex)
+          .ok_or_else(|| format!("token_sha256 of {} isn't a SHA-256 in hex", token.name))?,
+        _ => {
+          return Err(format!(
+            "Token {} needs exactly one of token and token_sha256",
+            token.name
+          ))
+        }
+      };
+
+      let expires = match &token.expires {
+        Some(expires) => Some(time::parse_timestamp(expires, time::now_usec())?),
+        None => None,
+      };
+
+      tokens.push(ApiToken {
+        name: token.name.clone(),
+        user: token.user.clone(),
+        hash,
+        expires,
+        revoked: token.revoked,
+      });
+    }
+
+    let mut certificates: Vec<ApiCertificate> = Vec::new();
+    for certificate in &config.certificates {
+      if certificates
+        .iter()
+        .any(|other| other.name == certificate.name)
+        || tokens.iter().any(|token| token.name == certificate.name)
+      {
+        return Err(format!(
+          "Certificate name {} is used more than once",
+          certificate.name
+        ));
+      }
+
+      certificates.push(ApiCertificate {
+        name: certificate.name.clone(),
+        user: certificate.user.clone(),
+        fingerprint: parse_sha256(&certificate.sha256.replace(':', "")).ok_or_else(|| {
+          format!(
+            "sha256 of certificate {} isn't a SHA-256 in hex",
+            certificate.name
+          )
+        })?,
+      });
+    }
+
+    Ok(Auth {
+      enabled: config.enabled,
+      session_ttl: config.session_ttl,
+      pam_service: config.pam_service.clone(),
+      pam_allowed_users: config.pam_allowed_users.clone(),
+      pam_allowed_groups: config.pam_allowed_groups.clone(),
+      tokens,
+      certificates,
+      sessions: Mutex::new(HashMap::new()),
+      throttle: LoginThrottle::default(),
+    })
+  }
+
+  /// Identity behind a bearer token, `None` if the token is unknown, expired or revoked
+  pub fn authenticate(&self, token: &str) -> Option<Identity> {
+    let token_hash = hash(token);
+    let now = time::now_usec();
+
+    // Every static token is compared, so timing doesn't tell how much of a token matched
+    let static_token = self.tokens.iter().fold(None, |found, candidate| {
+      match constant_time_eq(&candidate.hash, &token_hash) {
+        true => Some(candidate),
+        false => found,
+      }
+    });
+    if let Some(token) = static_token {
+      return match token.revoked || token.expires.is_some_and(|expires| expires <= now) {
+        true => None,
+        false => Some(Identity {
+          name: token.name.clone(),
+          kind: IdentityKind::Token,
+          user: token.user.clone(),
+          session_id: None,
+        }),
+      };
+    }
+
+    let mut sessions = self.sessions.lock().unwrap();
+    sessions.retain(|_, session| session.expires_at > now);
+    sessions.get(&token_hash).map(|session| Identity {
+      name: session.user.clone(),
+      kind: IdentityKind::Session,
+      user: Some(session.user.clone()),
+      session_id: Some(session.id.clone()),
+    })
+  }
+
+  /// Identity behind a client certificate, `None` if it isn't in the config. The TLS handshake
+  /// already checked it against the client CA.
+  pub fn authenticate_certificate(&self, certificate: &ClientCertificate) -> Option<Identity> {
+    self
+      .certificates
+      .iter()
+      .find(|candidate| candidate.fingerprint == certificate.fingerprint)
+      .map(|certificate| Identity {
+        name: certificate.name.clone(),
+        kind: IdentityKind::Certificate,
+        user: certificate.user.clone(),
+        session_id: None,
+      })
+  }
+
+  /// Checks the password with PAM and starts a session. Blocks while PAM runs. Attempts
+  /// from the same `source` address or for the same user are throttled after a few failures.
+  pub fn login(
+    &self,
+    user: &str,
+    password: &str,
+    source: &str,
+  ) -> Result<(String, SessionDto), ApiError> {
+    let denied = || ApiError::Unauthorized("Invalid username or password".to_owned());
+
+    let keys = [format!("source {}", source), format!("user {}", user)];
+    if let Err(wait) = self.throttle.attempt(&keys) {
+      warn!(
+        "Login refused for {} from {}, too many failures",
+        user, source
+      );
+      return Err(ApiError::TooManyRequests(wait.as_secs_f64().ceil() as u64));
+    }
+
+    if !self.may_log_in(user) {
+      warn!("Login refused for {}, user isn't allowed to log in", user);
+      return Err(denied());
+    }
+    if let Err(err) = pam::authenticate(&self.pam_service, user, password) {
+      warn!("Login failed for {}: {}", user, err);
+      return Err(denied());
+    }
+
+    self.throttle.succeeded(&keys);
+    let token = random_hex(32)?;
+    let now = time::now_usec();
+    let session = Session {
+      id: random_hex(8)?,
+      user: user.to_owned(),
+      created_at: now,
+      expires_at: now.saturating_add(self.session_ttl.saturating_mul(1_000_000)),
+    };
+    let dto = session.to_dto();
+
+    info!("{} logged in from {}, session {}", user, source, session.id);
+    self.sessions.lock().unwrap().insert(hash(&token), session);
+    Ok((token, dto))
+  }
+
+  pub fn list_sessions(&self) -> Vec<SessionDto> {
+    let now = time::now_usec();
+    let mut sessions = self.sessions.lock().unwrap();
+    sessions.retain(|_, session| session.expires_at > now);
+
+    let mut list: Vec<SessionDto> = sessions.values().map(Session::to_dto).collect();
+    list.sort_by_key(|session| session.created_at);
+    list
+  }
+
+  /// Ends the session, `false` if there's no session with the id
+  pub fn revoke_session(&self, id: &str) -> bool {
+    let mut sessions = self.sessions.lock().unwrap();
+    let count = sessions.len();
+    sessions.retain(|_, session| session.id != id);
+    sessions.len() != count
+  }
+
+  fn may_log_in(&self, user: &str) -> bool {
+    if self.pam_allowed_users.iter().any(|allowed| allowed == user) {
+      return true;
+    }
+    if self.pam_allowed_groups.is_empty() {
+      return false;
+    }
+
+    let groups = user_groups(user);
+    self
+      .pam_allowed_groups
+      .iter()
+      .filter_map(|group| group_id(group))
+      .any(|gid| groups.contains(&gid))
+  }
+}
+
+fn hash(token: &str) -> [u8; 32] {
+  Sha256::digest(token.as_bytes()).into()
+}
+
+fn parse_sha256(hex: &str) -> Option<[u8; 32]> {
+  let hex = hex.trim();
+  if hex.len() != 64 || !hex.is_ascii() {
+    return None;
+  }
+
+  let mut bytes = [0u8; 32];
+  for (index, byte) in bytes.iter_mut().enumerate() {
+    *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok()?;
+  }
+  Some(bytes)
+}
+
+fn constant_time_eq(a: &[u8; 32], b: &[u8; 32]) -> bool {
+  a.iter().zip(b).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Random bytes as hex
+pub fn random_hex(bytes: usize) -> Result<String, ApiError> {
+  let mut buffer = vec![0u8; bytes];
+  File::open("/dev/urandom")?.read_exact(&mut buffer)?;
+
+  Ok(buffer.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// User id and primary group id of the user
+fn passwd_entry(name: &CString) -> Option<(libc::uid_t, libc::gid_t)> {
+  unsafe {
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+    let mut passwd: libc::passwd = std::mem::zeroed();
+    let mut result = ptr::null_mut();
+    let status = libc::getpwnam_r(
+      name.as_ptr(),
+      &mut passwd,
+      buffer.as_mut_ptr(),
+      buffer.len(),
+      &mut result,
+    );
+
+    match status == 0 && !result.is_null() {
+      true => Some((passwd.pw_uid, passwd.pw_gid)),
+      false => None,
+    }
+  }
+}
+
+pub(crate) fn user_id(user: &str) -> Option<libc::uid_t> {
+  passwd_entry(&CString::new(user).ok()?).map(|(uid, _)| uid)
+}
+
+/// Group ids the user is a member of, empty if the user doesn't exist
+pub(crate) fn user_groups(user: &str) -> Vec<libc::gid_t> {
+  let name = match CString::new(user) {
+    Ok(name) => name,
+    Err(_) => return Vec::new(),
+  };
+  let primary_group = match passwd_entry(&name) {
+    Some((_, gid)) => gid,
+    None => return Vec::new(),
+  };
+
+  let mut count: libc::c_int = 64;
+  loop {
+    let mut groups = vec![0 as libc::gid_t; count as usize];
+    let previous = count;
+    let status = unsafe {
+      libc::getgrouplist(
+        name.as_ptr(),
+        primary_group,
+        groups.as_mut_ptr(),
+        &mut count,
+      )
+    };
+    if status >= 0 {
+      groups.truncate(count as usize);
+      return groups;
+    }
+    // Too small, count now holds the number of groups, at least on glibc
+    if previous >= libc::c_int::from(u16::MAX) {
+      return Vec::new();
+    }
+    count = count.max(previous * 2);
+  }
+}
+
+pub(crate) fn group_id(group: &str) -> Option<libc::gid_t> {
+  let name = CString::new(group).ok()?;
+
+  unsafe {
+    let mut buffer = vec![0 as libc::c_char; 16 * 1024];
+    let mut entry: libc::group = std::mem::zeroed();
+    let mut result = ptr::null_mut();
+    let status = libc::getgrnam_r(
+      name.as_ptr(),
+      &mut entry,
+      buffer.as_mut_ptr(),
+      buffer.len(),
+      &mut result,
+    );
+
+    match status == 0 && !result.is_null() {
+      true => Some(entry.gr_gid),
+      false => None,
+    }
+  }
+}
@@ -0,0 +1,176 @@
+//! Password checks with PAM. libpam is loaded at runtime, so dragond builds and runs on
+//! systems without its development files.
+
+use std::{
+  ffi::{c_void, CStr, CString},
+  mem, ptr,
+  sync::OnceLock,
+};
+
+use libc::{c_char, c_int};
+
+const PAM_SUCCESS: c_int = 0;
+const PAM_BUF_ERR: c_int = 5;
+const PAM_CONV_ERR: c_int = 19;
+
+const PAM_PROMPT_ECHO_OFF: c_int = 1;
+const PAM_ERROR_MSG: c_int = 3;
+const PAM_TEXT_INFO: c_int = 4;
+
+const PAM_SILENT: c_int = 0x8000;
+const PAM_DISALLOW_NULL_AUTHTOK: c_int = 0x0001;
+
+#[repr(C)]
+struct PamMessage {
+  msg_style: c_int,
+  msg: *const c_char,
+}
+
+#[repr(C)]
+struct PamResponse {
+  resp: *mut c_char,
+  resp_retcode: c_int,
+}
+
+type Conversation =
+  extern "C" fn(c_int, *mut *const PamMessage, *mut *mut PamResponse, *mut c_void) -> c_int;
+
+#[repr(C)]
+struct PamConv {
+  conv: Conversation,
+  appdata_ptr: *mut c_void,
+}
+
+type PamStart =
+  unsafe extern "C" fn(*const c_char, *const c_char, *const PamConv, *mut *mut c_void) -> c_int;
+type PamCall = unsafe extern "C" fn(*mut c_void, c_int) -> c_int;
+type PamStrerror = unsafe extern "C" fn(*mut c_void, c_int) -> *const c_char;
+
+/// Functions of libpam, the library itself is never unloaded
+struct Library {
+  start: PamStart,
+  authenticate: PamCall,
+  acct_mgmt: PamCall,
+  end: PamCall,
+  strerror: PamStrerror,
+}
+
+static LIBRARY: OnceLock<Result<Library, String>> = OnceLock::new();
+
+fn library() -> Result<&'static Library, String> {
+  LIBRARY
+    .get_or_init(|| unsafe { load_library() })
+    .as_ref()
+    .map_err(Clone::clone)
+}
+
+unsafe fn load_library() -> Result<Library, String> {
+  let handle = libc::dlopen(c"libpam.so.0".as_ptr(), libc::RTLD_NOW);
+  if handle.is_null() {
+    return Err("Can't load libpam.so.0".to_owned());
+  }
+
+  let symbol = |name: &CStr| {
+    let symbol = libc::dlsym(handle, name.as_ptr());
+    match symbol.is_null() {
+      true => Err(format!("libpam has no {}", name.to_string_lossy())),
+      false => Ok(symbol),
+    }
+  };
+
+  Ok(Library {
+    start: mem::transmute::<*mut c_void, PamStart>(symbol(c"pam_start")?),
+    authenticate: mem::transmute::<*mut c_void, PamCall>(symbol(c"pam_authenticate")?),
+    acct_mgmt: mem::transmute::<*mut c_void, PamCall>(symbol(c"pam_acct_mgmt")?),
+    end: mem::transmute::<*mut c_void, PamCall>(symbol(c"pam_end")?),
+    strerror: mem::transmute::<*mut c_void, PamStrerror>(symbol(c"pam_strerror")?),
+  })
+}
+
+/// Answers password prompts with the password `appdata` points to. Anything else, like a
+/// prompt for a one-time code, can't be answered over the API and fails the login.
+extern "C" fn conversation(
+  count: c_int,
+  messages: *mut *const PamMessage,
+  responses: *mut *mut PamResponse,
+  appdata: *mut c_void,
+) -> c_int {
+  if count <= 0 || messages.is_null() || responses.is_null() || appdata.is_null() {
+    return PAM_CONV_ERR;
+  }
+
+  unsafe {
+    let password = &*(appdata as *const CString);
+    let count = count as usize;
+    let replies = libc::calloc(count, mem::size_of::<PamResponse>()) as *mut PamResponse;
+    if replies.is_null() {
+      return PAM_BUF_ERR;
+    }
+
+    for index in 0..count {
+      let message = &**messages.add(index);
+      match message.msg_style {
+        PAM_PROMPT_ECHO_OFF => (*replies.add(index)).resp = libc::strdup(password.as_ptr()),
+        PAM_ERROR_MSG | PAM_TEXT_INFO => {}
+        _ => {
+          free_replies(replies, count);
+          return PAM_CONV_ERR;
+        }
+      }
+    }
+
+    *responses = replies;
+  }
+
+  PAM_SUCCESS
+}
+
+unsafe fn free_replies(replies: *mut PamResponse, count: usize) {
+  for index in 0..count {
+    libc::free((*replies.add(index)).resp as *mut c_void);
+  }
+  libc::free(replies as *mut c_void);
+}
+
+/// Checks the password and whether the account may log in right now. Blocks, PAM modules
+/// delay failed attempts on purpose.
+pub fn authenticate(service: &str, user: &str, password: &str) -> Result<(), String> {
+  let library = library()?;
+  let service = CString::new(service).map_err(|_| "Invalid PAM service name".to_owned())?;
+  let user = CString::new(user).map_err(|_| "Invalid user name".to_owned())?;
+  let password = CString::new(password).map_err(|_| "Invalid password".to_owned())?;
+
+  let conv = PamConv {
+    conv: conversation,
+    appdata_ptr: &password as *const CString as *mut c_void,
+  };
+  let mut handle: *mut c_void = ptr::null_mut();
+
+  unsafe {
+    let status = (library.start)(service.as_ptr(), user.as_ptr(), &conv, &mut handle);
+    if status != PAM_SUCCESS {
+      return Err(error_message(library, handle, status));
+    }
+
+    let flags = PAM_SILENT | PAM_DISALLOW_NULL_AUTHTOK;
+    let mut status = (library.authenticate)(handle, flags);
+    if status == PAM_SUCCESS {
+      status = (library.acct_mgmt)(handle, flags);
+    }
+
+    let result = match status {
+      PAM_SUCCESS => Ok(()),
+      _ => Err(error_message(library, handle, status)),
+    };
+    (library.end)(handle, status);
+    result
+  }
+}
+
+unsafe fn error_message(library: &Library, handle: *mut c_void, status: c_int) -> String {
+  let message = (library.strerror)(handle, status);
+  match message.is_null() {
+    true => format!("PAM error {}", status),
+    false => CStr::from_ptr(message).to_string_lossy().into_owned(),
+  }
+}
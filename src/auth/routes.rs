@@ -0,0 +1,133 @@
+use crate::{
+  api_errors::ApiError,
+  auth::{
+    dto::{Identity, LoginDto, LoginRequest},
+    SESSION_COOKIE,
+  },
+  AppState,
+};
+use actix_web::{
+  cookie::{time::Duration, Cookie, SameSite},
+  delete, get,
+  http::header::ContentType,
+  post, web, HttpMessage, HttpRequest, HttpResponse, Responder,
+};
+
+/// Checks username and password with PAM and returns a session token. The token is set as
+/// a cookie too, which only event streams accept. Refused over plain TCP from other hosts, the
+/// password would cross the network readable.
+#[post("/login")]
+async fn login(
+  state: web::Data<AppState<'static>>,
+  req: HttpRequest,
+  request: web::Json<LoginRequest>,
+) -> Result<impl Responder, ApiError> {
+  let remote = req
+    .peer_addr()
+    .is_some_and(|address| !address.ip().is_loopback());
+  if remote && !req.app_config().secure() {
+    return Err(ApiError::Forbidden(
+      "Log in over TLS, a loopback address or a Unix socket".to_owned(),
+    ));
+  }
+
+  let request = request.into_inner();
+  let auth_state = state.clone();
+  let source = match req.peer_addr() {
+    Some(address) => address.ip().to_string(),
+    None => "local".to_owned(),
+  };
+
+  // PAM blocks, failed attempts usually take a few seconds on purpose
+  let (token, session) = web::block(move || {
+    auth_state
+      .auth
+      .login(&request.username, &request.password, &source)
+  })
+  .await
+  .map_err(|err| ApiError::Io(std::io::Error::other(err.to_string())))??;
+
+  let max_age = (session.expires_at - session.created_at) / 1_000_000;
+  let cookie = session_cookie(&req, token.clone())
+    .max_age(Duration::seconds(max_age as i64))
+    .finish();
+  let serialized = serde_json::to_string(&LoginDto { token, session }).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Created()
+      .append_header(ContentType::json())
+      .cookie(cookie)
+      .body(serialized),
+  )
+}
+
+fn session_cookie(req: &HttpRequest, value: String) -> actix_web::cookie::CookieBuilder<'static> {
+  Cookie::build(SESSION_COOKIE, value)
+    .path("/")
+    .http_only(true)
+    .secure(req.connection_info().scheme() == "https")
+    .same_site(SameSite::Strict)
+}
+
+/// Ends the session the request was made with
+#[post("/logout")]
+async fn logout(
+  state: web::Data<AppState<'static>>,
+  req: HttpRequest,
+) -> Result<impl Responder, ApiError> {
+  let session_id = req
+    .extensions()
+    .get::<Identity>()
+    .and_then(|identity| identity.session_id.clone());
+
+  match session_id {
+    Some(id) => {
+      state.auth.revoke_session(&id);
+      let mut cookie = session_cookie(&req, String::new()).finish();
+      cookie.make_removal();
+      Ok(HttpResponse::NoContent().cookie(cookie).finish())
+    }
+    None => Err(ApiError::Validation(
+      "Request wasn't made with a session token".to_owned(),
+    )),
+  }
+}
+
+/// Identity of the request, `null` when authentication is turned off
+#[get("/whoami")]
+async fn whoami(req: HttpRequest) -> Result<impl Responder, ApiError> {
+  let identity = req.extensions().get::<Identity>().cloned();
+
+  let serialized = serde_json::to_string(&identity).unwrap_or("null".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[get("/sessions")]
+async fn list_sessions(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let sessions = state.auth.list_sessions();
+
+  let serialized = serde_json::to_string(&sessions).unwrap_or("[]".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[delete("/sessions/{id}")]
+async fn revoke_session(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+  if !state.auth.revoke_session(&path) {
+    return Err(ApiError::NotFound(format!("No session {}", path)));
+  }
+
+  Ok(HttpResponse::NoContent().finish())
+}
@@ -0,0 +1,155 @@
+//! Slows down password guessing on `POST /auth/login`. Failed attempts are counted per client
+//! address and per user name, after a few of them each further attempt has to wait twice as
+//! long as the one before.
+
+use std::{
+  collections::HashMap,
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+/// Failed attempts allowed before the waiting starts
+const FREE_ATTEMPTS: u32 = 5;
+
+/// Wait after the first failed attempt past the free ones
+const BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait between attempts
+const MAX_DELAY: Duration = Duration::from_secs(15 * 60);
+
+/// Failed attempts are forgotten after this long without another one
+const FORGET_AFTER: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Default)]
+pub struct LoginThrottle {
+  /// Failed attempts and when the last one was made, by key
+  failures: Mutex<HashMap<String, (u32, Instant)>>,
+}
+
+impl LoginThrottle {
+  /// Counts an attempt for each key. Refused with the time left to wait if any of the keys
+  /// failed too often recently. Attempts count as failed until `succeeded`, so attempts made
+  /// in parallel are limited as well.
+  pub fn attempt(&self, keys: &[String]) -> Result<(), Duration> {
+    self.attempt_at(keys, Instant::now())
+  }
+
+  /// Forgets failed attempts of the keys
+  pub fn succeeded(&self, keys: &[String]) {
+    let mut failures = self.failures.lock().unwrap();
+    for key in keys {
+      failures.remove(key);
+    }
+  }
+
+  fn attempt_at(&self, keys: &[String], now: Instant) -> Result<(), Duration> {
+    let mut failures = self.failures.lock().unwrap();
+    failures.retain(|_, (_, last)| now.saturating_duration_since(*last) < FORGET_AFTER);
+
+    let wait = keys
+      .iter()
+      .filter_map(|key| failures.get(key))
+      .map(|(count, last)| delay(*count).saturating_sub(now.saturating_duration_since(*last)))
+      .max()
+      .unwrap_or_default();
+    if !wait.is_zero() {
+      return Err(wait);
+    }
+
+    for key in keys {
+      let (count, last) = failures.entry(key.clone()).or_insert((0, now));
+      *count = count.saturating_add(1);
+      *last = now;
+    }
+    Ok(())
+  }
+}
+
+/// How long to wait after this many failed attempts
+fn delay(failures: u32) -> Duration {
+  match failures.checked_sub(FREE_ATTEMPTS) {
+    None => Duration::ZERO,
+    Some(over) => BASE_DELAY.saturating_mul(1 << over.min(16)).min(MAX_DELAY),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn keys(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|key| key.to_string()).collect()
+  }
+
+  #[test]
+  fn allows_a_few_failed_attempts() {
+    let throttle = LoginThrottle::default();
+    let now = Instant::now();
+
+    for _ in 0..FREE_ATTEMPTS {
+      assert!(throttle.attempt_at(&keys(&["user root"]), now).is_ok());
+    }
+    assert_eq!(
+      throttle.attempt_at(&keys(&["user root"]), now),
+      Err(BASE_DELAY)
+    );
+    assert!(throttle.attempt_at(&keys(&["user admin"]), now).is_ok());
+  }
+
+  #[test]
+  fn waits_longer_after_each_failure() {
+    let throttle = LoginThrottle::default();
+    let mut now = Instant::now();
+    let key = keys(&["source 192.0.2.1"]);
+
+    for _ in 0..FREE_ATTEMPTS {
+      throttle.attempt_at(&key, now).unwrap();
+    }
+    for wait in [1, 2, 4, 8] {
+      assert_eq!(
+        throttle.attempt_at(&key, now),
+        Err(Duration::from_secs(wait))
+      );
+      now += Duration::from_secs(wait);
+      throttle.attempt_at(&key, now).unwrap();
+    }
+
+    assert_eq!(delay(u32::MAX), MAX_DELAY);
+  }
+
+  #[test]
+  fn any_key_can_refuse() {
+    let throttle = LoginThrottle::default();
+    let now = Instant::now();
+
+    for _ in 0..FREE_ATTEMPTS {
+      throttle.attempt_at(&keys(&["user root"]), now).unwrap();
+    }
+    assert!(throttle
+      .attempt_at(&keys(&["source 192.0.2.1", "user root"]), now)
+      .is_err());
+    // Refused attempts aren't counted
+    assert!(throttle
+      .attempt_at(&keys(&["source 192.0.2.1"]), now)
+      .is_ok());
+  }
+
+  #[test]
+  fn forgets_failures_after_success_or_time() {
+    let throttle = LoginThrottle::default();
+    let now = Instant::now();
+    let key = keys(&["user root"]);
+
+    for _ in 0..FREE_ATTEMPTS {
+      throttle.attempt_at(&key, now).unwrap();
+    }
+    throttle.succeeded(&key);
+    assert!(throttle.attempt_at(&key, now).is_ok());
+
+    for _ in 1..FREE_ATTEMPTS {
+      throttle.attempt_at(&key, now).unwrap();
+    }
+    assert!(throttle.attempt_at(&key, now).is_err());
+    assert!(throttle.attempt_at(&key, now + FORGET_AFTER).is_ok());
+  }
+}
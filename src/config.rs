@@ -0,0 +1,556 @@
+//! dragond configuration, read from a TOML file at startup
+
+use std::{fs, io::ErrorKind};
+
+/// Read when `DRAGOND_CONFIG` isn't set. Unlike a file given explicitly, it may be missing.
+pub const DEFAULT_CONFIG_PATH: &str = "/etc/dragond/config.toml";
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+  pub server: ServerConfig,
+  pub auth: AuthConfig,
+  pub policy: PolicyConfig,
+  pub polkit: PolkitConfig,
+  pub audit: AuditConfig,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+  /// `host:port` or `unix:/path`. Ignored when systemd passes sockets with `LISTEN_FDS`. Only
+  /// loopback by default, other clients should connect over TLS.
+  pub bind: Vec<String>,
+
+  /// Permissions of Unix sockets dragond creates
+  pub socket_mode: u32,
+
+  /// Serves TCP listeners over TLS, Unix sockets stay plain
+  pub tls: Option<TlsConfig>,
+}
+
+impl Default for ServerConfig {
+  fn default() -> Self {
+    Self {
+      bind: vec!["127.0.0.1:4444".to_owned()],
+      socket_mode: 0o660,
+      tls: None,
+    }
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TlsConfig {
+  /// PEM certificate chain, server certificate first
+  pub certificate: String,
+
+  /// PEM private key
+  pub key: String,
+
+  /// Seconds between checks whether the certificate or key changed, 0 turns reloading off
+  #[serde(default = "default_tls_reload_interval")]
+  pub reload_interval: u64,
+
+  /// PEM CA certificates client certificates are checked against. Without it, clients aren't
+  /// asked for certificates.
+  pub client_ca: Option<String>,
+
+  #[serde(default)]
+  pub client_auth: ClientAuth,
+}
+
+fn default_tls_reload_interval() -> u64 {
+  60
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ClientAuth {
+  /// Connections without a valid client certificate are refused
+  #[default]
+  Required,
+
+  /// Clients may connect without a certificate, and authenticate with a token instead
+  Optional,
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuthConfig {
+  /// Turning it off lets anyone who can reach dragond control the machine
+  pub enabled: bool,
+
+  /// Seconds a session from PAM login stays valid
+  pub session_ttl: u64,
+
+  /// PAM service used for login, see /etc/pam.d
+  pub pam_service: String,
+
+  /// Users allowed to log in with PAM, besides members of `pam_allowed_groups`
+  pub pam_allowed_users: Vec<String>,
+  pub pam_allowed_groups: Vec<String>,
+
+  /// Static API tokens, i.e. for automation
+  pub tokens: Vec<TokenConfig>,
+
+  /// TLS client certificates requests may authenticate with instead of a token
+  pub certificates: Vec<CertificateConfig>,
+}
+
+impl Default for AuthConfig {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      session_ttl: 8 * 60 * 60,
+      pam_service: "login".to_owned(),
+      pam_allowed_users: vec!["root".to_owned()],
+      pam_allowed_groups: Vec::new(),
+      tokens: Vec::new(),
+      certificates: Vec::new(),
+    }
+  }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenConfig {
+  /// Shown as the identity of requests using the token
+  pub name: String,
+
+  /// Unix account the token acts as, for policy `unix_groups` and polkit checks
+  pub user: Option<String>,
+
+  /// The token itself. Prefer `token_sha256`, so the config doesn't hold the secret.
+  pub token: Option<String>,
+
+  /// Hex SHA-256 of the token, i.e. from `printf %s "$TOKEN" | sha256sum`
+  pub token_sha256: Option<String>,
+
+  /// When the token stops working, any timestamp journalctl's `--since` takes
+  pub expires: Option<String>,
+
+  /// Keeps the token in the config but refuses it
+  #[serde(default)]
+  pub revoked: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CertificateConfig {
+  /// Shown as the identity of requests using the certificate
+  pub name: String,
+
+  /// SHA-256 fingerprint of the certificate, i.e. from
+  /// `openssl x509 -noout -fingerprint -sha256`. Colons are optional.
+  pub sha256: String,
+
+  /// Unix account the certificate acts as, for policy `unix_groups` and polkit checks
+  pub user: Option<String>,
+}
+
+/// Who may do what. Without it, every authenticated request is allowed.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PolicyConfig {
+  pub enabled: bool,
+  pub groups: Vec<PolicyGroupConfig>,
+  pub rules: Vec<PolicyRuleConfig>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyGroupConfig {
+  pub name: String,
+
+  /// Token names and user names
+  #[serde(default)]
+  pub members: Vec<String>,
+
+  /// Identities whose Unix account is in one of these groups are members too
+  #[serde(default)]
+  pub unix_groups: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyEffect {
+  #[default]
+  Allow,
+  Deny,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PolicyRuleConfig {
+  #[serde(default)]
+  pub effect: PolicyEffect,
+
+  /// Group names, `*` for everyone authenticated
+  pub groups: Vec<String>,
+
+  /// Action globs, i.e. `unit.restart` or `unit.*`
+  pub actions: Vec<String>,
+
+  /// Unit name globs. Allow rules with units only match requests naming units, deny rules also
+  /// match requests whose units aren't known or which can reach any unit.
+  pub units: Option<Vec<String>>,
+}
+
+/// Asking polkit whether the user behind a request may do what it asks for, so the host's polkit
+/// rules apply to the panel like they do to systemctl. Needs dragond to run as root.
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct PolkitConfig {
+  pub enabled: bool,
+}
+
+/// Records of state-changing requests
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AuditConfig {
+  pub enabled: bool,
+
+  /// Writes records to the journal, where `GET /audit` reads them from
+  pub journal: bool,
+
+  /// Also writes records as JSON lines to this file. Unlike the journal, other local users
+  /// can't add records to it.
+  pub file: Option<String>,
+
+  /// Bytes after which the file is rotated
+  pub file_max_size: u64,
+
+  /// Rotated files kept besides the current one, as `file.1` (newest) to `file.N`
+  pub file_keep: usize,
+}
+
+impl Default for AuditConfig {
+  fn default() -> Self {
+    Self {
+      enabled: true,
+      journal: true,
+      file: None,
+      file_max_size: 10 * 1024 * 1024,
+      file_keep: 5,
+    }
+  }
+}
+
+/// Settings given as environment variables or command line options, which take precedence over
+/// the config file. Command line options win over environment variables.
+#[derive(Default)]
+pub struct Overrides {
+  pub config: Option<String>,
+  pub bind: Vec<String>,
+  pub tls_certificate: Option<String>,
+  pub tls_key: Option<String>,
+  pub tls_client_ca: Option<String>,
+}
+
+pub const USAGE: &str = "Usage: dragond [options]
+
+Options:
+  --config <path>         Config file, default /etc/dragond/config.toml (DRAGOND_CONFIG)
+  --bind <address>        host:port or unix:/path, may be repeated (DRAGOND_BIND, comma separated)
+  --tls-cert <path>       PEM certificate chain (DRAGOND_TLS_CERT)
+  --tls-key <path>        PEM private key (DRAGOND_TLS_KEY)
+  --tls-client-ca <path>  PEM CA certificates for client certificates (DRAGOND_TLS_CLIENT_CA)
+  --help                  Show this help";
+
+impl Overrides {
+  pub fn from_env() -> Overrides {
+    let var = |name| {
+      std::env::var(name)
+        .ok()
+        .filter(|value: &String| !value.is_empty())
+    };
+
+    Overrides {
+      config: var("DRAGOND_CONFIG"),
+      bind: var("DRAGOND_BIND")
+        .map(|bind| {
+          bind
+            .split(',')
+            .map(str::trim)
+            .filter(|address| !address.is_empty())
+            .map(str::to_owned)
+            .collect()
+        })
+        .unwrap_or_default(),
+      tls_certificate: var("DRAGOND_TLS_CERT"),
+      tls_key: var("DRAGOND_TLS_KEY"),
+      tls_client_ca: var("DRAGOND_TLS_CLIENT_CA"),
+    }
+  }
+
+  /// Applies command line options on top, `--bind` replaces addresses from the environment
+  pub fn parse_args(mut self, args: impl IntoIterator<Item = String>) -> Result<Overrides, String> {
+    let mut args = args.into_iter();
+    let mut bind = Vec::new();
+
+    while let Some(arg) = args.next() {
+      let (option, inline_value) = match arg.split_once('=') {
+        Some((option, value)) => (option.to_owned(), Some(value.to_owned())),
+        None => (arg, None),
+      };
+      let mut value = || {
+        inline_value
+          .clone()
+          .or_else(|| args.next())
+          .ok_or_else(|| format!("{} needs a value", option))
+      };
+
+      match option.as_str() {
+        "--config" => self.config = Some(value()?),
+        "--bind" => bind.push(value()?),
+        "--tls-cert" => self.tls_certificate = Some(value()?),
+        "--tls-key" => self.tls_key = Some(value()?),
+        "--tls-client-ca" => self.tls_client_ca = Some(value()?),
+        _ => return Err(format!("Unknown option {}, see --help", option)),
+      }
+    }
+
+    if !bind.is_empty() {
+      self.bind = bind;
+    }
+    Ok(self)
+  }
+}
+
+impl Config {
+  /// Reads the config file named by the overrides, then applies the rest of them
+  pub fn load_with_overrides(overrides: Overrides) -> Result<Config, String> {
+    let mut config = Config::load(overrides.config.as_deref())?;
+
+    if !overrides.bind.is_empty() {
+      config.server.bind = overrides.bind;
+    }
+
+    match (
+      &mut config.server.tls,
+      overrides.tls_certificate,
+      overrides.tls_key,
+    ) {
+      (Some(tls), certificate, key) => {
+        if let Some(certificate) = certificate {
+          tls.certificate = certificate;
+        }
+        if let Some(key) = key {
+          tls.key = key;
+        }
+      }
+      (None, Some(certificate), Some(key)) => {
+        config.server.tls = Some(TlsConfig {
+          certificate,
+          key,
+          reload_interval: default_tls_reload_interval(),
+          client_ca: None,
+          client_auth: ClientAuth::default(),
+        })
+      }
+      (None, None, None) => {}
+      (None, _, _) => {
+        return Err("TLS needs both a certificate and a key".to_owned());
+      }
+    }
+
+    if let Some(client_ca) = overrides.tls_client_ca {
+      match &mut config.server.tls {
+        Some(tls) => tls.client_ca = Some(client_ca),
+        None => return Err("A client CA needs TLS, set a certificate and a key".to_owned()),
+      }
+    }
+
+    Ok(config)
+  }
+
+  /// Reads the config file. The default path may be missing, then defaults are used.
+  pub fn load(path: Option<&str>) -> Result<Config, String> {
+    let file = path.unwrap_or(DEFAULT_CONFIG_PATH);
+    let content = match fs::read_to_string(file) {
+      Ok(content) => content,
+      Err(err) if path.is_none() && err.kind() == ErrorKind::NotFound => {
+        info!("No config at {}, using defaults", file);
+        return Ok(Config::default());
+      }
+      Err(err) => return Err(format!("Can't read config {}: {}", file, err)),
+    };
+
+    toml::from_str(&content).map_err(|err| format!("Invalid config {}: {}", file, err))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+  }
+
+  /// Overrides as if read from the environment, with a config file holding `content`
+  fn with_file(name: &str, content: &str) -> (Overrides, std::path::PathBuf) {
+    let path = std::env::temp_dir().join(format!("dragond-{}-{}.toml", std::process::id(), name));
+    fs::write(&path, content).unwrap();
+    let overrides = Overrides {
+      config: Some(path.to_string_lossy().into_owned()),
+      ..Default::default()
+    };
+    (overrides, path)
+  }
+
+  const TLS_CONFIG: &str = r#"
+[server]
+bind = ["0.0.0.0:4444"]
+
+[server.tls]
+certificate = "/etc/dragond/cert.pem"
+key = "/etc/dragond/key.pem"
+"#;
+
+  #[test]
+  fn parses_options() {
+    let overrides = Overrides::default()
+      .parse_args(args(&[
+        "--config",
+        "/etc/dragond.toml",
+        "--bind=127.0.0.1:80",
+        "--bind",
+        "unix:/run/dragond.sock",
+        "--tls-cert=cert.pem",
+        "--tls-key",
+        "key.pem",
+        "--tls-client-ca",
+        "ca.pem",
+      ]))
+      .unwrap();
+
+    assert_eq!(overrides.config.as_deref(), Some("/etc/dragond.toml"));
+    assert_eq!(overrides.bind, ["127.0.0.1:80", "unix:/run/dragond.sock"]);
+    assert_eq!(overrides.tls_certificate.as_deref(), Some("cert.pem"));
+    assert_eq!(overrides.tls_key.as_deref(), Some("key.pem"));
+    assert_eq!(overrides.tls_client_ca.as_deref(), Some("ca.pem"));
+  }
+
+  #[test]
+  fn rejects_bad_options() {
+    let result = Overrides::default().parse_args(args(&["--bind"]));
+    assert_eq!(result.err().as_deref(), Some("--bind needs a value"));
+
+    let result = Overrides::default().parse_args(args(&["--port", "80"]));
+    assert_eq!(
+      result.err().as_deref(),
+      Some("Unknown option --port, see --help")
+    );
+  }
+
+  #[test]
+  fn options_win_over_environment() {
+    let env = Overrides {
+      config: Some("env.toml".to_owned()),
+      bind: vec!["0.0.0.0:1".to_owned(), "0.0.0.0:2".to_owned()],
+      tls_key: Some("env-key.pem".to_owned()),
+      ..Default::default()
+    };
+
+    let overrides = env
+      .parse_args(args(&["--bind", "127.0.0.1:3", "--config=cli.toml"]))
+      .unwrap();
+    assert_eq!(overrides.config.as_deref(), Some("cli.toml"));
+    assert_eq!(overrides.bind, ["127.0.0.1:3"]);
+    // Not given on the command line, so the environment's stays
+    assert_eq!(overrides.tls_key.as_deref(), Some("env-key.pem"));
+
+    let overrides = Overrides {
+      bind: vec!["0.0.0.0:1".to_owned()],
+      ..Default::default()
+    }
+    .parse_args(Vec::new())
+    .unwrap();
+    assert_eq!(overrides.bind, ["0.0.0.0:1"]);
+  }
+
+  #[test]
+  fn defaults_to_loopback() {
+    assert_eq!(Config::default().server.bind, ["127.0.0.1:4444"]);
+  }
+
+  #[test]
+  fn overrides_win_over_file() {
+    let (mut overrides, path) = with_file("overrides", TLS_CONFIG);
+    overrides.bind = vec!["127.0.0.1:5555".to_owned()];
+    overrides.tls_certificate = Some("/run/cert.pem".to_owned());
+    let config = Config::load_with_overrides(overrides);
+    fs::remove_file(path).unwrap();
+
+    let config = config.unwrap();
+    assert_eq!(config.server.bind, ["127.0.0.1:5555"]);
+    let tls = config.server.tls.unwrap();
+    assert_eq!(tls.certificate, "/run/cert.pem");
+    // The file's key stays when only the certificate is overridden
+    assert_eq!(tls.key, "/etc/dragond/key.pem");
+  }
+
+  #[test]
+  fn file_applies_without_overrides() {
+    let (overrides, path) = with_file("file", TLS_CONFIG);
+    let config = Config::load_with_overrides(overrides);
+    fs::remove_file(path).unwrap();
+
+    let config = config.unwrap();
+    assert_eq!(config.server.bind, ["0.0.0.0:4444"]);
+    assert_eq!(
+      config.server.tls.unwrap().certificate,
+      "/etc/dragond/cert.pem"
+    );
+  }
+
+  #[test]
+  fn tls_overrides_need_certificate_and_key() {
+    let (overrides, path) = with_file("tls-pairs", "");
+    let config_path = overrides.config.clone();
+    let load = |certificate: Option<&str>, key: Option<&str>, client_ca: Option<&str>| {
+      Config::load_with_overrides(Overrides {
+        config: config_path.clone(),
+        tls_certificate: certificate.map(str::to_owned),
+        tls_key: key.map(str::to_owned),
+        tls_client_ca: client_ca.map(str::to_owned),
+        ..Default::default()
+      })
+    };
+
+    let only_certificate = load(Some("cert.pem"), None, None);
+    let only_key = load(None, Some("key.pem"), None);
+    let only_client_ca = load(None, None, Some("ca.pem"));
+    let both = load(Some("cert.pem"), Some("key.pem"), Some("ca.pem"));
+    fs::remove_file(path).unwrap();
+
+    assert_eq!(
+      only_certificate.err().as_deref(),
+      Some("TLS needs both a certificate and a key")
+    );
+    assert_eq!(
+      only_key.err().as_deref(),
+      Some("TLS needs both a certificate and a key")
+    );
+    assert_eq!(
+      only_client_ca.err().as_deref(),
+      Some("A client CA needs TLS, set a certificate and a key")
+    );
+
+    let tls = both.unwrap().server.tls.unwrap();
+    assert_eq!(
+      (tls.certificate.as_str(), tls.key.as_str()),
+      ("cert.pem", "key.pem")
+    );
+    assert_eq!(tls.client_ca.as_deref(), Some("ca.pem"));
+    assert_eq!(tls.reload_interval, 60);
+  }
+
+  #[test]
+  fn explicit_config_must_exist() {
+    let result = Config::load(Some("/nonexistent/dragond.toml"));
+    assert!(result.err().unwrap().starts_with("Can't read config"));
+  }
+}
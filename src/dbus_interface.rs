@@ -1,12 +1,25 @@
-use crate::systemd::dbus::{
-  manager::OrgFreedesktopSystemd1Manager, service::OrgFreedesktopSystemd1Service,
-  unit::OrgFreedesktopSystemd1Unit,
+use crate::{
+  polkit::dbus::authority::OrgFreedesktopPolicyKit1Authority,
+  power::dbus::login1::OrgFreedesktopLogin1Manager,
+  systemd::dbus::{
+    automount::OrgFreedesktopSystemd1Automount, device::OrgFreedesktopSystemd1Device,
+    job::OrgFreedesktopSystemd1Job, manager::OrgFreedesktopSystemd1Manager,
+    mount::OrgFreedesktopSystemd1Mount, path::OrgFreedesktopSystemd1Path,
+    scope::OrgFreedesktopSystemd1Scope, service::OrgFreedesktopSystemd1Service,
+    slice::OrgFreedesktopSystemd1Slice, socket::OrgFreedesktopSystemd1Socket,
+    swap::OrgFreedesktopSystemd1Swap, timer::OrgFreedesktopSystemd1Timer,
+    unit::OrgFreedesktopSystemd1Unit,
+  },
 };
 use dbus::blocking::{Connection, Proxy};
-use std::{ops::Deref, time::Duration};
+use std::time::Duration;
 
-static SYSTEMD_DESTINATION: &str = "org.freedesktop.systemd1";
-static SYSTEMD_MANAGER_PATH: &str = "/org/freedesktop/systemd1";
+pub static SYSTEMD_DESTINATION: &str = "org.freedesktop.systemd1";
+pub static SYSTEMD_MANAGER_PATH: &str = "/org/freedesktop/systemd1";
+pub static LOGIN1_DESTINATION: &str = "org.freedesktop.login1";
+pub static LOGIN1_MANAGER_PATH: &str = "/org/freedesktop/login1";
+pub static POLKIT_DESTINATION: &str = "org.freedesktop.PolicyKit1";
+pub static POLKIT_AUTHORITY_PATH: &str = "/org/freedesktop/PolicyKit1/Authority";
 
 pub struct DBusInterface<'a> {
   _connection: Box<Connection>,
@@ -32,10 +45,10 @@ impl<'b> DBusInterface<'b> {
 
     // And I put connection to struct, so it won't get deleted from memory
     // that would lead to my favourite "Segmentation fault (core dumped)"
-    return DBusInterface {
+    DBusInterface {
       _connection: conn,
       systemd_manager: systemd_proxy,
-    };
+    }
   }
 
   pub fn systemd_manager(&self) -> &(impl OrgFreedesktopSystemd1Manager + 'b) {
@@ -43,18 +56,18 @@ impl<'b> DBusInterface<'b> {
   }
 
   fn create_proxy<'a>(
-    connection: &Box<Connection>,
+    connection: &Connection,
     dest: &'a str,
     path: &'a str,
     timeout: Duration,
   ) -> Proxy<'a, &'a Connection> {
     let proxy = unsafe {
-      // I can take a pointer to Connection after dereferencing Box
-      let conn_ptr: *const Connection = connection.deref();
+      // I can take a pointer to Connection the Box points to
+      let conn_ptr: *const Connection = connection;
       // Then I create proxy with it, which borrows connection
       (*conn_ptr).with_proxy(dest, path, timeout)
     };
-    return proxy;
+    proxy
   }
 
   pub fn systemd_proxy_for_path(&self, path: &'b str) -> Proxy<'b, &'b Connection> {
@@ -67,10 +80,70 @@ impl<'b> DBusInterface<'b> {
   }
 
   pub fn systemd_unit(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Unit + 'b {
-    self.systemd_proxy_for_path(&path)
+    self.systemd_proxy_for_path(path)
   }
 
   pub fn systemd_service(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Service + 'b {
-    self.systemd_proxy_for_path(&path)
+    self.systemd_proxy_for_path(path)
+  }
+
+  pub fn systemd_timer(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Timer + 'b {
+    self.systemd_proxy_for_path(path)
+  }
+
+  pub fn systemd_socket(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Socket + 'b {
+    self.systemd_proxy_for_path(path)
+  }
+
+  pub fn systemd_mount(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Mount + 'b {
+    self.systemd_proxy_for_path(path)
+  }
+
+  pub fn systemd_automount(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Automount + 'b {
+    self.systemd_proxy_for_path(path)
+  }
+
+  pub fn systemd_path(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Path + 'b {
+    self.systemd_proxy_for_path(path)
+  }
+
+  pub fn systemd_swap(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Swap + 'b {
+    self.systemd_proxy_for_path(path)
+  }
+
+  pub fn systemd_scope(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Scope + 'b {
+    self.systemd_proxy_for_path(path)
+  }
+
+  pub fn systemd_slice(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Slice + 'b {
+    self.systemd_proxy_for_path(path)
+  }
+
+  pub fn systemd_device(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Device + 'b {
+    self.systemd_proxy_for_path(path)
+  }
+
+  pub fn systemd_job(&self, path: &'b str) -> impl OrgFreedesktopSystemd1Job + 'b {
+    self.systemd_proxy_for_path(path)
+  }
+
+  /// systemd-logind, which handles sleep and scheduled shutdowns
+  pub fn login1_manager(&self) -> impl OrgFreedesktopLogin1Manager + 'b {
+    Self::create_proxy(
+      &self._connection,
+      LOGIN1_DESTINATION,
+      LOGIN1_MANAGER_PATH,
+      Duration::from_secs(5),
+    )
+  }
+
+  /// polkit, which decides whether users may do privileged things
+  pub fn polkit_authority(&self) -> impl OrgFreedesktopPolicyKit1Authority + 'b {
+    Self::create_proxy(
+      &self._connection,
+      POLKIT_DESTINATION,
+      POLKIT_AUTHORITY_PATH,
+      Duration::from_secs(5),
+    )
   }
 }
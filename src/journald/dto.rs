@@ -0,0 +1,103 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::{
+  source::{hex, JournalEntry},
+  time::format_rfc3339,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalEntryDto {
+  /// Pass it as `cursor` or `after_cursor` to continue reading from this entry
+  pub cursor: String,
+
+  /// Wall clock time the entry was written at, in microseconds since the epoch
+  pub realtime_timestamp: u64,
+
+  /// Same as realtimeTimestamp, as RFC 3339 in UTC
+  pub timestamp: String,
+
+  /// Microseconds since the boot of bootId
+  pub monotonic_timestamp: u64,
+  pub boot_id: String,
+
+  /// Syslog priority, 0 (emerg) to 7 (debug)
+  pub priority: Option<u8>,
+
+  /// Message text, binary messages are decoded with invalid UTF-8 sequences replaced
+  pub message: Option<String>,
+
+  /// Unit which wrote the entry
+  pub unit: Option<String>,
+  pub identifier: Option<String>,
+  pub pid: Option<u32>,
+  pub hostname: Option<String>,
+
+  /// All fields of the entry. Each field is a list, since a field can be present more than
+  /// once. Values are decoded the same way as message.
+  pub fields: BTreeMap<String, Vec<String>>,
+}
+
+impl From<&JournalEntry> for JournalEntryDto {
+  fn from(entry: &JournalEntry) -> Self {
+    let text = |name: &str| {
+      entry
+        .field(name)
+        .map(|value| String::from_utf8_lossy(value).into_owned())
+    };
+
+    let mut fields: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (name, value) in &entry.fields {
+      fields
+        .entry(name.to_owned())
+        .or_default()
+        .push(String::from_utf8_lossy(value).into_owned());
+    }
+
+    JournalEntryDto {
+      cursor: entry.cursor(),
+      realtime_timestamp: entry.key.realtime,
+      timestamp: format_rfc3339(entry.key.realtime),
+      monotonic_timestamp: entry.key.monotonic,
+      boot_id: hex(&entry.key.boot_id),
+      priority: text("PRIORITY").and_then(|priority| priority.parse().ok()),
+      message: text("MESSAGE"),
+      unit: text("_SYSTEMD_UNIT"),
+      identifier: text("SYSLOG_IDENTIFIER"),
+      pid: text("_PID").and_then(|pid| pid.parse().ok()),
+      hostname: text("_HOSTNAME"),
+      fields,
+    }
+  }
+}
+
+/// One page of entries. Entries are in the order of the query direction.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JournalPageDto {
+  pub entries: Vec<JournalEntryDto>,
+
+  /// Cursor of the last entry, read the next page by passing it as `after_cursor` with the
+  /// same direction
+  pub next_cursor: Option<String>,
+
+  /// Cursor of the first entry, read the previous page by passing it as `after_cursor` with
+  /// the opposite direction
+  pub prev_cursor: Option<String>,
+
+  /// Whether there were more entries than the limit allowed
+  pub has_more: bool,
+}
+
+impl JournalPageDto {
+  pub fn new(entries: Vec<JournalEntryDto>, has_more: bool) -> Self {
+    JournalPageDto {
+      next_cursor: entries.last().map(|entry| entry.cursor.clone()),
+      prev_cursor: entries.first().map(|entry| entry.cursor.clone()),
+      entries,
+      has_more,
+    }
+  }
+}
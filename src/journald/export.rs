@@ -0,0 +1,441 @@
+use std::{io::Write, sync::Arc};
+
+use actix_web::{
+  http::header::{self, ContentDisposition, DispositionParam, DispositionType},
+  web::Bytes,
+  HttpResponse,
+};
+use flate2::{write::GzEncoder, Compression};
+use tokio::sync::mpsc;
+
+use super::{
+  dto::JournalEntryDto,
+  source::{hex, JournalEntry, JournalQuery, JournalSource},
+  time::format_rfc3339,
+};
+use crate::sse::ReceiverStream;
+
+/// Entries read from the journal at once, each batch is formatted and sent before the next
+const EXPORT_BATCH_SIZE: usize = 1000;
+
+/// How many formatted batches can wait for a slow client
+const CHANNEL_CAPACITY: usize = 4;
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExportFormat {
+  /// One JSON entry per line, same entries `/journald/logs` returns
+  #[default]
+  Json,
+
+  /// Journal Export Format, https://systemd.io/JOURNAL_EXPORT_FORMATS/
+  Export,
+
+  /// Text like `journalctl --output short-iso` prints, with time in UTC
+  ShortIso,
+
+  Csv,
+}
+
+impl ExportFormat {
+  fn content_type(&self) -> &'static str {
+    match self {
+      ExportFormat::Json => "application/x-ndjson",
+      ExportFormat::Export => "application/vnd.fdo.journal",
+      ExportFormat::ShortIso => "text/plain; charset=utf-8",
+      ExportFormat::Csv => "text/csv; charset=utf-8",
+    }
+  }
+
+  fn extension(&self) -> &'static str {
+    match self {
+      ExportFormat::Json => "ndjson",
+      ExportFormat::Export => "journal-export",
+      ExportFormat::ShortIso => "log",
+      ExportFormat::Csv => "csv",
+    }
+  }
+
+  /// Written once before all entries
+  fn header(&self) -> &'static [u8] {
+    match self {
+      ExportFormat::Csv => b"timestamp,hostname,unit,identifier,pid,priority,message,cursor\r\n",
+      _ => b"",
+    }
+  }
+
+  fn write_entry(&self, entry: &JournalEntry, out: &mut Vec<u8>) {
+    match self {
+      ExportFormat::Json => write_json(entry, out),
+      ExportFormat::Export => write_export(entry, out),
+      ExportFormat::ShortIso => write_short_iso(entry, out),
+      ExportFormat::Csv => write_csv(entry, out),
+    }
+  }
+}
+
+/// Creates a response streaming all entries matching the query as a downloaded file named
+/// after `name`
+pub fn export_response(
+  source: Arc<dyn JournalSource + Send + Sync>,
+  query: JournalQuery,
+  format: ExportFormat,
+  gzip: bool,
+  name: &str,
+) -> HttpResponse {
+  let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+  actix_web::rt::spawn(stream_export(source, query, format, gzip, sender));
+
+  let mut filename = format!("{}.{}", name, format.extension());
+  let content_type = match gzip {
+    true => {
+      filename.push_str(".gz");
+      "application/gzip"
+    }
+    false => format.content_type(),
+  };
+
+  HttpResponse::Ok()
+    .insert_header((header::CONTENT_TYPE, content_type))
+    .insert_header(ContentDisposition {
+      disposition: DispositionType::Attachment,
+      parameters: vec![DispositionParam::Filename(filename)],
+    })
+    .streaming(ReceiverStream::new(receiver))
+}
+
+/// Reads the journal batch by batch and pushes formatted entries into the response. Stops
+/// early when the client disconnects.
+async fn stream_export(
+  source: Arc<dyn JournalSource + Send + Sync>,
+  query: JournalQuery,
+  format: ExportFormat,
+  gzip: bool,
+  sender: mpsc::Sender<Bytes>,
+) {
+  let mut output = Output::new(gzip);
+  output.write(format.header());
+
+  let mut remaining = query.limit.unwrap_or(usize::MAX);
+  let mut batch_query = query.clone();
+
+  while remaining > 0 {
+    batch_query.limit = Some(remaining.min(EXPORT_BATCH_SIZE));
+
+    let batch = batch_query.clone();
+    let source = source.clone();
+    let entries = match actix_web::web::block(move || source.read(&batch)).await {
+      Ok(Ok(entries)) => entries,
+      Ok(Err(err)) => {
+        warn!("Journal export stopped: {}", err);
+        break;
+      }
+      Err(err) => {
+        warn!("Journal export stopped: {}", err);
+        break;
+      }
+    };
+
+    let mut formatted = Vec::new();
+    for entry in &entries {
+      format.write_entry(entry, &mut formatted);
+    }
+    output.write(&formatted);
+
+    if sender.send(output.take()).await.is_err() {
+      return;
+    }
+
+    remaining -= entries.len();
+    match entries.last() {
+      Some(last) if entries.len() == EXPORT_BATCH_SIZE => {
+        batch_query.cursor = Some(last.key);
+        batch_query.exclude_cursor = true;
+      }
+      _ => break,
+    }
+  }
+
+  let _ = sender.send(output.finish()).await;
+}
+
+/// Plain or gzip compressed response body
+enum Output {
+  Plain(Vec<u8>),
+  Gzip(GzEncoder<Vec<u8>>),
+}
+
+impl Output {
+  fn new(gzip: bool) -> Self {
+    match gzip {
+      true => Output::Gzip(GzEncoder::new(Vec::new(), Compression::default())),
+      false => Output::Plain(Vec::new()),
+    }
+  }
+
+  fn write(&mut self, data: &[u8]) {
+    match self {
+      Output::Plain(buffer) => buffer.extend_from_slice(data),
+      Output::Gzip(encoder) => {
+        // Writing into a Vec can't fail
+        let _ = encoder.write_all(data);
+      }
+    }
+  }
+
+  /// Takes what's ready to be sent so far
+  fn take(&mut self) -> Bytes {
+    match self {
+      Output::Plain(buffer) => Bytes::from(std::mem::take(buffer)),
+      Output::Gzip(encoder) => Bytes::from(std::mem::take(encoder.get_mut())),
+    }
+  }
+
+  fn finish(self) -> Bytes {
+    match self {
+      Output::Plain(buffer) => Bytes::from(buffer),
+      Output::Gzip(encoder) => Bytes::from(encoder.finish().unwrap_or_default()),
+    }
+  }
+}
+
+fn write_json(entry: &JournalEntry, out: &mut Vec<u8>) {
+  if serde_json::to_writer(&mut *out, &JournalEntryDto::from(entry)).is_ok() {
+    out.push(b'\n');
+  }
+}
+
+/// Fields are `NAME=value` lines, except values which aren't printable text or contain a new
+/// line. Those are written as the name, a new line, 64-bit little endian size and raw value.
+fn write_export(entry: &JournalEntry, out: &mut Vec<u8>) {
+  let metadata = [
+    ("__CURSOR", entry.cursor()),
+    ("__REALTIME_TIMESTAMP", entry.key.realtime.to_string()),
+    ("__MONOTONIC_TIMESTAMP", entry.key.monotonic.to_string()),
+    ("_BOOT_ID", hex(&entry.key.boot_id)),
+  ];
+  for (name, value) in metadata {
+    out.extend_from_slice(format!("{}={}\n", name, value).as_bytes());
+  }
+
+  // Boot id is already written from the entry header
+  for (name, value) in entry.fields.iter().filter(|(name, _)| name != "_BOOT_ID") {
+    out.extend_from_slice(name.as_bytes());
+
+    match is_printable(value) && !value.contains(&b'\n') {
+      true => {
+        out.push(b'=');
+        out.extend_from_slice(value);
+      }
+      false => {
+        out.push(b'\n');
+        out.extend_from_slice(&(value.len() as u64).to_le_bytes());
+        out.extend_from_slice(value);
+      }
+    }
+    out.push(b'\n');
+  }
+
+  out.push(b'\n');
+}
+
+/// `2023-01-17T12:30:15+0000 host identifier[pid]: message`, following lines of multiline
+/// messages are indented to the start of the message
+fn write_short_iso(entry: &JournalEntry, out: &mut Vec<u8>) {
+  let timestamp = format_rfc3339(entry_timestamp(entry));
+  let mut prefix = format!("{}+0000", &timestamp[..19]);
+
+  if let Some(hostname) = text(entry, "_HOSTNAME") {
+    prefix.push(' ');
+    prefix.push_str(&hostname);
+  }
+
+  let identifier = text(entry, "SYSLOG_IDENTIFIER").or_else(|| text(entry, "_COMM"));
+  let pid = text(entry, "SYSLOG_PID").or_else(|| text(entry, "_PID"));
+  match (identifier, pid) {
+    (Some(identifier), Some(pid)) => prefix.push_str(&format!(" {}[{}]:", identifier, pid)),
+    (Some(identifier), None) => prefix.push_str(&format!(" {}:", identifier)),
+    (None, _) => prefix.push(':'),
+  }
+
+  let message = match entry.field("MESSAGE") {
+    Some(message) if is_printable(message) => String::from_utf8_lossy(message).into_owned(),
+    Some(message) => format!("[{}B blob data]", message.len()),
+    None => String::new(),
+  };
+
+  let indent = " ".repeat(prefix.len() + 1);
+  let mut lines = message.lines();
+  out.extend_from_slice(format!("{} {}\n", prefix, lines.next().unwrap_or_default()).as_bytes());
+  for line in lines {
+    out.extend_from_slice(format!("{}{}\n", indent, line).as_bytes());
+  }
+}
+
+fn write_csv(entry: &JournalEntry, out: &mut Vec<u8>) {
+  let columns = [
+    format_rfc3339(entry_timestamp(entry)),
+    text(entry, "_HOSTNAME").unwrap_or_default(),
+    text(entry, "_SYSTEMD_UNIT").unwrap_or_default(),
+    text(entry, "SYSLOG_IDENTIFIER").unwrap_or_default(),
+    text(entry, "_PID").unwrap_or_default(),
+    text(entry, "PRIORITY").unwrap_or_default(),
+    text(entry, "MESSAGE").unwrap_or_default(),
+    entry.cursor(),
+  ];
+
+  let row: Vec<String> = columns.iter().map(|column| csv_escape(column)).collect();
+  out.extend_from_slice(row.join(",").as_bytes());
+  out.extend_from_slice(b"\r\n");
+}
+
+/// Quotes values with separators, quotes or line breaks, as RFC 4180 says
+fn csv_escape(value: &str) -> String {
+  match value.contains([',', '"', '\r', '\n']) {
+    true => format!("\"{}\"", value.replace('"', "\"\"")),
+    false => value.to_owned(),
+  }
+}
+
+/// Time the message was logged at by its sender, if it says so, or written by journald
+fn entry_timestamp(entry: &JournalEntry) -> u64 {
+  text(entry, "_SOURCE_REALTIME_TIMESTAMP")
+    .and_then(|timestamp| timestamp.parse().ok())
+    .unwrap_or(entry.key.realtime)
+}
+
+fn text(entry: &JournalEntry, name: &str) -> Option<String> {
+  entry
+    .field(name)
+    .map(|value| String::from_utf8_lossy(value).into_owned())
+}
+
+/// Valid UTF-8 without control characters other than new lines and tabs
+fn is_printable(value: &[u8]) -> bool {
+  match std::str::from_utf8(value) {
+    Ok(text) => text
+      .chars()
+      .all(|c| !c.is_control() || c == '\n' || c == '\t'),
+    Err(_) => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::journald::journal_file::EntryKey;
+
+  /// 2023-01-17T12:30:15Z
+  const REALTIME: u64 = 1_673_958_615_000_000;
+
+  fn entry(fields: &[(&str, &[u8])]) -> JournalEntry {
+    JournalEntry {
+      key: EntryKey {
+        seqnum_id: [1; 16],
+        seqnum: 7,
+        boot_id: [2; 16],
+        monotonic: 5,
+        realtime: REALTIME,
+        xor_hash: 0,
+      },
+      fields: fields
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_vec()))
+        .collect(),
+    }
+  }
+
+  fn written(format: ExportFormat, entry: &JournalEntry) -> Vec<u8> {
+    let mut out = Vec::new();
+    format.write_entry(entry, &mut out);
+    out
+  }
+
+  #[test]
+  fn frames_export_fields() {
+    let entry = entry(&[
+      ("_BOOT_ID", b"02020202020202020202020202020202"),
+      ("MESSAGE", b"hello"),
+      ("MULTILINE", b"a\nb"),
+      ("BINARY", b"\x00\x01"),
+    ]);
+
+    let mut expected = format!(
+      "__CURSOR={}\n__REALTIME_TIMESTAMP={}\n__MONOTONIC_TIMESTAMP=5\n_BOOT_ID={}\n",
+      entry.cursor(),
+      REALTIME,
+      "02".repeat(16)
+    )
+    .into_bytes();
+    expected.extend_from_slice(b"MESSAGE=hello\n");
+    expected.extend_from_slice(b"MULTILINE\n");
+    expected.extend_from_slice(&3u64.to_le_bytes());
+    expected.extend_from_slice(b"a\nb\n");
+    expected.extend_from_slice(b"BINARY\n");
+    expected.extend_from_slice(&2u64.to_le_bytes());
+    expected.extend_from_slice(b"\x00\x01\n");
+    expected.push(b'\n');
+
+    assert_eq!(written(ExportFormat::Export, &entry), expected);
+  }
+
+  #[test]
+  fn formats_short_iso() {
+    let entry = entry(&[
+      ("_HOSTNAME", b"host"),
+      ("SYSLOG_IDENTIFIER", b"app"),
+      ("_PID", b"42"),
+      ("MESSAGE", b"first\nsecond"),
+    ]);
+    let prefix = "2023-01-17T12:30:15+0000 host app[42]:";
+    assert_eq!(
+      String::from_utf8(written(ExportFormat::ShortIso, &entry)).unwrap(),
+      format!("{} first\n{}second\n", prefix, " ".repeat(prefix.len() + 1))
+    );
+  }
+
+  #[test]
+  fn short_iso_falls_back_on_missing_fields() {
+    // The sender's timestamp wins over journald's
+    let entry = entry(&[
+      ("_SOURCE_REALTIME_TIMESTAMP", b"1673958614000000"),
+      ("_COMM", b"sshd"),
+      ("MESSAGE", b"\x00binary"),
+    ]);
+    assert_eq!(
+      written(ExportFormat::ShortIso, &entry),
+      b"2023-01-17T12:30:14+0000 sshd: [7B blob data]\n"
+    );
+
+    let entry = self::entry(&[]);
+    assert_eq!(
+      written(ExportFormat::ShortIso, &entry),
+      b"2023-01-17T12:30:15+0000: \n"
+    );
+  }
+
+  #[test]
+  fn writes_csv_rows() {
+    let entry = entry(&[
+      ("_HOSTNAME", b"host"),
+      ("_SYSTEMD_UNIT", b"app.service"),
+      ("PRIORITY", b"6"),
+      ("MESSAGE", b"say \"hi\", bob"),
+    ]);
+    assert_eq!(
+      String::from_utf8(written(ExportFormat::Csv, &entry)).unwrap(),
+      format!(
+        "2023-01-17T12:30:15.000000Z,host,app.service,,,6,\"say \"\"hi\"\", bob\",{}\r\n",
+        entry.cursor()
+      )
+    );
+  }
+
+  #[test]
+  fn escapes_csv_values() {
+    assert_eq!(csv_escape("plain"), "plain");
+    assert_eq!(csv_escape("a,b"), "\"a,b\"");
+    assert_eq!(csv_escape("a\r\nb"), "\"a\r\nb\"");
+    assert_eq!(csv_escape("\""), "\"\"\"\"");
+  }
+}
@@ -1,48 +1,267 @@
-use std::process::Command;
-use std::string::String;
+use std::{sync::Arc, time::Instant};
 
-/// Reads journal entries for specified unit. The .service suffix can be omitted.
-///
-/// If lines_num is provided, reads only that amount of entries.
-/// If cursor is provided, reads entries since that cursor.
-///
-/// In case of error when executing command, return exit code.
+use super::{
+  dto::{JournalEntryDto, JournalPageDto},
+  journal_file::EntryKey,
+  source::{Direction, JournalEntry, JournalError, JournalQuery, JournalSource},
+  time::now_usec,
+};
+use crate::sse::{self, SseSender};
+
+/// How often followed logs are checked for new entries
+const FOLLOW_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Most entries read from the journal at once while following
+const FOLLOW_BATCH_SIZE: usize = 1000;
+
+/// How many entries `journalctl --follow` shows when asked for no specific amount
+const FOLLOW_DEFAULT_LINES: usize = 10;
+
+/// Page size when the query doesn't ask for a specific one
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// Largest page read at once, bigger requests get this many entries and `has_more`
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// Unit types systemd knows, names without one of these get `.service` appended
+const UNIT_SUFFIXES: [&str; 11] = [
+  ".service",
+  ".socket",
+  ".target",
+  ".device",
+  ".mount",
+  ".automount",
+  ".swap",
+  ".timer",
+  ".path",
+  ".slice",
+  ".scope",
+];
+
+/// Coredumps are logged by systemd-coredump with this message id
+const COREDUMP_MESSAGE_ID: &str = "fc2e22bc6ee647b6b90729ab34a250b1";
+
+/// Reads one page of entries matching the query. Query limit is the page size, which
+/// defaults to `DEFAULT_PAGE_SIZE` and can't be more than `MAX_PAGE_SIZE`.
+pub fn read_page(
+  source: &dyn JournalSource,
+  query: &JournalQuery,
+) -> Result<JournalPageDto, JournalError> {
+  let page_size = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).min(MAX_PAGE_SIZE);
+
+  // One more entry than requested tells if there are more
+  let query = JournalQuery {
+    limit: Some(page_size + 1),
+    ..query.clone()
+  };
+
+  let mut entries = source.read(&query)?;
+  let has_more = entries.len() > page_size;
+  if has_more {
+    entries.pop();
+  }
+
+  Ok(JournalPageDto::new(
+    entries.iter().map(JournalEntryDto::from).collect(),
+    has_more,
+  ))
+}
+
+/// Same matches journalctl uses for `--unit`: messages of unit processes, messages systemd
+/// logged about the unit and coredumps of its processes
+pub fn unit_matches(unit_name: &str) -> Vec<Vec<(String, String)>> {
+  let unit = mangle_unit_name(unit_name);
+  let pair = |field: &str, value: &str| (field.to_owned(), value.to_owned());
+
+  vec![
+    vec![pair("_SYSTEMD_UNIT", &unit)],
+    vec![
+      pair("MESSAGE_ID", COREDUMP_MESSAGE_ID),
+      pair("COREDUMP_UNIT", &unit),
+    ],
+    vec![pair("_PID", "1"), pair("UNIT", &unit)],
+    vec![pair("_UID", "0"), pair("OBJECT_SYSTEMD_UNIT", &unit)],
+  ]
+}
+
+fn mangle_unit_name(unit_name: &str) -> String {
+  if UNIT_SUFFIXES
+    .iter()
+    .any(|suffix| unit_name.ends_with(suffix))
+  {
+    unit_name.to_owned()
+  } else {
+    format!("{}.service", unit_name)
+  }
+}
+
+/// Pushes entries matching the query to the client as `entry` events as they get written,
+/// with the entry cursor as event id. Stops once the client disconnects.
 ///
-pub fn read_lines(
-  unit_name: &String,
-  lines_num: &Option<usize>,
-  cursor: &Option<String>,
-) -> Result<String, i32> {
-  let mut command = Command::new("journalctl");
-  command.arg("--no-pager");
-  command.arg("--reverse");
-  command.args(["--output", "json"]);
-  command.args(["--unit", &unit_name]);
+/// If query has a cursor, starts with entries since that cursor, so a client can resume
+/// where it stopped. Otherwise starts with as many newest entries as the query limit says.
+/// Query direction doesn't matter, entries are always sent oldest first.
+pub async fn follow_entries(
+  source: Arc<dyn JournalSource + Send + Sync>,
+  query: JournalQuery,
+  sender: SseSender,
+) {
+  let base = query.clone();
+
+  let mut query = match query.cursor {
+    Some(_) => JournalQuery {
+      direction: Direction::Forward,
+      limit: Some(FOLLOW_BATCH_SIZE),
+      ..query
+    },
+    None => JournalQuery {
+      direction: Direction::Backward,
+      limit: Some(query.limit.unwrap_or(FOLLOW_DEFAULT_LINES)),
+      ..query
+    },
+  };
 
-  if cursor.is_some() {
-    command.args(["--cursor", cursor.as_ref().unwrap()]);
+  // Entries are followed from now on even if there were none to show at first
+  let mut last_key = query.cursor.unwrap_or_else(now_key);
+  let mut last_sent = Instant::now();
+
+  loop {
+    let direction = query.direction;
+    let limit = query.limit;
+    let entries = match read_blocking(source.clone(), query).await {
+      Some(entries) => entries,
+      None => return,
+    };
+
+    // Catching up with the journal doesn't have to wait
+    let catching_up =
+      direction == Direction::Forward && limit.is_some_and(|limit| entries.len() >= limit);
+    let entries: Vec<JournalEntry> = match direction {
+      Direction::Forward => entries,
+      Direction::Backward => entries.into_iter().rev().collect(),
+    };
+
+    for entry in &entries {
+      let data = serde_json::to_string(&JournalEntryDto::from(entry)).unwrap_or("{}".to_owned());
+      if !sender.send("entry", Some(&entry.cursor()), &data).await {
+        return;
+      }
+      last_key = entry.key;
+      last_sent = Instant::now();
+    }
+
+    if last_sent.elapsed() >= sse::KEEPALIVE_INTERVAL {
+      if !sender.keepalive().await {
+        return;
+      }
+      last_sent = Instant::now();
+    }
+
+    query = JournalQuery {
+      direction: Direction::Forward,
+      cursor: Some(last_key),
+      exclude_cursor: true,
+      limit: Some(FOLLOW_BATCH_SIZE),
+      ..base.clone()
+    };
+
+    if !catching_up {
+      tokio::time::sleep(FOLLOW_POLL_INTERVAL).await;
+    }
   }
+}
 
-  if lines_num.is_some() {
-    command.args(["--lines", &lines_num.unwrap().to_string()]);
+/// Reads the journal on a thread pool, so polling doesn't block the server
+async fn read_blocking(
+  source: Arc<dyn JournalSource + Send + Sync>,
+  query: JournalQuery,
+) -> Option<Vec<JournalEntry>> {
+  match actix_web::web::block(move || source.read(&query)).await {
+    Ok(Ok(entries)) => Some(entries),
+    Ok(Err(err)) => {
+      warn!("Failed to read followed journal: {}", err);
+      None
+    }
+    Err(err) => {
+      warn!("Failed to read followed journal: {}", err);
+      None
+    }
   }
+}
 
-  let output = command.output();
+/// Position in the journal at current time, doesn't belong to any sequence or boot so only
+/// the wall clock is used to compare it with entries
+fn now_key() -> EntryKey {
+  EntryKey {
+    seqnum_id: [0; 16],
+    seqnum: 0,
+    boot_id: [0; 16],
+    monotonic: 0,
+    realtime: now_usec(),
+    xor_hash: 0,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{cell::Cell, path::Path};
+
+  use super::*;
+  use crate::journald::source::{Boot, JournalDirectories};
 
-  //TODO in case of error, returning both status code and error msg
-  if output.is_err() {
-    let exit_code = match output.unwrap().status.code() {
-      Some(code) => code,
-      None => -1,
+  /// Remembers the limit it was asked to read with
+  #[derive(Default)]
+  struct LimitSource(Cell<Option<usize>>);
+
+  impl JournalSource for LimitSource {
+    fn read(&self, query: &JournalQuery) -> Result<Vec<JournalEntry>, JournalError> {
+      self.0.set(query.limit);
+      Ok(Vec::new())
+    }
+
+    fn boots(&self) -> Result<Vec<Boot>, JournalError> {
+      Ok(Vec::new())
+    }
+  }
+
+  fn page_limit(limit: Option<usize>) -> Option<usize> {
+    let source = LimitSource::default();
+    let query = JournalQuery {
+      limit,
+      ..Default::default()
     };
+    read_page(&source, &query).unwrap();
+    source.0.get()
+  }
 
-    return Err(exit_code);
+  #[test]
+  fn limits_page_size() {
+    assert_eq!(page_limit(None), Some(DEFAULT_PAGE_SIZE + 1));
+    assert_eq!(page_limit(Some(5)), Some(6));
+    assert_eq!(page_limit(Some(MAX_PAGE_SIZE + 1)), Some(MAX_PAGE_SIZE + 1));
+    assert_eq!(page_limit(Some(usize::MAX)), Some(MAX_PAGE_SIZE + 1));
   }
 
-  // replaces newlines with commas and adds square brackets to end and beginning
-  let mut command_stdout = String::from_utf8_lossy(&output.unwrap().stdout).to_string();
-  command_stdout = format!("[{}]", command_stdout);
-  command_stdout = command_stdout.replace("\n", ",");
+  #[test]
+  fn tells_if_there_are_more_entries() {
+    let source = JournalDirectories::new(vec![
+      Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/journal/machine")
+    ]);
+    let page = |limit| {
+      let query = JournalQuery {
+        limit,
+        ..Default::default()
+      };
+      read_page(&source, &query).unwrap()
+    };
 
-  Ok(command_stdout)
+    let first = page(Some(30));
+    assert_eq!(first.entries.len(), 30);
+    assert!(first.has_more);
+
+    let all = page(Some(31));
+    assert_eq!(all.entries.len(), 31);
+    assert!(!all.has_more);
+    assert!(!page(None).has_more);
+  }
 }
@@ -0,0 +1,850 @@
+//! Reader for the journal file format described in
+//! https://systemd.io/JOURNAL_FILE_FORMAT/
+//!
+//! Only reading is supported. Files which are being written to by journald at the same time
+//! can be read too, objects which aren't completely written yet end the iteration early.
+
+use std::{
+  cmp::Ordering,
+  fs::File,
+  io::Read,
+  os::unix::fs::FileExt,
+  path::{Path, PathBuf},
+};
+
+use super::source::JournalError;
+
+const SIGNATURE: &[u8; 8] = b"LPKSHHRH";
+
+/// Size of the header in the oldest supported format version
+const MIN_HEADER_SIZE: u64 = 208;
+
+const INCOMPATIBLE_COMPRESSED_XZ: u32 = 1 << 0;
+const INCOMPATIBLE_COMPRESSED_LZ4: u32 = 1 << 1;
+const INCOMPATIBLE_KEYED_HASH: u32 = 1 << 2;
+const INCOMPATIBLE_COMPRESSED_ZSTD: u32 = 1 << 3;
+const INCOMPATIBLE_COMPACT: u32 = 1 << 4;
+const INCOMPATIBLE_SUPPORTED: u32 = INCOMPATIBLE_COMPRESSED_XZ
+  | INCOMPATIBLE_COMPRESSED_LZ4
+  | INCOMPATIBLE_KEYED_HASH
+  | INCOMPATIBLE_COMPRESSED_ZSTD
+  | INCOMPATIBLE_COMPACT;
+
+const OBJECT_COMPRESSED_XZ: u8 = 1 << 0;
+const OBJECT_COMPRESSED_LZ4: u8 = 1 << 1;
+const OBJECT_COMPRESSED_ZSTD: u8 = 1 << 2;
+
+const OBJECT_HEADER_SIZE: u64 = 16;
+
+/// Refuse to decompress fields bigger than that, journald itself doesn't store bigger ones
+const MAX_FIELD_SIZE: usize = 768 * 1024 * 1024;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ObjectType {
+  Data = 1,
+  Field = 2,
+  Entry = 3,
+  EntryArray = 6,
+}
+
+struct Header {
+  incompatible_flags: u32,
+  seqnum_id: [u8; 16],
+  header_size: u64,
+  arena_size: u64,
+  field_hash_table_offset: u64,
+  field_hash_table_size: u64,
+  n_entries: u64,
+  entry_array_offset: u64,
+  head_entry_seqnum: u64,
+  tail_entry_seqnum: u64,
+  head_entry_realtime: u64,
+  tail_entry_realtime: u64,
+}
+
+/// Everything needed to order entries and build their cursor, available without reading
+/// any of the entry fields
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct EntryKey {
+  pub seqnum_id: [u8; 16],
+  pub seqnum: u64,
+  pub boot_id: [u8; 16],
+  pub monotonic: u64,
+  pub realtime: u64,
+  pub xor_hash: u64,
+}
+
+impl EntryKey {
+  /// Orders entries the same way sd-journal does: by sequence number if both come from the
+  /// same sequence, by monotonic clock if they come from the same boot and by wall clock
+  /// otherwise. Each step falls back to the next one on a tie, sequence numbers repeat
+  /// when journald didn't shut down cleanly.
+  pub fn compare(&self, other: &EntryKey) -> Ordering {
+    let mut ordering = Ordering::Equal;
+
+    if self.seqnum_id == other.seqnum_id {
+      ordering = self.seqnum.cmp(&other.seqnum);
+    }
+    if ordering == Ordering::Equal && self.boot_id == other.boot_id {
+      ordering = self.monotonic.cmp(&other.monotonic);
+    }
+
+    ordering
+      .then(self.realtime.cmp(&other.realtime))
+      .then(self.xor_hash.cmp(&other.xor_hash))
+  }
+}
+
+/// Data object found by its field
+#[derive(Clone)]
+pub struct FieldData {
+  pub offset: u64,
+
+  /// Decompressed `FIELD=value`
+  pub payload: Vec<u8>,
+
+  /// Offset of the oldest entry referencing this data
+  pub first_entry: u64,
+
+  /// Entry array with the other entries referencing this data
+  pub entry_array_offset: u64,
+  pub n_entries: u64,
+}
+
+/// Offsets of entries kept in a chain of entry array objects, oldest first. Only the chain is
+/// read up front, items are read when they're needed, so finding an entry by bisecting the
+/// array doesn't read all of it.
+#[derive(Default)]
+pub struct EntryArray {
+  /// Entry stored outside of the array, data objects keep their oldest entry themselves
+  first: Option<u64>,
+  segments: Vec<EntrySegment>,
+  len: u64,
+}
+
+/// Used items of one entry array object
+struct EntrySegment {
+  items_offset: u64,
+  len: u64,
+}
+
+impl EntryArray {
+  pub fn len(&self) -> u64 {
+    self.len
+  }
+}
+
+/// Entry header together with offsets of all its data objects
+pub struct EntryObject {
+  pub key: EntryKey,
+  pub data_offsets: Vec<u64>,
+}
+
+pub struct JournalFile {
+  file: File,
+  path: PathBuf,
+  header: Header,
+}
+
+impl JournalFile {
+  pub fn open(path: &Path) -> Result<JournalFile, JournalError> {
+    let file = File::open(path)?;
+    let file_size = file.metadata()?.len();
+
+    let mut raw = [0u8; 272];
+    let read = read_up_to(&file, 0, &mut raw)?;
+    if (read as u64) < MIN_HEADER_SIZE || &raw[0..8] != SIGNATURE {
+      return Err(JournalError::Corrupted(format!(
+        "{} is not a journal file",
+        path.display()
+      )));
+    }
+
+    let header = Header {
+      incompatible_flags: le32(&raw, 12),
+      seqnum_id: id128(&raw, 72),
+      header_size: le64(&raw, 88),
+      arena_size: le64(&raw, 96),
+      field_hash_table_offset: le64(&raw, 120),
+      field_hash_table_size: le64(&raw, 128),
+      n_entries: le64(&raw, 152),
+      tail_entry_seqnum: le64(&raw, 160),
+      head_entry_seqnum: le64(&raw, 168),
+      entry_array_offset: le64(&raw, 176),
+      head_entry_realtime: le64(&raw, 184),
+      tail_entry_realtime: le64(&raw, 192),
+    };
+
+    if header.incompatible_flags & !INCOMPATIBLE_SUPPORTED != 0 {
+      return Err(JournalError::Unsupported(format!(
+        "{} uses unknown incompatible flags {:#x}",
+        path.display(),
+        header.incompatible_flags
+      )));
+    }
+
+    let arena_end = header.header_size.checked_add(header.arena_size);
+    if header.header_size < MIN_HEADER_SIZE || arena_end.is_none_or(|end| end > file_size) {
+      return Err(JournalError::Corrupted(format!(
+        "{} has invalid header or arena size",
+        path.display()
+      )));
+    }
+
+    let field_hash_table_end = header
+      .field_hash_table_offset
+      .checked_add(header.field_hash_table_size);
+    if field_hash_table_end.is_none_or(|end| end > file_size) {
+      return Err(JournalError::Corrupted(format!(
+        "{} has field hash table out of bounds",
+        path.display()
+      )));
+    }
+
+    Ok(JournalFile {
+      file,
+      path: path.to_owned(),
+      header,
+    })
+  }
+
+  pub fn seqnum_id(&self) -> [u8; 16] {
+    self.header.seqnum_id
+  }
+
+  pub fn n_entries(&self) -> u64 {
+    self.header.n_entries
+  }
+
+  /// Sequence numbers of the first and last entry
+  pub fn seqnum_range(&self) -> (u64, u64) {
+    (self.header.head_entry_seqnum, self.header.tail_entry_seqnum)
+  }
+
+  /// Wall clock timestamps of the first and last entry, in microseconds
+  pub fn realtime_range(&self) -> (u64, u64) {
+    (
+      self.header.head_entry_realtime,
+      self.header.tail_entry_realtime,
+    )
+  }
+
+  /// Offset right after the last object. Can't overflow, `open` checks it's within the file.
+  fn arena_end(&self) -> u64 {
+    self.header.header_size + self.header.arena_size
+  }
+
+  fn is_compact(&self) -> bool {
+    self.header.incompatible_flags & INCOMPATIBLE_COMPACT != 0
+  }
+
+  /// Entries of the file, oldest first
+  pub fn entry_array(&self) -> Result<EntryArray, JournalError> {
+    self.read_entry_array(None, self.header.entry_array_offset, self.header.n_entries)
+  }
+
+  /// Entries referencing a data object, oldest first
+  pub fn data_entry_array(&self, data: &FieldData) -> Result<EntryArray, JournalError> {
+    match data.n_entries {
+      0 => Ok(EntryArray::default()),
+      // The oldest entry is stored in the data object, the array holds the rest
+      n_entries => self.read_entry_array(
+        Some(data.first_entry),
+        data.entry_array_offset,
+        n_entries - 1,
+      ),
+    }
+  }
+
+  /// Offset of the entry at `index` of the array
+  pub fn entry_array_item(&self, array: &EntryArray, index: u64) -> Result<u64, JournalError> {
+    let mut remaining = index;
+    if let Some(first) = array.first {
+      if remaining == 0 {
+        return Ok(first);
+      }
+      remaining -= 1;
+    }
+
+    for segment in &array.segments {
+      if remaining < segment.len {
+        return self.read_item(segment.items_offset + remaining * self.item_size());
+      }
+      remaining -= segment.len;
+    }
+
+    Err(JournalError::Corrupted(format!(
+      "{}: entry array index {} out of bounds",
+      self.path.display(),
+      index
+    )))
+  }
+
+  /// Walks a chain of entry array objects, reading just their headers, up to `limit` items
+  fn read_entry_array(
+    &self,
+    first: Option<u64>,
+    mut array_offset: u64,
+    limit: u64,
+  ) -> Result<EntryArray, JournalError> {
+    let item_size = self.item_size();
+    let mut array = EntryArray {
+      first,
+      segments: Vec::new(),
+      len: first.is_some() as u64,
+    };
+    let mut items = 0;
+
+    while array_offset != 0 && items < limit {
+      let (_, size) = self.read_object_header(array_offset, ObjectType::EntryArray)?;
+      if size < OBJECT_HEADER_SIZE + 8 {
+        return Err(self.corrupted(array_offset, "entry array too small"));
+      }
+
+      let mut next = [0u8; 8];
+      self
+        .file
+        .read_exact_at(&mut next, array_offset + OBJECT_HEADER_SIZE)?;
+      let next = le64(&next, 0);
+
+      let items_offset = array_offset + OBJECT_HEADER_SIZE + 8;
+      let mut len = ((size - OBJECT_HEADER_SIZE - 8) / item_size).min(limit - items);
+      if next == 0 {
+        len = self.used_items(items_offset, len)?;
+      }
+      if len > 0 {
+        array.segments.push(EntrySegment { items_offset, len });
+        items += len;
+      }
+
+      if next != 0 && next <= array_offset {
+        return Err(self.corrupted(array_offset, "entry array chain loops"));
+      }
+      array_offset = next;
+    }
+
+    array.len += items;
+    Ok(array)
+  }
+
+  /// Arrays are allocated ahead and filled in order, this finds the first unused slot
+  fn used_items(&self, items_offset: u64, len: u64) -> Result<u64, JournalError> {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+      let middle = low + (high - low) / 2;
+      match self.read_item(items_offset + middle * self.item_size())? {
+        0 => high = middle,
+        _ => low = middle + 1,
+      }
+    }
+    Ok(low)
+  }
+
+  fn read_item(&self, offset: u64) -> Result<u64, JournalError> {
+    let mut item = [0u8; 8];
+    let item = &mut item[..self.item_size() as usize];
+    self.file.read_exact_at(item, offset)?;
+
+    Ok(match item.len() {
+      4 => le32(item, 0) as u64,
+      _ => le64(item, 0),
+    })
+  }
+
+  fn item_size(&self) -> u64 {
+    if self.is_compact() {
+      4
+    } else {
+      8
+    }
+  }
+
+  /// Reads entry object header and offsets of its data objects, without reading the data.
+  pub fn read_entry(&self, offset: u64) -> Result<EntryObject, JournalError> {
+    let object = self.read_object(offset, ObjectType::Entry)?;
+    if object.len() < 48 {
+      return Err(self.corrupted(offset, "entry object too small"));
+    }
+
+    let key = EntryKey {
+      seqnum_id: self.header.seqnum_id,
+      seqnum: le64(&object, 0),
+      realtime: le64(&object, 8),
+      monotonic: le64(&object, 16),
+      boot_id: id128(&object, 24),
+      xor_hash: le64(&object, 40),
+    };
+
+    let items = &object[48..];
+    let data_offsets = match self.is_compact() {
+      true => items
+        .chunks_exact(4)
+        .map(|item| le32(item, 0) as u64)
+        .collect(),
+      // Regular items are pairs of data object offset and its hash
+      false => items.chunks_exact(16).map(|item| le64(item, 0)).collect(),
+    };
+
+    Ok(EntryObject { key, data_offsets })
+  }
+
+  /// Reads a data object and returns its decompressed `FIELD=value` payload
+  pub fn read_data(&self, offset: u64) -> Result<Vec<u8>, JournalError> {
+    let (flags, object) = self.read_object_with_flags(offset, ObjectType::Data)?;
+
+    // Compact data objects carry 8 more bytes of the tail entry array
+    let payload_start = if self.is_compact() { 56 } else { 48 };
+    if object.len() < payload_start {
+      return Err(self.corrupted(offset, "data object too small"));
+    }
+
+    self.decompress(offset, flags, &object[payload_start..])
+  }
+
+  /// Finds all data objects of a field.
+  ///
+  /// Walks the field hash table instead of hashing the name, which keeps this independent of
+  /// the hash function used by the file. Field tables are small, so it's cheap.
+  pub fn field_data(&self, field: &[u8]) -> Result<Vec<FieldData>, JournalError> {
+    let mut result = Vec::new();
+    let field_offset = match self.find_field(field)? {
+      Some(offset) => offset,
+      None => return Ok(result),
+    };
+
+    let field_object = self.read_object(field_offset, ObjectType::Field)?;
+    if field_object.len() < 24 {
+      return Err(self.corrupted(field_offset, "field object too small"));
+    }
+
+    let mut data_offset = le64(&field_object, 16);
+    while data_offset != 0 {
+      let (flags, object) = self.read_object_with_flags(data_offset, ObjectType::Data)?;
+      let payload_start = if self.is_compact() { 56 } else { 48 };
+      if object.len() < payload_start {
+        return Err(self.corrupted(data_offset, "data object too small"));
+      }
+
+      let payload = self.decompress(data_offset, flags, &object[payload_start..])?;
+      result.push(FieldData {
+        offset: data_offset,
+        payload,
+        first_entry: le64(&object, 24),
+        entry_array_offset: le64(&object, 32),
+        n_entries: le64(&object, 40),
+      });
+
+      // next_field_offset links all data objects of one field, newest first
+      let next = le64(&object, 16);
+      if next >= data_offset {
+        return Err(self.corrupted(data_offset, "data object chain loops"));
+      }
+      data_offset = next;
+    }
+
+    Ok(result)
+  }
+
+  fn find_field(&self, field: &[u8]) -> Result<Option<u64>, JournalError> {
+    let table_size = self.header.field_hash_table_size as usize;
+    if table_size == 0 {
+      return Ok(None);
+    }
+
+    let mut table = vec![0u8; table_size];
+    self
+      .file
+      .read_exact_at(&mut table, self.header.field_hash_table_offset)?;
+
+    // Every bucket holds head and tail offset of a chain of field objects
+    for bucket in table.chunks_exact(16) {
+      let mut offset = le64(bucket, 0);
+      let mut depth = 0;
+
+      while offset != 0 && depth < 1024 {
+        let object = self.read_object(offset, ObjectType::Field)?;
+        if object.len() < 24 {
+          return Err(self.corrupted(offset, "field object too small"));
+        }
+        if &object[24..] == field {
+          return Ok(Some(offset));
+        }
+        offset = le64(&object, 8);
+        depth += 1;
+      }
+    }
+
+    Ok(None)
+  }
+
+  fn decompress(&self, offset: u64, flags: u8, payload: &[u8]) -> Result<Vec<u8>, JournalError> {
+    let result = if flags & OBJECT_COMPRESSED_ZSTD != 0 {
+      let mut decoded = Vec::new();
+      zstd::stream::read::Decoder::new(payload)
+        .and_then(|decoder| {
+          decoder
+            .take(MAX_FIELD_SIZE as u64)
+            .read_to_end(&mut decoded)
+        })
+        .map(|_| decoded)
+    } else if flags & OBJECT_COMPRESSED_LZ4 != 0 {
+      // journald prefixes LZ4 blocks with their uncompressed size
+      if payload.len() < 8 {
+        return Err(self.corrupted(offset, "LZ4 payload too small"));
+      }
+      let size = le64(payload, 0) as usize;
+      if size > MAX_FIELD_SIZE {
+        return Err(self.corrupted(offset, "LZ4 payload too big"));
+      }
+      lz4_flex::block::decompress(&payload[8..], size)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+    } else if flags & OBJECT_COMPRESSED_XZ != 0 {
+      let mut decoded = Vec::new();
+      xz2::read::XzDecoder::new(payload)
+        .take(MAX_FIELD_SIZE as u64)
+        .read_to_end(&mut decoded)
+        .map(|_| decoded)
+    } else {
+      return Ok(payload.to_vec());
+    };
+
+    result.map_err(|err| self.corrupted(offset, &format!("can't decompress data: {}", err)))
+  }
+
+  fn read_object(&self, offset: u64, expected: ObjectType) -> Result<Vec<u8>, JournalError> {
+    self
+      .read_object_with_flags(offset, expected)
+      .map(|(_, payload)| payload)
+  }
+
+  /// Reads object at offset and returns its flags and everything after the object header
+  fn read_object_with_flags(
+    &self,
+    offset: u64,
+    expected: ObjectType,
+  ) -> Result<(u8, Vec<u8>), JournalError> {
+    let (flags, size) = self.read_object_header(offset, expected)?;
+
+    let mut payload = vec![0u8; (size - OBJECT_HEADER_SIZE) as usize];
+    self
+      .file
+      .read_exact_at(&mut payload, offset + OBJECT_HEADER_SIZE)?;
+
+    Ok((flags, payload))
+  }
+
+  /// Reads and checks object header at offset, returns flags and size of the object
+  fn read_object_header(
+    &self,
+    offset: u64,
+    expected: ObjectType,
+  ) -> Result<(u8, u64), JournalError> {
+    let arena_end = self.arena_end();
+    if !offset.is_multiple_of(8)
+      || offset < self.header.header_size
+      || offset
+        .checked_add(OBJECT_HEADER_SIZE)
+        .is_none_or(|end| end > arena_end)
+    {
+      return Err(self.corrupted(offset, "object offset out of bounds"));
+    }
+
+    let mut header = [0u8; OBJECT_HEADER_SIZE as usize];
+    self.file.read_exact_at(&mut header, offset)?;
+
+    let object_type = header[0];
+    let flags = header[1];
+    let size = le64(&header, 8);
+
+    if object_type != expected as u8 {
+      return Err(self.corrupted(offset, "unexpected object type"));
+    }
+    if size < OBJECT_HEADER_SIZE || offset.checked_add(size).is_none_or(|end| end > arena_end) {
+      return Err(self.corrupted(offset, "object size out of bounds"));
+    }
+
+    Ok((flags, size))
+  }
+
+  fn corrupted(&self, offset: u64, reason: &str) -> JournalError {
+    JournalError::Corrupted(format!(
+      "{} at offset {}: {}",
+      self.path.display(),
+      offset,
+      reason
+    ))
+  }
+}
+
+fn read_up_to(file: &File, offset: u64, buf: &mut [u8]) -> std::io::Result<usize> {
+  let mut read = 0;
+  while read < buf.len() {
+    match file.read_at(&mut buf[read..], offset + read as u64)? {
+      0 => break,
+      n => read += n,
+    }
+  }
+  Ok(read)
+}
+
+fn le32(buf: &[u8], at: usize) -> u32 {
+  u32::from_le_bytes(buf[at..at + 4].try_into().unwrap())
+}
+
+fn le64(buf: &[u8], at: usize) -> u64 {
+  u64::from_le_bytes(buf[at..at + 8].try_into().unwrap())
+}
+
+fn id128(buf: &[u8], at: usize) -> [u8; 16] {
+  buf[at..at + 16].try_into().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::PathBuf;
+
+  use super::*;
+
+  fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+      .join("tests/fixtures/journal")
+      .join(name)
+  }
+
+  fn key(seqnum_id: u8, seqnum: u64, boot_id: u8, monotonic: u64, realtime: u64) -> EntryKey {
+    EntryKey {
+      seqnum_id: [seqnum_id; 16],
+      seqnum,
+      boot_id: [boot_id; 16],
+      monotonic,
+      realtime,
+      xor_hash: 0,
+    }
+  }
+
+  fn offsets(file: &JournalFile, array: &EntryArray) -> Vec<u64> {
+    (0..array.len())
+      .map(|index| file.entry_array_item(array, index).unwrap())
+      .collect()
+  }
+
+  /// Every entry of the file as `FIELD=value` payloads
+  fn read_all(file: &JournalFile) -> Vec<(EntryKey, Vec<Vec<u8>>)> {
+    offsets(file, &file.entry_array().unwrap())
+      .into_iter()
+      .map(|offset| {
+        let entry = file.read_entry(offset).unwrap();
+        let data = entry
+          .data_offsets
+          .iter()
+          .map(|offset| file.read_data(*offset).unwrap())
+          .collect();
+        (entry.key, data)
+      })
+      .collect()
+  }
+
+  #[test]
+  fn parses_header() {
+    let file = JournalFile::open(&fixture("plain.journal")).unwrap();
+
+    assert_eq!(file.n_entries(), 11);
+    assert_eq!(file.seqnum_range(), (1, 11));
+    assert!(file.is_compact());
+
+    let (head, tail) = file.realtime_range();
+    assert!(head > 0 && head < tail);
+  }
+
+  #[test]
+  fn rejects_other_files() {
+    let result = JournalFile::open(&fixture("generate.py"));
+    assert!(matches!(result, Err(JournalError::Corrupted(_))));
+
+    let result = JournalFile::open(&fixture("machine/does-not-exist.journal"));
+    assert!(matches!(result, Err(JournalError::Io(_))));
+  }
+
+  #[test]
+  fn rejects_truncated_file() {
+    let result = JournalFile::open(&fixture("truncated.journal"));
+    assert!(matches!(result, Err(JournalError::Corrupted(_))));
+  }
+
+  /// Copy of `plain.journal` with `patch` applied to its header
+  fn patched(name: &str, patch: impl Fn(&mut [u8])) -> PathBuf {
+    let mut content = std::fs::read(fixture("plain.journal")).unwrap();
+    patch(&mut content);
+
+    let path = std::env::temp_dir().join(format!("dragond-{}-{}", std::process::id(), name));
+    std::fs::write(&path, content).unwrap();
+    path
+  }
+
+  #[test]
+  fn rejects_sizes_out_of_bounds() {
+    for (name, at) in [("arena_size", 96), ("field_hash_table_size", 128)] {
+      let path = patched(name, |header| {
+        header[at..at + 8].copy_from_slice(&(u64::MAX - 8).to_le_bytes())
+      });
+      let result = JournalFile::open(&path);
+      std::fs::remove_file(path).unwrap();
+
+      assert!(
+        matches!(result, Err(JournalError::Corrupted(_))),
+        "{}",
+        name
+      );
+    }
+  }
+
+  #[test]
+  fn stops_at_corrupt_objects() {
+    // Claims 2^60 entries, the second item of the entry array points into the header
+    let file = JournalFile::open(&fixture("corrupt.journal")).unwrap();
+    assert_eq!(file.n_entries(), 1 << 60);
+
+    let offsets = offsets(&file, &file.entry_array().unwrap());
+    assert_eq!(offsets.len(), 11);
+    assert_eq!(file.read_entry(offsets[0]).unwrap().key.seqnum, 1);
+    assert!(matches!(
+      file.read_entry(offsets[1]),
+      Err(JournalError::Corrupted(_))
+    ));
+
+    for offset in [u64::MAX - 7, u64::MAX - 15, 3] {
+      assert!(matches!(
+        file.read_entry(offset),
+        Err(JournalError::Corrupted(_))
+      ));
+    }
+  }
+
+  #[test]
+  fn iterates_entries_in_order() {
+    let file = JournalFile::open(&fixture("plain.journal")).unwrap();
+    let entries = read_all(&file);
+
+    assert_eq!(entries.len(), 11);
+    for (i, (key, _)) in entries.iter().enumerate() {
+      assert_eq!(key.seqnum, i as u64 + 1);
+      assert_eq!(key.seqnum_id, file.seqnum_id());
+    }
+    assert!(entries
+      .windows(2)
+      .all(|pair| pair[0].0.compare(&pair[1].0) == Ordering::Less));
+
+    let (_, first) = &entries[0];
+    assert!(first.contains(&b"MESSAGE=Journal started".to_vec()));
+    let (_, app) = &entries[2];
+    assert!(app.contains(&b"MESSAGE=app started on boot A".to_vec()));
+    assert!(app.contains(&b"_SYSTEMD_UNIT=fixture-app.service".to_vec()));
+  }
+
+  #[test]
+  fn decompresses_data() {
+    let file = JournalFile::open(&fixture("plain.journal")).unwrap();
+    let entries = read_all(&file);
+
+    let expected = format!(
+      "MESSAGE=app dumped its state:\n{}",
+      "state line\n".repeat(100)
+    );
+    let (_, dump) = &entries[4];
+    assert!(dump.contains(&expected.into_bytes()));
+
+    let (_, binary) = &entries[9];
+    assert!(binary.contains(&b"FIXTURE_BINARY=\x00\xff\xfe".to_vec()));
+  }
+
+  #[test]
+  fn finds_field_data() {
+    let file = JournalFile::open(&fixture("plain.journal")).unwrap();
+
+    let mut units: Vec<Vec<u8>> = file
+      .field_data(b"_SYSTEMD_UNIT")
+      .unwrap()
+      .into_iter()
+      .map(|data| data.payload)
+      .collect();
+    units.sort();
+    assert_eq!(
+      units,
+      vec![
+        b"_SYSTEMD_UNIT=fixture-app.service".to_vec(),
+        b"_SYSTEMD_UNIT=fixture-other.service".to_vec(),
+      ]
+    );
+
+    let boots = file.field_data(b"_BOOT_ID").unwrap();
+    assert_eq!(boots.len(), 1);
+    assert_eq!(file.read_entry(boots[0].first_entry).unwrap().key.seqnum, 1);
+
+    assert!(file.field_data(b"NO_SUCH_FIELD").unwrap().is_empty());
+  }
+
+  #[test]
+  fn lists_entries_of_data() {
+    let file = JournalFile::open(&fixture("plain.journal")).unwrap();
+    let seqnums = |field: &[u8], value: &[u8]| -> Vec<u64> {
+      let data = file
+        .field_data(field)
+        .unwrap()
+        .into_iter()
+        .find(|data| data.payload.ends_with(value))
+        .unwrap();
+      let array = file.data_entry_array(&data).unwrap();
+      assert_eq!(array.len(), data.n_entries);
+
+      offsets(&file, &array)
+        .into_iter()
+        .map(|offset| file.read_entry(offset).unwrap().key.seqnum)
+        .collect()
+    };
+
+    assert_eq!(
+      seqnums(b"_SYSTEMD_UNIT", b"=fixture-app.service"),
+      vec![3, 4, 5, 6, 7]
+    );
+    assert_eq!(
+      seqnums(b"_SYSTEMD_UNIT", b"=fixture-other.service"),
+      vec![8]
+    );
+    assert_eq!(seqnums(b"_BOOT_ID", b""), (1..=11).collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn orders_by_seqnum_within_sequence() {
+    let older = key(1, 1, 1, 200, 200);
+    let newer = key(1, 2, 2, 100, 100);
+    assert_eq!(older.compare(&newer), Ordering::Less);
+    assert_eq!(newer.compare(&older), Ordering::Greater);
+    assert_eq!(older.compare(&older), Ordering::Equal);
+  }
+
+  #[test]
+  fn orders_by_monotonic_within_boot() {
+    let older = key(1, 5, 1, 100, 200);
+    let newer = key(2, 1, 1, 200, 100);
+    assert_eq!(older.compare(&newer), Ordering::Less);
+  }
+
+  #[test]
+  fn orders_by_realtime_otherwise() {
+    let older = key(1, 5, 1, 200, 100);
+    let newer = key(2, 1, 2, 100, 200);
+    assert_eq!(older.compare(&newer), Ordering::Less);
+  }
+
+  #[test]
+  fn falls_back_on_ties() {
+    // Repeated sequence number after journald didn't shut down cleanly
+    let older = key(1, 5, 1, 100, 100);
+    let newer = key(1, 5, 1, 200, 50);
+    assert_eq!(older.compare(&newer), Ordering::Less);
+
+    let older = key(1, 5, 2, 100, 100);
+    let newer = key(1, 5, 3, 50, 200);
+    assert_eq!(older.compare(&newer), Ordering::Less);
+  }
+}
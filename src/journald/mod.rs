@@ -1,2 +1,8 @@
+pub mod dto;
+pub mod export;
 pub mod functions;
+pub mod journal_file;
+pub mod query;
 pub mod routes;
+pub mod source;
+pub mod time;
@@ -0,0 +1,427 @@
+use actix_web::web::Query;
+use regex::RegexBuilder;
+
+use super::{
+  source::{self, and_matches, hex, Direction, JournalQuery, JournalSource},
+  time::{now_usec, parse_timestamp},
+};
+use crate::api_errors::ApiError;
+
+/// Syslog priority names, index is the numeric priority
+const PRIORITY_NAMES: [&str; 8] = [
+  "emerg", "alert", "crit", "err", "warning", "notice", "info", "debug",
+];
+
+/// Filters for reading the journal, mirroring journalctl options.
+///
+/// Arbitrary field matches are given as repeated `match=FIELD=value` parameters, which serde
+/// can't put into this struct, see `field_matches`.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub struct LogsQuery {
+  /// Maximum number of entries, pages of `/logs` are 100 entries by default and 1000 at most
+  pub lines_number: Option<usize>,
+
+  /// Start at this entry, including it
+  pub cursor: Option<String>,
+
+  /// Start right after this entry
+  pub after_cursor: Option<String>,
+
+  /// `backward` (newest first, the default) or `forward`
+  #[serde(default)]
+  pub direction: Direction,
+
+  /// See `parse_timestamp` for accepted formats
+  pub since: Option<String>,
+  pub until: Option<String>,
+
+  /// Single priority, which includes all more important ones, or a `FROM..TO` range. Both
+  /// numbers and names like `err` are accepted.
+  pub priority: Option<String>,
+
+  /// Boot id or offset: 0 is the latest boot, -1 the one before it and 1 the oldest one
+  pub boot: Option<String>,
+
+  pub pid: Option<u32>,
+  pub uid: Option<u32>,
+
+  /// Syslog identifier, `journalctl --identifier`
+  pub identifier: Option<String>,
+
+  /// Regular expression `MESSAGE` has to match
+  pub grep: Option<String>,
+
+  /// By default grep is case sensitive only when the pattern has upper case letters
+  pub case_sensitive: Option<bool>,
+}
+
+impl LogsQuery {
+  /// Builds query for the source, only entries matching `matches` as well as all filters of
+  /// this query are read
+  pub fn to_journal_query(
+    &self,
+    source: &dyn JournalSource,
+    matches: Vec<Vec<(String, String)>>,
+  ) -> Result<JournalQuery, ApiError> {
+    let (cursor, exclude_cursor) = match (&self.cursor, &self.after_cursor) {
+      (Some(_), Some(_)) => {
+        return Err(ApiError::Validation(
+          "Only one of cursor and after_cursor can be used".to_owned(),
+        ))
+      }
+      (Some(cursor), None) => (Some(source::parse_cursor(cursor)?), false),
+      (None, Some(cursor)) => (Some(source::parse_cursor(cursor)?), true),
+      (None, None) => (None, false),
+    };
+
+    let now = now_usec();
+    let since = self
+      .since
+      .as_deref()
+      .map(|since| parse_timestamp(since, now))
+      .transpose()
+      .map_err(ApiError::Validation)?;
+    let until = self
+      .until
+      .as_deref()
+      .map(|until| parse_timestamp(until, now))
+      .transpose()
+      .map_err(ApiError::Validation)?;
+
+    if let (Some(since), Some(until)) = (since, until) {
+      if since > until {
+        return Err(ApiError::Validation(
+          "since has to be before until".to_owned(),
+        ));
+      }
+    }
+
+    let mut filters = Vec::new();
+
+    if let Some(priority) = &self.priority {
+      let (from, to) = parse_priority_range(priority).map_err(ApiError::Validation)?;
+      for priority in from..=to {
+        filters.push(("PRIORITY".to_owned(), priority.to_string()));
+      }
+    }
+
+    if let Some(boot) = &self.boot {
+      let boot_id = resolve_boot(source, boot)?;
+      filters.push(("_BOOT_ID".to_owned(), boot_id));
+    }
+
+    if let Some(pid) = self.pid {
+      filters.push(("_PID".to_owned(), pid.to_string()));
+    }
+    if let Some(uid) = self.uid {
+      filters.push(("_UID".to_owned(), uid.to_string()));
+    }
+    if let Some(identifier) = &self.identifier {
+      filters.push(("SYSLOG_IDENTIFIER".to_owned(), identifier.to_owned()));
+    }
+
+    let grep = match &self.grep {
+      Some(pattern) => {
+        let case_sensitive = self
+          .case_sensitive
+          .unwrap_or_else(|| pattern.chars().any(char::is_uppercase));
+        let regex = RegexBuilder::new(pattern)
+          .case_insensitive(!case_sensitive)
+          .build()
+          .map_err(|err| ApiError::Validation(format!("Invalid grep pattern: {}", err)))?;
+        Some(regex)
+      }
+      None => None,
+    };
+
+    let filters = match filters.is_empty() {
+      true => Vec::new(),
+      false => vec![filters],
+    };
+
+    Ok(JournalQuery {
+      matches: and_matches(matches, filters),
+      direction: self.direction,
+      cursor,
+      exclude_cursor,
+      limit: self.lines_number,
+      since,
+      until,
+      grep,
+    })
+  }
+}
+
+/// Parses `match` parameters of a query string. Same rules as journalctl matches apply:
+/// values of the same field are alternatives, different fields all have to match and
+/// a `+` starts a new group of matches, any of the groups can match.
+pub fn field_matches(query_string: &str) -> Result<Vec<Vec<(String, String)>>, String> {
+  let pairs = Query::<Vec<(String, String)>>::from_query(query_string)
+    .map_err(|err| format!("Invalid query string: {}", err))?
+    .into_inner();
+
+  let mut groups = vec![Vec::new()];
+  for value in pairs
+    .into_iter()
+    .filter(|(name, _)| name == "match")
+    .map(|(_, value)| value)
+  {
+    if value == "+" {
+      groups.push(Vec::new());
+      continue;
+    }
+
+    let (field, field_value) = value
+      .split_once('=')
+      .ok_or_else(|| format!("Match {} isn't in FIELD=value form", value))?;
+    if !is_valid_field_name(field) {
+      return Err(format!("Invalid field name {}", field));
+    }
+
+    groups
+      .last_mut()
+      .unwrap()
+      .push((field.to_owned(), field_value.to_owned()));
+  }
+
+  if groups.iter().any(Vec::is_empty) && groups.len() > 1 {
+    return Err("Every match group separated by + has to have a match".to_owned());
+  }
+
+  Ok(
+    groups
+      .into_iter()
+      .filter(|group| !group.is_empty())
+      .collect(),
+  )
+}
+
+/// Journal field names are upper case letters, digits and underscores, not starting with
+/// a digit
+fn is_valid_field_name(field: &str) -> bool {
+  !field.is_empty()
+    && !field.starts_with(|c: char| c.is_ascii_digit())
+    && field
+      .chars()
+      .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn parse_priority_range(priority: &str) -> Result<(u8, u8), String> {
+  match priority.split_once("..") {
+    Some((from, to)) => {
+      let (from, to) = (parse_priority(from)?, parse_priority(to)?);
+      Ok((from.min(to), from.max(to)))
+    }
+    // Single priority means this one and everything more important
+    None => Ok((0, parse_priority(priority)?)),
+  }
+}
+
+fn parse_priority(priority: &str) -> Result<u8, String> {
+  let priority = priority.trim();
+
+  if let Some(index) = PRIORITY_NAMES.iter().position(|name| *name == priority) {
+    return Ok(index as u8);
+  }
+
+  match priority.parse::<u8>() {
+    Ok(number) if (number as usize) < PRIORITY_NAMES.len() => Ok(number),
+    _ => Err(format!(
+      "Invalid priority {}, expected 0-7 or one of: {}",
+      priority,
+      PRIORITY_NAMES.join(", ")
+    )),
+  }
+}
+
+/// Turns boot id or offset into boot id in the form used by the `_BOOT_ID` field
+fn resolve_boot(source: &dyn JournalSource, boot: &str) -> Result<String, ApiError> {
+  let boot = boot.trim();
+  if boot.len() == 32 && boot.chars().all(|c| c.is_ascii_hexdigit()) {
+    return Ok(boot.to_ascii_lowercase());
+  }
+
+  let offset: i64 = boot.parse().map_err(|_| {
+    ApiError::Validation(format!("Invalid boot {}, expected boot id or offset", boot))
+  })?;
+
+  let boots = source.boots()?;
+  let index = match offset {
+    // Positive offsets count from the oldest boot, starting with 1
+    1.. => Some(offset - 1),
+    _ => Some(boots.len() as i64 - 1 + offset),
+  }
+  .filter(|index| *index >= 0 && (*index as usize) < boots.len());
+
+  match index {
+    Some(index) => Ok(hex(&boots[index as usize].id)),
+    None => Err(ApiError::Validation(format!(
+      "No boot with offset {} in the journal",
+      offset
+    ))),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::path::Path;
+
+  use super::*;
+  use crate::journald::{
+    functions::unit_matches,
+    source::{JournalDirectories, JournalEntry},
+  };
+
+  /// See `tests/fixtures/journal/generate.py` for what was logged
+  fn machine() -> JournalDirectories {
+    JournalDirectories::new(vec![
+      Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/journal/machine")
+    ])
+  }
+
+  fn read(params: LogsQuery, matches: Vec<Vec<(String, String)>>) -> Vec<JournalEntry> {
+    let source = machine();
+    let query = params.to_journal_query(&source, matches).unwrap();
+    source.read(&query).unwrap()
+  }
+
+  fn field(entry: &JournalEntry, name: &str) -> String {
+    String::from_utf8_lossy(entry.field(name).unwrap()).into_owned()
+  }
+
+  fn messages(entries: &[JournalEntry]) -> Vec<String> {
+    entries
+      .iter()
+      .map(|entry| field(entry, "MESSAGE"))
+      .collect()
+  }
+
+  fn forward() -> LogsQuery {
+    LogsQuery {
+      direction: Direction::Forward,
+      ..Default::default()
+    }
+  }
+
+  #[test]
+  fn unit_logs_filtered_by_pid() {
+    let worker = read(
+      forward(),
+      vec![vec![("FIXTURE_STEP".to_owned(), "4".to_owned())]],
+    );
+    let pid: u32 = field(&worker[0], "_PID").parse().unwrap();
+
+    let params = LogsQuery {
+      pid: Some(pid),
+      ..forward()
+    };
+    let entries = read(params, unit_matches("fixture-app"));
+    assert_eq!(
+      messages(&entries),
+      ["worker ready", "worker failed to connect"]
+    );
+  }
+
+  #[test]
+  fn unit_logs_filtered_by_uid() {
+    // The manager logs about the unit as root, which mustn't widen the uid filter
+    let params = LogsQuery {
+      uid: Some(65534),
+      ..forward()
+    };
+    let entries = read(params, unit_matches("fixture-app.service"));
+    assert_eq!(
+      messages(&entries),
+      ["worker ready", "worker failed to connect"].repeat(3)
+    );
+
+    let entries = read(forward(), unit_matches("fixture-app.service"));
+    assert_eq!(entries.len(), 18);
+  }
+
+  #[test]
+  fn filters_by_priority() {
+    let params = LogsQuery {
+      priority: Some("err".to_owned()),
+      ..forward()
+    };
+    assert_eq!(
+      messages(&read(params, Vec::new())),
+      ["worker failed to connect"].repeat(3)
+    );
+
+    let params = LogsQuery {
+      priority: Some("warning..4".to_owned()),
+      ..forward()
+    };
+    assert_eq!(
+      messages(&read(params, Vec::new())),
+      ["app is low on disk space"].repeat(3)
+    );
+  }
+
+  #[test]
+  fn filters_by_boot() {
+    let boot_messages = |boot: &str| {
+      let params = LogsQuery {
+        boot: Some(boot.to_owned()),
+        identifier: Some("fixture-app".to_owned()),
+        ..forward()
+      };
+      messages(&read(params, Vec::new()))
+        .into_iter()
+        .filter(|message| message.starts_with("app started"))
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(boot_messages("0"), ["app started on boot B"]);
+    assert_eq!(boot_messages("-1"), ["app started on boot A"; 2]);
+    assert_eq!(boot_messages("1"), ["app started on boot A"; 2]);
+    assert_eq!(
+      boot_messages("0B0B0B0B0B0B4B0B8B0B0B0B0B0B0B0B"),
+      ["app started on boot B"]
+    );
+
+    let params = LogsQuery {
+      boot: Some("-2".to_owned()),
+      ..forward()
+    };
+    assert!(params.to_journal_query(&machine(), Vec::new()).is_err());
+  }
+
+  #[test]
+  fn parses_field_matches() {
+    assert_eq!(
+      field_matches("match=A=1&match=B=x%3Dy&other=1&match=%2B&match=A=2").unwrap(),
+      vec![
+        vec![
+          ("A".to_owned(), "1".to_owned()),
+          ("B".to_owned(), "x=y".to_owned())
+        ],
+        vec![("A".to_owned(), "2".to_owned())],
+      ]
+    );
+    assert!(field_matches("other=1").unwrap().is_empty());
+
+    for invalid in [
+      "match=A",
+      "match=a=1",
+      "match=1A=1",
+      "match=%2B&match=A=1",
+      "match=A=1&match=%2B",
+    ] {
+      assert!(field_matches(invalid).is_err(), "{}", invalid);
+    }
+  }
+
+  #[test]
+  fn parses_priorities() {
+    assert_eq!(parse_priority_range("3"), Ok((0, 3)));
+    assert_eq!(parse_priority_range("err"), Ok((0, 3)));
+    assert_eq!(parse_priority_range("debug..warning"), Ok((4, 7)));
+    assert_eq!(parse_priority_range("1..2"), Ok((1, 2)));
+    assert!(parse_priority_range("8").is_err());
+    assert!(parse_priority_range("loud").is_err());
+  }
+}
@@ -1,22 +1,173 @@
-use crate::{api_errors::ApiError, journald::functions};
-use actix_web::{get, http::header::ContentType, web, web::Query, HttpResponse, Responder};
+use crate::{
+  api_errors::ApiError,
+  app_state::AppState,
+  journald::{
+    export::{self, ExportFormat},
+    functions,
+    query::{self, LogsQuery},
+    source::{and_matches, JournalQuery},
+  },
+  sse,
+};
+use actix_web::{
+  get, http::header::ContentType, web, web::Query, HttpRequest, HttpResponse, Responder,
+};
 
-#[derive(Deserialize)]
-struct Info {
-  lines_number: Option<usize>,
-  cursor: Option<String>,
-}
+/// Combines filters from query parameters, `match` parameters and matches of the endpoint.
+/// Boot offsets are resolved by reading the journal, so it runs on the blocking thread pool.
+pub(crate) async fn journal_query(
+  state: &AppState<'static>,
+  req: &HttpRequest,
+  params: LogsQuery,
+  matches: Vec<Vec<(String, String)>>,
+) -> Result<JournalQuery, ApiError> {
+  let field_matches = query::field_matches(req.query_string()).map_err(ApiError::Validation)?;
+  let matches = and_matches(matches, field_matches);
 
-#[get("/unit-logs/{name}")]
-async fn unit_logs(path: web::Path<String>, info: Query<Info>) -> Result<impl Responder, ApiError> {
-  let info = info.into_inner();
-  let name = path.to_string(); //TODO checking if unit exists and returning appropriate http error if not
+  let journal = state.journal.clone();
+  web::block(move || params.to_journal_query(journal.as_ref(), matches))
+    .await
+    .map_err(|err| ApiError::Io(std::io::Error::other(err.to_string())))?
+}
 
-  let response_body = functions::read_lines(&name, &info.lines_number, &info.cursor);
+/// Reads one page of entries on the blocking thread pool, see `functions::read_page`
+pub(crate) async fn entries_response(
+  state: &AppState<'static>,
+  query: JournalQuery,
+) -> Result<HttpResponse, ApiError> {
+  let journal = state.journal.clone();
+  let page = web::block(move || functions::read_page(journal.as_ref(), &query))
+    .await
+    .map_err(|err| ApiError::Io(std::io::Error::other(err.to_string())))??;
+  let serialized = serde_json::to_string(&page).unwrap_or("{}".to_owned());
 
   Ok(
     HttpResponse::Ok()
       .append_header(ContentType::json())
-      .body(response_body.unwrap().to_string()),
+      .body(serialized),
   )
 }
+
+/// Reads journal entries, see `LogsQuery` for filters
+#[get("/logs")]
+async fn logs(
+  state: web::Data<AppState<'static>>,
+  req: HttpRequest,
+  params: Query<LogsQuery>,
+) -> Result<impl Responder, ApiError> {
+  let query = journal_query(&state, &req, params.into_inner(), Vec::new()).await?;
+  entries_response(&state, query).await
+}
+
+#[get("/unit-logs/{name}")]
+async fn unit_logs(
+  state: web::Data<AppState<'static>>,
+  req: HttpRequest,
+  path: web::Path<String>,
+  params: Query<LogsQuery>,
+) -> Result<impl Responder, ApiError> {
+  let name = path.to_string(); //TODO checking if unit exists and returning appropriate http error if not
+
+  let matches = functions::unit_matches(&name);
+  let query = journal_query(&state, &req, params.into_inner(), matches).await?;
+  entries_response(&state, query).await
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+  #[serde(default)]
+  format: ExportFormat,
+
+  #[serde(default)]
+  gzip: bool,
+
+  /// Only logs of this unit, same as `/unit-logs/{name}`
+  unit: Option<String>,
+}
+
+/// Downloads journal entries as a file, filtered the same way as `/logs`
+#[get("/export")]
+async fn export_logs(
+  state: web::Data<AppState<'static>>,
+  req: HttpRequest,
+  params: Query<LogsQuery>,
+  export_params: Query<ExportQuery>,
+) -> Result<impl Responder, ApiError> {
+  let matches = match &export_params.unit {
+    Some(unit) => functions::unit_matches(unit),
+    None => Vec::new(),
+  };
+  let query = journal_query(&state, &req, params.into_inner(), matches).await?;
+
+  // Unit names can contain characters which aren't nice in file names
+  let name = match &export_params.unit {
+    Some(unit) => unit.replace(|c: char| !c.is_ascii_alphanumeric() && c != '.', "_"),
+    None => "journal".to_owned(),
+  };
+
+  Ok(export::export_response(
+    state.journal.clone(),
+    query,
+    export_params.format,
+    export_params.gzip,
+    &name,
+  ))
+}
+
+/// Streams journal entries as Server-Sent Events, see `follow_response`
+#[get("/logs/follow")]
+async fn follow_logs(
+  state: web::Data<AppState<'static>>,
+  req: HttpRequest,
+  params: Query<LogsQuery>,
+) -> Result<impl Responder, ApiError> {
+  follow_response(&state, &req, params.into_inner(), Vec::new()).await
+}
+
+/// Streams unit logs as Server-Sent Events, see `follow_response`
+#[get("/unit-logs/{name}/follow")]
+async fn follow_unit_logs(
+  state: web::Data<AppState<'static>>,
+  req: HttpRequest,
+  path: web::Path<String>,
+  params: Query<LogsQuery>,
+) -> Result<impl Responder, ApiError> {
+  let name = path.to_string();
+  follow_response(
+    &state,
+    &req,
+    params.into_inner(),
+    functions::unit_matches(&name),
+  )
+  .await
+}
+
+/// Each `entry` event carries one journal entry and its cursor as event id, so reconnecting
+/// clients continue after the last entry they got.
+async fn follow_response(
+  state: &AppState<'static>,
+  req: &HttpRequest,
+  mut params: LogsQuery,
+  matches: Vec<Vec<(String, String)>>,
+) -> Result<HttpResponse, ApiError> {
+  // EventSource sends id of the last received event when it reconnects
+  let last_event_id = req
+    .headers()
+    .get("Last-Event-ID")
+    .and_then(|value| value.to_str().ok());
+  if let Some(last_event_id) = last_event_id {
+    params.cursor = None;
+    params.after_cursor = Some(last_event_id.to_owned());
+  }
+
+  let query = journal_query(state, req, params, matches).await?;
+
+  let (sender, response) = sse::channel();
+  actix_web::rt::spawn(functions::follow_entries(
+    state.journal.clone(),
+    query,
+    sender,
+  ));
+
+  Ok(response)
+}
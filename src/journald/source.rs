@@ -0,0 +1,1110 @@
+use std::{
+  cmp::Ordering,
+  fs,
+  io::Error as IoError,
+  path::{Path, PathBuf},
+};
+
+use derive_more::{Display, Error, From};
+use regex::Regex;
+
+use super::journal_file::{EntryArray, EntryKey, EntryObject, FieldData, JournalFile};
+
+/// Directories journald writes to, persistent and volatile one
+const JOURNAL_ROOTS: [&str; 2] = ["/var/log/journal", "/run/log/journal"];
+
+#[derive(Debug, Display, Error, From)]
+pub enum JournalError {
+  Io(IoError),
+
+  /// File doesn't follow the journal file format
+  #[display(fmt = "{}", _0)]
+  #[from(ignore)]
+  Corrupted(#[error(not(source))] String),
+
+  /// File uses a feature this reader doesn't know
+  #[display(fmt = "{}", _0)]
+  #[from(ignore)]
+  Unsupported(#[error(not(source))] String),
+
+  #[display(fmt = "Invalid cursor: {}", _0)]
+  #[from(ignore)]
+  InvalidCursor(#[error(not(source))] String),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+  /// Oldest entries first
+  Forward,
+
+  /// Newest entries first
+  #[default]
+  Backward,
+}
+
+/// Which entries to read and in what order
+#[derive(Clone, Default)]
+pub struct JournalQuery {
+  /// Groups of `FIELD=value` pairs, entry matches if it matches at least one group. Entry
+  /// matches a group if it has every field of the group with one of values given for it, same
+  /// as matches of journalctl. No groups means every entry matches.
+  pub matches: Vec<Vec<(String, String)>>,
+
+  /// Skip entries written before this time, in microseconds since the epoch
+  pub since: Option<u64>,
+
+  /// Skip entries written after this time, in microseconds since the epoch
+  pub until: Option<u64>,
+
+  /// Only entries with a `MESSAGE` matching this pattern
+  pub grep: Option<Regex>,
+
+  pub direction: Direction,
+
+  /// Start reading at this entry, older entries are skipped when going forward and newer ones
+  /// when going backward
+  pub cursor: Option<EntryKey>,
+
+  /// Skip the entry `cursor` points to as well
+  pub exclude_cursor: bool,
+
+  /// Maximum number of entries to return
+  pub limit: Option<usize>,
+}
+
+pub struct JournalEntry {
+  pub key: EntryKey,
+
+  /// Raw `FIELD`, `value` pairs, in the order they're stored. A field can repeat.
+  pub fields: Vec<(String, Vec<u8>)>,
+}
+
+impl JournalEntry {
+  /// Cursor in the same format journalctl uses
+  pub fn cursor(&self) -> String {
+    format_cursor(&self.key)
+  }
+
+  /// First value of a field
+  pub fn field(&self, name: &str) -> Option<&[u8]> {
+    self
+      .fields
+      .iter()
+      .find(|(field, _)| field == name)
+      .map(|(_, value)| value.as_slice())
+  }
+}
+
+/// One boot recorded in the journal
+pub struct Boot {
+  pub id: [u8; 16],
+
+  /// Time of the oldest entry of this boot, in microseconds since the epoch
+  pub first_realtime: u64,
+}
+
+/// Something journal entries can be read from
+pub trait JournalSource {
+  fn read(&self, query: &JournalQuery) -> Result<Vec<JournalEntry>, JournalError>;
+
+  /// Boots which have entries in the journal, oldest first
+  fn boots(&self) -> Result<Vec<Boot>, JournalError>;
+}
+
+/// Combines matches so only entries matching both `left` and `right` match
+pub fn and_matches(
+  left: Vec<Vec<(String, String)>>,
+  right: Vec<Vec<(String, String)>>,
+) -> Vec<Vec<(String, String)>> {
+  if left.is_empty() {
+    return right;
+  }
+  if right.is_empty() {
+    return left;
+  }
+
+  let mut groups = Vec::with_capacity(left.len() * right.len());
+  for left_group in &left {
+    for right_group in &right {
+      groups.extend(and_group(left_group, right_group));
+    }
+  }
+  groups
+}
+
+/// Group matching entries which match both groups. Values of a field are alternatives within
+/// a group, so a field both groups have keeps only values accepted by both of them. None when
+/// there are no such values, the groups contradict each other.
+fn and_group(
+  left: &[(String, String)],
+  right: &[(String, String)],
+) -> Option<Vec<(String, String)>> {
+  let has_field =
+    |group: &[(String, String)], field: &str| group.iter().any(|(name, _)| name == field);
+
+  let mut group: Vec<(String, String)> = left
+    .iter()
+    .filter(|pair| !has_field(right, &pair.0) || right.contains(pair))
+    .cloned()
+    .collect();
+  group.extend(
+    right
+      .iter()
+      .filter(|(field, _)| !has_field(left, field))
+      .cloned(),
+  );
+
+  left
+    .iter()
+    .chain(right)
+    .all(|(field, _)| has_field(&group, field))
+    .then_some(group)
+}
+
+/// Reads every `*.journal` file in given directories, like `journalctl --directory`
+pub struct JournalDirectories {
+  directories: Vec<PathBuf>,
+}
+
+impl JournalDirectories {
+  pub fn new(directories: Vec<PathBuf>) -> Self {
+    Self { directories }
+  }
+
+  /// Journal directories of the local machine, same as journalctl uses by default
+  pub fn system() -> Self {
+    let machine_id = fs::read_to_string("/etc/machine-id")
+      .ok()
+      .map(|id| id.trim().to_owned())
+      .filter(|id| !id.is_empty());
+
+    let mut directories = Vec::new();
+    for root in JOURNAL_ROOTS {
+      match &machine_id {
+        Some(id) => directories.push(Path::new(root).join(id)),
+        None => directories.extend(subdirectories(Path::new(root))),
+      }
+    }
+
+    Self::new(directories)
+  }
+
+  fn open_files(&self) -> Vec<JournalFile> {
+    let mut files = Vec::new();
+
+    for directory in &self.directories {
+      let dir_entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(_) => continue,
+      };
+
+      for path in dir_entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+      {
+        // Files ending with `.journal~` were corrupted and put aside by journald
+        if path
+          .extension()
+          .is_none_or(|extension| extension != "journal")
+        {
+          continue;
+        }
+
+        match JournalFile::open(&path) {
+          Ok(file) => files.push(file),
+          Err(err) => warn!("Skipping journal file {}: {}", path.display(), err),
+        }
+      }
+    }
+
+    files
+  }
+}
+
+impl JournalSource for JournalDirectories {
+  fn read(&self, query: &JournalQuery) -> Result<Vec<JournalEntry>, JournalError> {
+    let mut readers = Vec::new();
+    for file in self.open_files() {
+      match FileReader::new(file, query) {
+        Ok(Some(reader)) => readers.push(reader),
+        Ok(None) => {}
+        Err(err) => warn!("Skipping unreadable journal file: {}", err),
+      }
+    }
+
+    let limit = query.limit.unwrap_or(usize::MAX);
+    let mut entries: Vec<JournalEntry> = Vec::new();
+    let mut last_key: Option<EntryKey> = None;
+
+    // Every reader has its next matching entry ready, the best one of them goes next
+    let mut heads: Vec<Option<EntryObject>> = readers.iter_mut().map(|r| r.next()).collect();
+
+    while entries.len() < limit {
+      let best = heads
+        .iter()
+        .enumerate()
+        .filter_map(|(i, head)| head.as_ref().map(|entry| (i, entry)))
+        .reduce(|best, candidate| {
+          let ordering = candidate.1.key.compare(&best.1.key);
+          match (query.direction, ordering) {
+            (Direction::Forward, Ordering::Less) | (Direction::Backward, Ordering::Greater) => {
+              candidate
+            }
+            _ => best,
+          }
+        })
+        .map(|(i, _)| i);
+
+      let index = match best {
+        Some(index) => index,
+        None => break,
+      };
+
+      let entry = heads[index].take().unwrap();
+      heads[index] = readers[index].next();
+
+      // The same entry can be stored in more than one file, i.e. when journals were merged
+      if last_key == Some(entry.key) {
+        continue;
+      }
+      last_key = Some(entry.key);
+
+      let entry = match readers[index].read_fields(entry) {
+        Ok(entry) => entry,
+        Err(err) => {
+          warn!("Skipping unreadable journal entry: {}", err);
+          continue;
+        }
+      };
+
+      if let Some(grep) = &query.grep {
+        let message = entry.field("MESSAGE").map(String::from_utf8_lossy);
+        if !message.is_some_and(|message| grep.is_match(&message)) {
+          continue;
+        }
+      }
+
+      entries.push(entry);
+    }
+
+    Ok(entries)
+  }
+
+  fn boots(&self) -> Result<Vec<Boot>, JournalError> {
+    let mut boots: Vec<Boot> = Vec::new();
+
+    for file in self.open_files() {
+      let file_boots = match file_boots(&file) {
+        Ok(file_boots) => file_boots,
+        Err(err) => {
+          warn!("Skipping unreadable journal file: {}", err);
+          continue;
+        }
+      };
+
+      for Boot { id, first_realtime } in file_boots {
+        match boots.iter_mut().find(|boot| boot.id == id) {
+          Some(boot) => boot.first_realtime = boot.first_realtime.min(first_realtime),
+          None => boots.push(Boot { id, first_realtime }),
+        }
+      }
+    }
+
+    boots.sort_by_key(|boot| boot.first_realtime);
+    Ok(boots)
+  }
+}
+
+/// Boots with entries in the file, with the first entry of each in this file
+fn file_boots(file: &JournalFile) -> Result<Vec<Boot>, JournalError> {
+  let mut boots = Vec::new();
+
+  for data in file.field_data(b"_BOOT_ID")? {
+    let first_realtime = file.read_entry(data.first_entry)?.key.realtime;
+    let value = data.payload.get(9..).ok_or_else(|| {
+      JournalError::Corrupted(format!(
+        "_BOOT_ID data object at offset {} is too small",
+        data.offset
+      ))
+    })?;
+    if let Some(id) = parse_id128(&String::from_utf8_lossy(value)) {
+      boots.push(Boot { id, first_realtime });
+    }
+  }
+
+  Ok(boots)
+}
+
+/// Iterates matching entries of a single file in the direction of the query
+struct FileReader {
+  file: JournalFile,
+
+  /// Entries which may match. All entries of the file without matches, otherwise entries of
+  /// data objects of the matches, see `FileReader::new`.
+  lists: Vec<ListPosition>,
+  direction: Direction,
+
+  /// Data object offsets of match groups, see `JournalQuery::matches`. Every field of a group
+  /// has a list of offsets of its accepted values.
+  match_groups: Vec<Vec<Vec<u64>>>,
+  filtered: bool,
+
+  since: Option<u64>,
+  until: Option<u64>,
+}
+
+/// Where reading of one list of entries got to
+struct ListPosition {
+  entries: EntryArray,
+
+  /// Index of the next entry going forward, or one past it going backward
+  position: u64,
+
+  /// Offset of the next entry, None when there are no more
+  next: Option<u64>,
+}
+
+impl FileReader {
+  /// Returns None when nothing in the file can match
+  fn new(file: JournalFile, query: &JournalQuery) -> Result<Option<FileReader>, JournalError> {
+    if file.n_entries() == 0
+      || !may_contain_cursor_range(&file, query)
+      || !may_contain_time_range(&file, query)
+    {
+      return Ok(None);
+    }
+
+    let filtered = !query.matches.is_empty();
+    let groups = resolve_matches(&file, &query.matches)?;
+    if filtered && groups.is_empty() {
+      return Ok(None);
+    }
+
+    // An entry matching a group has one of the values of every field of the group, so only
+    // entries of the field with the least of them have to be looked at
+    let mut lists = Vec::new();
+    match filtered {
+      false => lists.push(file.entry_array()?),
+      true => {
+        for group in &groups {
+          let field = group
+            .iter()
+            .min_by_key(|values| values.iter().map(|data| data.n_entries).sum::<u64>())
+            .expect("groups aren't empty");
+          for data in field {
+            lists.push(file.data_entry_array(data)?);
+          }
+        }
+      }
+    }
+
+    let match_groups = groups
+      .into_iter()
+      .map(|group| {
+        group
+          .into_iter()
+          .map(|values| values.into_iter().map(|data| data.offset).collect())
+          .collect()
+      })
+      .collect();
+
+    let mut reader = FileReader {
+      file,
+      lists: Vec::new(),
+      direction: query.direction,
+      match_groups,
+      filtered,
+      since: query.since,
+      until: query.until,
+    };
+
+    for entries in lists {
+      let position = match (query.direction, &query.cursor) {
+        (Direction::Forward, None) => match query.since {
+          Some(since) => reader.partition_point(&entries, |key| key.realtime < since),
+          None => 0,
+        },
+        (Direction::Backward, None) => match query.until {
+          Some(until) => reader.partition_point(&entries, |key| key.realtime <= until),
+          None => entries.len(),
+        },
+        // Index of the first entry which comes after the cursor position
+        (direction, Some(cursor)) => {
+          reader.partition_point(&entries, |key| match key.compare(cursor) {
+            Ordering::Less => true,
+            Ordering::Equal => query.exclude_cursor == (direction == Direction::Forward),
+            Ordering::Greater => false,
+          })
+        }
+      };
+
+      let mut list = ListPosition {
+        entries,
+        position,
+        next: None,
+      };
+      list.next = reader.peek(&list);
+      reader.lists.push(list);
+    }
+
+    Ok(Some(reader))
+  }
+
+  /// Binary search over entries, `is_before` has to be true for a prefix of entries
+  fn partition_point(&self, entries: &EntryArray, is_before: impl Fn(&EntryKey) -> bool) -> u64 {
+    let (mut low, mut high) = (0, entries.len());
+
+    while low < high {
+      let middle = low + (high - low) / 2;
+      let before = match self
+        .file
+        .entry_array_item(entries, middle)
+        .and_then(|offset| self.file.read_entry(offset))
+      {
+        Ok(entry) => is_before(&entry.key),
+        // Unreadable entries are most likely at the end of a file being written
+        Err(_) => false,
+      };
+
+      if before {
+        low = middle + 1;
+      } else {
+        high = middle;
+      }
+    }
+
+    low
+  }
+
+  /// Offset of the entry at the position of the list in the reading direction
+  fn peek(&self, list: &ListPosition) -> Option<u64> {
+    let index = match self.direction {
+      Direction::Forward if list.position < list.entries.len() => list.position,
+      Direction::Backward if list.position > 0 => list.position - 1,
+      _ => return None,
+    };
+
+    match self.file.entry_array_item(&list.entries, index) {
+      Ok(offset) => Some(offset),
+      Err(err) => {
+        warn!("Stopping at unreadable journal entry array: {}", err);
+        None
+      }
+    }
+  }
+
+  /// Takes the next entry of all lists. Entries are appended to the file, so their order in
+  /// the file is the order of their offsets.
+  fn next_offset(&mut self) -> Option<u64> {
+    let heads = self.lists.iter().filter_map(|list| list.next);
+    let offset = match self.direction {
+      Direction::Forward => heads.min()?,
+      Direction::Backward => heads.max()?,
+    };
+
+    // An entry can be in more than one list when it matches more than one group
+    for index in 0..self.lists.len() {
+      if self.lists[index].next != Some(offset) {
+        continue;
+      }
+      match self.direction {
+        Direction::Forward => self.lists[index].position += 1,
+        Direction::Backward => self.lists[index].position -= 1,
+      }
+      self.lists[index].next = self.peek(&self.lists[index]);
+    }
+
+    Some(offset)
+  }
+
+  fn next(&mut self) -> Option<EntryObject> {
+    loop {
+      let offset = self.next_offset()?;
+
+      let entry = match self.file.read_entry(offset) {
+        Ok(entry) => entry,
+        Err(err) => {
+          warn!("Stopping at unreadable journal entry: {}", err);
+          return None;
+        }
+      };
+
+      // Wall clock only grows within a file, apart from clock changes which are ignored here
+      // the same way journalctl does
+      let realtime = entry.key.realtime;
+      match self.direction {
+        Direction::Forward if self.until.is_some_and(|until| realtime > until) => return None,
+        Direction::Backward if self.since.is_some_and(|since| realtime < since) => return None,
+        _ => {}
+      }
+      if self.since.is_some_and(|since| realtime < since)
+        || self.until.is_some_and(|until| realtime > until)
+      {
+        continue;
+      }
+
+      if self.matches(&entry) {
+        return Some(entry);
+      }
+    }
+  }
+
+  fn matches(&self, entry: &EntryObject) -> bool {
+    if !self.filtered {
+      return true;
+    }
+
+    self.match_groups.iter().any(|group| {
+      group.iter().all(|alternatives| {
+        alternatives
+          .iter()
+          .any(|offset| entry.data_offsets.contains(offset))
+      })
+    })
+  }
+
+  fn read_fields(&self, entry: EntryObject) -> Result<JournalEntry, JournalError> {
+    let mut fields = Vec::with_capacity(entry.data_offsets.len());
+
+    for offset in entry.data_offsets {
+      let payload = self.file.read_data(offset)?;
+      let separator = payload.iter().position(|byte| *byte == b'=');
+
+      if let Some(separator) = separator {
+        let name = String::from_utf8_lossy(&payload[..separator]).into_owned();
+        fields.push((name, payload[separator + 1..].to_vec()));
+      }
+    }
+
+    Ok(JournalEntry {
+      key: entry.key,
+      fields,
+    })
+  }
+}
+
+/// Uses file header to rule out files which are entirely before or after the cursor
+fn may_contain_cursor_range(file: &JournalFile, query: &JournalQuery) -> bool {
+  let cursor = match &query.cursor {
+    Some(cursor) => cursor,
+    None => return true,
+  };
+
+  let (head, tail) = file.seqnum_range();
+  if file.seqnum_id() != cursor.seqnum_id {
+    let (head, tail) = file.realtime_range();
+    return match query.direction {
+      Direction::Forward => tail >= cursor.realtime,
+      Direction::Backward => head <= cursor.realtime,
+    };
+  }
+
+  match query.direction {
+    Direction::Forward => tail > cursor.seqnum || (tail == cursor.seqnum && !query.exclude_cursor),
+    Direction::Backward => head < cursor.seqnum || (head == cursor.seqnum && !query.exclude_cursor),
+  }
+}
+
+/// Uses file header to rule out files written entirely outside of the queried time range
+fn may_contain_time_range(file: &JournalFile, query: &JournalQuery) -> bool {
+  let (head, tail) = file.realtime_range();
+  query.since.is_none_or(|since| tail >= since) && query.until.is_none_or(|until| head <= until)
+}
+
+/// Looks up data objects for every match. Groups with a field which has none of its values in
+/// the file can't match anything, so they're left out.
+fn resolve_matches(
+  file: &JournalFile,
+  matches: &[Vec<(String, String)>],
+) -> Result<Vec<Vec<Vec<FieldData>>>, JournalError> {
+  let mut groups = Vec::new();
+  let mut field_data: Vec<(&str, Vec<FieldData>)> = Vec::new();
+
+  'groups: for group in matches {
+    let mut fields: Vec<(&str, Vec<FieldData>)> = Vec::new();
+
+    for (field, value) in group {
+      if !field_data.iter().any(|(name, _)| name == field) {
+        field_data.push((field, file.field_data(field.as_bytes())?));
+      }
+      let (_, values) = field_data.iter().find(|(name, _)| name == field).unwrap();
+
+      let expected = format!("{}={}", field, value).into_bytes();
+      let data = values.iter().find(|data| data.payload == expected).cloned();
+
+      let alternatives = match fields.iter_mut().find(|(name, _)| name == field) {
+        Some((_, alternatives)) => alternatives,
+        None => {
+          fields.push((field, Vec::new()));
+          &mut fields.last_mut().unwrap().1
+        }
+      };
+      alternatives.extend(data);
+    }
+
+    if fields
+      .iter()
+      .any(|(_, alternatives)| alternatives.is_empty())
+    {
+      continue 'groups;
+    }
+    groups.push(fields.into_iter().map(|(_, data)| data).collect());
+  }
+
+  Ok(groups)
+}
+
+fn subdirectories(path: &Path) -> Vec<PathBuf> {
+  fs::read_dir(path)
+    .map(|entries| {
+      entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Formats cursor the same way as sd-journal, so cursors are interchangeable with journalctl
+pub fn format_cursor(key: &EntryKey) -> String {
+  format!(
+    "s={};i={:x};b={};m={:x};t={:x};x={:x}",
+    hex(&key.seqnum_id),
+    key.seqnum,
+    hex(&key.boot_id),
+    key.monotonic,
+    key.realtime,
+    key.xor_hash
+  )
+}
+
+pub fn parse_cursor(cursor: &str) -> Result<EntryKey, JournalError> {
+  let invalid = || JournalError::InvalidCursor(cursor.to_owned());
+
+  let (mut seqnum_id, mut seqnum, mut boot_id) = (None, None, None);
+  let (mut monotonic, mut realtime, mut xor_hash) = (None, None, None);
+
+  for part in cursor.split(';') {
+    let (name, value) = part.split_once('=').ok_or_else(invalid)?;
+    match name {
+      "s" => seqnum_id = Some(parse_id128(value).ok_or_else(invalid)?),
+      "i" => seqnum = Some(u64::from_str_radix(value, 16).map_err(|_| invalid())?),
+      "b" => boot_id = Some(parse_id128(value).ok_or_else(invalid)?),
+      "m" => monotonic = Some(u64::from_str_radix(value, 16).map_err(|_| invalid())?),
+      "t" => realtime = Some(u64::from_str_radix(value, 16).map_err(|_| invalid())?),
+      "x" => xor_hash = Some(u64::from_str_radix(value, 16).map_err(|_| invalid())?),
+      _ => {}
+    }
+  }
+
+  Ok(EntryKey {
+    seqnum_id: seqnum_id.ok_or_else(invalid)?,
+    seqnum: seqnum.ok_or_else(invalid)?,
+    boot_id: boot_id.ok_or_else(invalid)?,
+    monotonic: monotonic.ok_or_else(invalid)?,
+    realtime: realtime.ok_or_else(invalid)?,
+    xor_hash: xor_hash.unwrap_or(0),
+  })
+}
+
+pub fn hex(id: &[u8; 16]) -> String {
+  id.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn parse_id128(value: &str) -> Option<[u8; 16]> {
+  if value.len() != 32 {
+    return None;
+  }
+
+  let mut id = [0u8; 16];
+  for (i, byte) in id.iter_mut().enumerate() {
+    *byte = u8::from_str_radix(value.get(i * 2..i * 2 + 2)?, 16).ok()?;
+  }
+  Some(id)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const BOOT_A: [u8; 16] = [
+    0x0a, 0x0a, 0x0a, 0x0a, 0x0a, 0x0a, 0x4a, 0x0a, 0x8a, 0x0a, 0x0a, 0x0a, 0x0a, 0x0a, 0x0a, 0x0a,
+  ];
+  const BOOT_B: [u8; 16] = [
+    0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x4b, 0x0b, 0x8b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b, 0x0b,
+  ];
+
+  /// Journal of two boots, rotated once during the first one. See
+  /// `tests/fixtures/journal/generate.py` for what was logged.
+  fn machine() -> JournalDirectories {
+    JournalDirectories::new(vec![
+      Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/journal/machine")
+    ])
+  }
+
+  fn forward() -> JournalQuery {
+    JournalQuery {
+      direction: Direction::Forward,
+      ..Default::default()
+    }
+  }
+
+  fn matching(matches: &[&[(&str, &str)]]) -> JournalQuery {
+    JournalQuery {
+      matches: matches
+        .iter()
+        .map(|group| {
+          group
+            .iter()
+            .map(|(field, value)| (field.to_string(), value.to_string()))
+            .collect()
+        })
+        .collect(),
+      ..forward()
+    }
+  }
+
+  fn read(query: &JournalQuery) -> Vec<JournalEntry> {
+    machine().read(query).unwrap()
+  }
+
+  fn seqnums(entries: &[JournalEntry]) -> Vec<u64> {
+    entries.iter().map(|entry| entry.key.seqnum).collect()
+  }
+
+  fn messages(entries: &[JournalEntry]) -> Vec<String> {
+    entries
+      .iter()
+      .map(|entry| String::from_utf8_lossy(entry.field("MESSAGE").unwrap()).into_owned())
+      .collect()
+  }
+
+  fn entry(seqnum: u64) -> JournalEntry {
+    read(&forward())
+      .into_iter()
+      .find(|entry| entry.key.seqnum == seqnum)
+      .unwrap()
+  }
+
+  fn pairs(group: &[(&str, &str)]) -> Vec<(String, String)> {
+    group
+      .iter()
+      .map(|(field, value)| (field.to_string(), value.to_string()))
+      .collect()
+  }
+
+  #[test]
+  fn and_matches_combines_every_pair_of_groups() {
+    let left = vec![pairs(&[("A", "1")]), pairs(&[("B", "1")])];
+    let right = vec![pairs(&[("C", "1")]), pairs(&[("D", "1")])];
+
+    assert_eq!(
+      and_matches(left.clone(), right.clone()),
+      vec![
+        pairs(&[("A", "1"), ("C", "1")]),
+        pairs(&[("A", "1"), ("D", "1")]),
+        pairs(&[("B", "1"), ("C", "1")]),
+        pairs(&[("B", "1"), ("D", "1")]),
+      ]
+    );
+    assert_eq!(and_matches(left.clone(), Vec::new()), left);
+    assert_eq!(and_matches(Vec::new(), right.clone()), right);
+  }
+
+  #[test]
+  fn and_matches_intersects_values_of_shared_fields() {
+    // Like unit matches, one group of which has _PID=1, combined with a pid filter
+    let left = vec![
+      pairs(&[("_SYSTEMD_UNIT", "a.service")]),
+      pairs(&[("_PID", "1"), ("UNIT", "a.service")]),
+      pairs(&[("PRIORITY", "3"), ("PRIORITY", "4"), ("PRIORITY", "5")]),
+    ];
+    let right = vec![pairs(&[
+      ("_PID", "123"),
+      ("PRIORITY", "4"),
+      ("PRIORITY", "5"),
+    ])];
+
+    assert_eq!(
+      and_matches(left, right),
+      vec![
+        pairs(&[
+          ("_SYSTEMD_UNIT", "a.service"),
+          ("_PID", "123"),
+          ("PRIORITY", "4"),
+          ("PRIORITY", "5")
+        ]),
+        pairs(&[("PRIORITY", "4"), ("PRIORITY", "5"), ("_PID", "123")]),
+      ]
+    );
+  }
+
+  #[test]
+  fn and_matches_drops_contradicting_groups() {
+    let left = vec![pairs(&[("_PID", "1")])];
+    let right = vec![pairs(&[("_PID", "2")])];
+    assert!(and_matches(left, right).is_empty());
+  }
+
+  #[test]
+  fn reads_all_files_in_order() {
+    let entries = read(&forward());
+    assert_eq!(seqnums(&entries), (1..=31).collect::<Vec<_>>());
+
+    let entries = read(&JournalQuery::default());
+    assert_eq!(seqnums(&entries), (1..=31).rev().collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn reads_fields() {
+    let entry = entry(3);
+    assert_eq!(entry.field("MESSAGE"), Some(&b"app started on boot A"[..]));
+    assert_eq!(entry.field("FIXTURE_STEP"), Some(&b"1"[..]));
+    assert_eq!(
+      entry.field("_SYSTEMD_UNIT"),
+      Some(&b"fixture-app.service"[..])
+    );
+    assert_eq!(entry.field("NO_SUCH_FIELD"), None);
+    assert_eq!(entry.key.boot_id, BOOT_A);
+  }
+
+  #[test]
+  fn limits_entries() {
+    let query = JournalQuery {
+      limit: Some(3),
+      ..Default::default()
+    };
+    assert_eq!(seqnums(&read(&query)), vec![31, 30, 29]);
+
+    let query = JournalQuery {
+      limit: Some(2),
+      ..forward()
+    };
+    assert_eq!(seqnums(&read(&query)), vec![1, 2]);
+  }
+
+  #[test]
+  fn matches_field() {
+    let entries = read(&matching(&[&[("_SYSTEMD_UNIT", "fixture-app.service")]]));
+    assert_eq!(entries.len(), 15);
+    assert!(entries
+      .iter()
+      .all(|entry| entry.field("_SYSTEMD_UNIT") == Some(&b"fixture-app.service"[..])));
+  }
+
+  #[test]
+  fn matches_any_value_of_a_field() {
+    let entries = read(&matching(&[&[("PRIORITY", "3"), ("PRIORITY", "4")]]));
+    assert_eq!(
+      messages(&entries),
+      ["app is low on disk space", "worker failed to connect"].repeat(3)
+    );
+  }
+
+  #[test]
+  fn matches_all_fields_of_a_group() {
+    let entries = read(&matching(&[&[
+      ("_SYSTEMD_UNIT", "fixture-app.service"),
+      ("_UID", "65534"),
+    ]]));
+    assert_eq!(
+      messages(&entries),
+      ["worker ready", "worker failed to connect"].repeat(3)
+    );
+  }
+
+  #[test]
+  fn matches_any_group() {
+    let entries = read(&matching(&[
+      &[("_SYSTEMD_UNIT", "fixture-other.service")],
+      &[("OBJECT_SYSTEMD_UNIT", "fixture-app.service")],
+    ]));
+    assert_eq!(
+      messages(&entries),
+      [
+        "other unit says hi",
+        "fixture-app.service: Consumed 1.000s CPU time."
+      ]
+      .repeat(3)
+    );
+  }
+
+  #[test]
+  fn matches_nothing_for_unknown_values() {
+    assert!(read(&matching(&[&[("_SYSTEMD_UNIT", "missing.service")]])).is_empty());
+    assert!(read(&matching(&[&[("NO_SUCH_FIELD", "1")]])).is_empty());
+    assert!(read(&matching(&[&[
+      ("_SYSTEMD_UNIT", "fixture-other.service"),
+      ("_UID", "65534"),
+    ]]))
+    .is_empty());
+  }
+
+  #[test]
+  fn filters_by_time() {
+    // Journal of the second boot started with entry 21
+    let since = entry(21).key.realtime;
+    let query = JournalQuery {
+      since: Some(since),
+      ..forward()
+    };
+    assert_eq!(seqnums(&read(&query)), (21..=31).collect::<Vec<_>>());
+
+    let until = entry(10).key.realtime;
+    let query = JournalQuery {
+      until: Some(until),
+      ..Default::default()
+    };
+    assert_eq!(seqnums(&read(&query)), (1..=10).rev().collect::<Vec<_>>());
+
+    let query = JournalQuery {
+      since: Some(entry(5).key.realtime),
+      until: Some(entry(7).key.realtime),
+      ..forward()
+    };
+    assert_eq!(seqnums(&read(&query)), vec![5, 6, 7]);
+  }
+
+  #[test]
+  fn greps_messages() {
+    let query = JournalQuery {
+      grep: Some(Regex::new("^worker").unwrap()),
+      ..forward()
+    };
+    assert_eq!(
+      messages(&read(&query)),
+      ["worker ready", "worker failed to connect"].repeat(3)
+    );
+  }
+
+  #[test]
+  fn starts_at_cursor() {
+    // Entry 11 is the first one of the file after rotation
+    let cursor = parse_cursor(&entry(11).cursor()).unwrap();
+    let query = JournalQuery {
+      cursor: Some(cursor),
+      limit: Some(2),
+      ..forward()
+    };
+    assert_eq!(seqnums(&read(&query)), vec![11, 12]);
+
+    let query = JournalQuery {
+      exclude_cursor: true,
+      ..query
+    };
+    assert_eq!(seqnums(&read(&query)), vec![12, 13]);
+
+    let query = JournalQuery {
+      cursor: Some(cursor),
+      limit: Some(2),
+      ..Default::default()
+    };
+    assert_eq!(seqnums(&read(&query)), vec![11, 10]);
+
+    let query = JournalQuery {
+      exclude_cursor: true,
+      ..query
+    };
+    assert_eq!(seqnums(&read(&query)), vec![10, 9]);
+  }
+
+  #[test]
+  fn cursor_round_trip() {
+    let entry = entry(12);
+    let cursor = entry.cursor();
+    assert!(cursor.starts_with(&format!(
+      "s={};i=c;b={};",
+      hex(&entry.key.seqnum_id),
+      hex(&BOOT_A)
+    )));
+    assert!(parse_cursor(&cursor).unwrap() == entry.key);
+
+    // Fields can come in any order and the hash is optional
+    let key = parse_cursor(
+      "b=0a0a0a0a0a0a4a0a8a0a0a0a0a0a0a0a;i=1f;t=10;m=20;s=000102030405060708090a0b0c0d0e0f",
+    )
+    .unwrap();
+    assert_eq!(key.seqnum, 0x1f);
+    assert_eq!(key.boot_id, BOOT_A);
+    assert_eq!(key.realtime, 0x10);
+    assert_eq!(key.monotonic, 0x20);
+    assert_eq!(key.xor_hash, 0);
+  }
+
+  #[test]
+  fn rejects_invalid_cursors() {
+    let valid = entry(1).cursor();
+    for cursor in [
+      "",
+      "garbage",
+      &valid.replace("i=1;", ""),
+      &valid.replace("i=1;", "i=zz;"),
+      &valid.replace("b=", "b=0"),
+    ] {
+      assert!(
+        matches!(parse_cursor(cursor), Err(JournalError::InvalidCursor(_))),
+        "{}",
+        cursor
+      );
+    }
+  }
+
+  #[test]
+  fn skips_broken_files() {
+    // Next to plain.journal are a truncated copy and a corrupt one, which breaks after the
+    // first entry. Entries in more than one file are returned once.
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/journal");
+    let source = JournalDirectories::new(vec![directory]);
+
+    let entries = source.read(&forward()).unwrap();
+    assert_eq!(seqnums(&entries), (1..=11).collect::<Vec<_>>());
+    assert_eq!(source.boots().unwrap().len(), 1);
+  }
+
+  #[test]
+  fn lists_boots_of_readable_files() {
+    // corrupt.journal, with the first entry of its _BOOT_ID data pointing nowhere, next to
+    // plain.journal
+    let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/journal");
+    let directory = std::env::temp_dir().join(format!("dragond-{}-boots", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::copy(
+      fixtures.join("plain.journal"),
+      directory.join("plain.journal"),
+    )
+    .unwrap();
+    let broken = directory.join("broken.journal");
+    std::fs::copy(fixtures.join("corrupt.journal"), &broken).unwrap();
+
+    let data = JournalFile::open(&broken)
+      .unwrap()
+      .field_data(b"_BOOT_ID")
+      .unwrap();
+    let mut content = std::fs::read(&broken).unwrap();
+    // Object header, hash, next hash and next field offset come before the first entry
+    let at = data[0].offset as usize + 40;
+    content[at..at + 8].copy_from_slice(&1u64.to_le_bytes());
+    std::fs::write(&broken, content).unwrap();
+
+    let broken_boots = file_boots(&JournalFile::open(&broken).unwrap());
+    let boots = JournalDirectories::new(vec![directory.clone()]).boots();
+    std::fs::remove_dir_all(&directory).unwrap();
+
+    assert!(broken_boots.is_err());
+    assert_eq!(boots.unwrap().len(), 1);
+  }
+
+  #[test]
+  fn lists_boots() {
+    let boots = machine().boots().unwrap();
+
+    assert_eq!(
+      boots.iter().map(|boot| boot.id).collect::<Vec<_>>(),
+      vec![BOOT_A, BOOT_B]
+    );
+    assert_eq!(boots[0].first_realtime, entry(1).key.realtime);
+    assert_eq!(boots[1].first_realtime, entry(21).key.realtime);
+  }
+}
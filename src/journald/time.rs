@@ -0,0 +1,463 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const USEC_PER_SEC: u64 = 1_000_000;
+const USEC_PER_DAY: u64 = 24 * 60 * 60 * USEC_PER_SEC;
+
+/// Current time in microseconds since the epoch
+pub fn now_usec() -> u64 {
+  SystemTime::now()
+    .duration_since(UNIX_EPOCH)
+    .map(|duration| duration.as_micros() as u64)
+    .unwrap_or(0)
+}
+
+/// Parses a subset of timestamps journalctl accepts, returning microseconds since the epoch:
+///
+/// - `now`, `today`, `yesterday`, `tomorrow`
+/// - `@1674000000` seconds since the epoch, fractions allowed
+/// - `-1h`, `+30min`, `2d 4h ago` relative to now
+/// - `2023-01-17`, `2023-01-17 12:30`, `2023-01-17T12:30:15.5+01:00`, `12:30` (today)
+///
+/// Same as journalctl, dates and times without `Z`, `UTC` or an offset are local time.
+pub fn parse_timestamp(timestamp: &str, now: u64) -> Result<u64, String> {
+  parse_timestamp_in(timestamp, now, &LocalTime)
+}
+
+/// Converts local dates and times of a timezone to seconds since the epoch and back
+trait TimeZone {
+  /// Seconds since the epoch at `second` of `day` of the month. Days and seconds out of
+  /// range roll over into the next or previous month or day.
+  fn to_epoch(&self, year: i64, month: u32, day: i64, second: i64) -> Option<i64>;
+
+  /// Year, month and day at seconds since the epoch
+  fn date(&self, seconds: i64) -> (i64, u32, u32);
+}
+
+/// Timezone of the machine, the C library looks it up from `TZ` and /etc/localtime
+struct LocalTime;
+
+impl TimeZone for LocalTime {
+  fn to_epoch(&self, year: i64, month: u32, day: i64, second: i64) -> Option<i64> {
+    // SAFETY: tm is plain data, mktime only reads and normalizes it
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    tm.tm_year = libc::c_int::try_from(year - 1900).ok()?;
+    tm.tm_mon = month as libc::c_int - 1;
+    tm.tm_mday = libc::c_int::try_from(day).ok()?;
+    tm.tm_sec = libc::c_int::try_from(second).ok()?;
+    // Let the timezone rules decide if daylight saving time applies
+    tm.tm_isdst = -1;
+
+    match unsafe { libc::mktime(&mut tm) } {
+      -1 => None,
+      seconds => Some(seconds),
+    }
+  }
+
+  fn date(&self, seconds: i64) -> (i64, u32, u32) {
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let time = seconds as libc::time_t;
+
+    // SAFETY: both pointers are valid for the duration of the call
+    match unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+      true => civil_from_days(seconds.div_euclid(86400)),
+      false => (
+        tm.tm_year as i64 + 1900,
+        tm.tm_mon as u32 + 1,
+        tm.tm_mday as u32,
+      ),
+    }
+  }
+}
+
+fn parse_timestamp_in(timestamp: &str, now: u64, zone: &impl TimeZone) -> Result<u64, String> {
+  let invalid = || format!("Invalid timestamp {}", timestamp);
+  let timestamp = timestamp.trim();
+
+  let today = zone.date((now / USEC_PER_SEC) as i64);
+  let midnight = |days: i64| {
+    let (year, month, day) = today;
+    zone
+      .to_epoch(year, month, day as i64 + days, 0)
+      .and_then(epoch_usec)
+      .ok_or_else(invalid)
+  };
+
+  match timestamp {
+    "now" => return Ok(now),
+    "today" => return midnight(0),
+    "yesterday" => return midnight(-1),
+    "tomorrow" => return midnight(1),
+    _ => {}
+  }
+
+  if let Some(seconds) = timestamp.strip_prefix('@') {
+    let seconds: f64 = seconds.parse().map_err(|_| invalid())?;
+    let usec = seconds * USEC_PER_SEC as f64;
+    if !(0.0..u64::MAX as f64).contains(&usec) {
+      return Err(invalid());
+    }
+    return Ok(usec as u64);
+  }
+
+  if let Some(span) = timestamp.strip_prefix('-') {
+    return Ok(now.saturating_sub(parse_timespan(span).ok_or_else(invalid)?));
+  }
+  if let Some(span) = timestamp.strip_suffix(" ago") {
+    return Ok(now.saturating_sub(parse_timespan(span).ok_or_else(invalid)?));
+  }
+  if let Some(span) = timestamp.strip_prefix('+') {
+    return now
+      .checked_add(parse_timespan(span).ok_or_else(invalid)?)
+      .ok_or_else(invalid);
+  }
+
+  parse_datetime(timestamp, today, zone).ok_or_else(invalid)
+}
+
+/// Microseconds since the epoch, None before the epoch
+fn epoch_usec(seconds: i64) -> Option<u64> {
+  u64::try_from(seconds).ok()?.checked_mul(USEC_PER_SEC)
+}
+
+/// Parses spans like `1h 30min` or `2d`, in microseconds
+fn parse_timespan(span: &str) -> Option<u64> {
+  let mut total = 0u64;
+  let mut rest = span.trim();
+  if rest.is_empty() {
+    return None;
+  }
+
+  while !rest.is_empty() {
+    let number_end = rest
+      .find(|c: char| !c.is_ascii_digit() && c != '.')
+      .unwrap_or(rest.len());
+    let number: f64 = rest[..number_end].parse().ok()?;
+    rest = rest[number_end..].trim_start();
+
+    let unit_end = rest
+      .find(|c: char| !c.is_ascii_alphabetic())
+      .unwrap_or(rest.len());
+    let multiplier = match &rest[..unit_end] {
+      "us" | "usec" => 1,
+      "ms" | "msec" => 1_000,
+      "" | "s" | "sec" | "second" | "seconds" => USEC_PER_SEC,
+      "m" | "min" | "minute" | "minutes" => 60 * USEC_PER_SEC,
+      "h" | "hr" | "hour" | "hours" => 60 * 60 * USEC_PER_SEC,
+      "d" | "day" | "days" => USEC_PER_DAY,
+      "w" | "week" | "weeks" => 7 * USEC_PER_DAY,
+      // Same average lengths systemd uses
+      "M" | "month" | "months" => 2_629_800 * USEC_PER_SEC,
+      "y" | "year" | "years" => 31_557_600 * USEC_PER_SEC,
+      _ => return None,
+    };
+    rest = rest[unit_end..].trim_start();
+
+    let value = number * multiplier as f64;
+    if value >= u64::MAX as f64 {
+      return None;
+    }
+    total = total.checked_add(value as u64)?;
+  }
+
+  Some(total)
+}
+
+/// Parses `YYYY-MM-DD[( |T)HH:MM[:SS[.frac]]][Z|UTC|±HH:MM]` or a bare time of `today`
+fn parse_datetime(datetime: &str, today: (i64, u32, u32), zone: &impl TimeZone) -> Option<u64> {
+  let (datetime, offset) = split_timezone(datetime)?;
+
+  let (date, time) = match datetime.split_once(['T', ' ']) {
+    Some((date, time)) => (Some(date), Some(time)),
+    None if datetime.contains(':') => (None, Some(datetime)),
+    None => (Some(datetime), None),
+  };
+
+  let (year, month, day) = match date {
+    Some(date) => {
+      let mut parts = date.split('-');
+      let year: i64 = parts.next()?.parse().ok()?;
+      let month: u32 = parts.next()?.parse().ok()?;
+      let day: u32 = parts.next()?.parse().ok()?;
+      if parts.next().is_some()
+        || !(1..=9999).contains(&year)
+        || !(1..=12).contains(&month)
+        || day < 1
+        || day > days_in_month(year, month)
+      {
+        return None;
+      }
+      (year, month, day)
+    }
+    None => today,
+  };
+
+  let (second, usec) = match time {
+    Some(time) => {
+      let mut parts = time.split(':');
+      let hours: i64 = parts.next()?.parse().ok()?;
+      let minutes: i64 = parts.next()?.parse().ok()?;
+      let seconds: f64 = parts.next().map_or(Some(0.0), |s| s.parse().ok())?;
+      if parts.next().is_some()
+        || !(0..=23).contains(&hours)
+        || !(0..=59).contains(&minutes)
+        || !(0.0..61.0).contains(&seconds)
+      {
+        return None;
+      }
+      let usec = (seconds * USEC_PER_SEC as f64) as u64;
+      (
+        hours * 3600 + minutes * 60 + (usec / USEC_PER_SEC) as i64,
+        usec % USEC_PER_SEC,
+      )
+    }
+    None => (0, 0),
+  };
+
+  let seconds = match offset {
+    Some(offset) => (days_from_civil(year, month, day) * 86400 + second).checked_sub(offset)?,
+    None => zone.to_epoch(year, month, day as i64, second)?,
+  };
+  epoch_usec(seconds)?.checked_add(usec)
+}
+
+/// Splits timezone off the end of a timestamp, returning its offset from UTC in seconds. No
+/// offset means local time.
+fn split_timezone(datetime: &str) -> Option<(&str, Option<i64>)> {
+  if let Some(rest) = datetime.strip_suffix('Z') {
+    return Some((rest, Some(0)));
+  }
+  if let Some(rest) = datetime.strip_suffix(" UTC") {
+    return Some((rest, Some(0)));
+  }
+
+  // `±HH:MM` after the time, minus signs in the date part are not an offset
+  let time_start = datetime.find(['T', ' ']).map_or(0, |index| index + 1);
+  let sign_index = datetime[time_start..]
+    .rfind(['+', '-'])
+    .map(|index| index + time_start);
+
+  match sign_index {
+    Some(index) if datetime[..index].contains(':') => {
+      let sign = if datetime[index..].starts_with('-') {
+        -1
+      } else {
+        1
+      };
+      let (hours, minutes) = datetime[index + 1..].split_once(':')?;
+      let hours: i64 = hours.parse().ok()?;
+      let minutes: i64 = minutes.parse().ok()?;
+      if !(0..=23).contains(&hours) || !(0..=59).contains(&minutes) {
+        return None;
+      }
+      Some((&datetime[..index], Some(sign * (hours * 60 + minutes) * 60)))
+    }
+    _ => Some((datetime, None)),
+  }
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+  let next = match month {
+    12 => days_from_civil(year + 1, 1, 1),
+    _ => days_from_civil(year, month + 1, 1),
+  };
+  (next - days_from_civil(year, month, 1)) as u32
+}
+
+/// Number of days since 1970-01-01, see http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+  let year = if month <= 2 { year - 1 } else { year };
+  let era = if year >= 0 { year } else { year - 399 } / 400;
+  let year_of_era = year - era * 400;
+  let month = month as i64;
+  let day_of_year =
+    (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+  let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+  era * 146097 + day_of_era - 719468
+}
+
+/// Date for a number of days since 1970-01-01, inverse of `days_from_civil`
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+  let days = days + 719468;
+  let era = if days >= 0 { days } else { days - 146096 } / 146097;
+  let day_of_era = days - era * 146097;
+  let year_of_era =
+    (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+  let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+  let month_index = (5 * day_of_year + 2) / 153;
+  let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+  let month = if month_index < 10 {
+    month_index + 3
+  } else {
+    month_index - 9
+  } as u32;
+  let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+  (year, month, day)
+}
+
+/// Formats microseconds since the epoch as RFC 3339 in UTC, i.e. `2023-01-17T12:30:15.123456Z`
+pub fn format_rfc3339(usec: u64) -> String {
+  let seconds = usec / USEC_PER_SEC;
+  let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+  let second_of_day = seconds % 86400;
+
+  format!(
+    "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+    year,
+    month,
+    day,
+    second_of_day / 3600,
+    second_of_day / 60 % 60,
+    second_of_day % 60,
+    usec % USEC_PER_SEC
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Timezone at a fixed offset from UTC, in seconds
+  struct FixedOffset(i64);
+
+  impl TimeZone for FixedOffset {
+    fn to_epoch(&self, year: i64, month: u32, day: i64, second: i64) -> Option<i64> {
+      Some((days_from_civil(year, month, 1) + day - 1) * 86400 + second - self.0)
+    }
+
+    fn date(&self, seconds: i64) -> (i64, u32, u32) {
+      civil_from_days((seconds + self.0).div_euclid(86400))
+    }
+  }
+
+  const HOUR: u64 = 3600 * USEC_PER_SEC;
+
+  /// 2023-01-17T23:30:00Z, already the 18th at +02:00
+  const NOW: u64 = 1_673_998_200 * USEC_PER_SEC;
+
+  /// 2023-01-17T00:00:00Z
+  const JAN_17: u64 = 1_673_913_600 * USEC_PER_SEC;
+
+  fn parse(timestamp: &str) -> Result<u64, String> {
+    parse_timestamp_in(timestamp, NOW, &FixedOffset(2 * 3600))
+  }
+
+  #[test]
+  fn parses_keywords_in_local_time() {
+    assert_eq!(parse("now"), Ok(NOW));
+    assert_eq!(parse("today"), Ok(JAN_17 + 22 * HOUR));
+    assert_eq!(parse("yesterday"), Ok(JAN_17 - 2 * HOUR));
+    assert_eq!(parse("tomorrow"), Ok(JAN_17 + 46 * HOUR));
+
+    let utc = |timestamp| parse_timestamp_in(timestamp, NOW, &FixedOffset(0));
+    assert_eq!(utc("today"), Ok(JAN_17));
+    assert_eq!(utc("yesterday"), Ok(JAN_17 - 24 * HOUR));
+  }
+
+  #[test]
+  fn parses_epoch_seconds() {
+    assert_eq!(parse("@1673913600"), Ok(JAN_17));
+    assert_eq!(parse("@1673913600.25"), Ok(JAN_17 + 250_000));
+    assert_eq!(parse("@0"), Ok(0));
+    assert!(parse("@-1").is_err());
+    assert!(parse("@1e300").is_err());
+    assert!(parse("@NaN").is_err());
+    assert!(parse("@").is_err());
+  }
+
+  #[test]
+  fn parses_relative_times() {
+    assert_eq!(parse("-1h"), Ok(NOW - HOUR));
+    assert_eq!(parse("+30min"), Ok(NOW + HOUR / 2));
+    assert_eq!(parse("2d 4h ago"), Ok(NOW - 52 * HOUR));
+    assert_eq!(parse("-1h30m"), Ok(NOW - 3 * HOUR / 2));
+    assert_eq!(parse("-10"), Ok(NOW - 10 * USEC_PER_SEC));
+    assert!(parse("-1 fortnight").is_err());
+    assert!(parse("-").is_err());
+  }
+
+  #[test]
+  fn relative_times_dont_overflow() {
+    // Going back further than the epoch stops at it
+    assert_eq!(parse("-100000y"), Ok(0));
+    assert!(parse("+100000000y").is_err());
+    assert!(parse("-1e30y").is_err());
+    assert!(parse_timestamp_in("+1s", u64::MAX - 1, &FixedOffset(0)).is_err());
+  }
+
+  #[test]
+  fn parses_local_dates_and_times() {
+    assert_eq!(parse("2023-01-17"), Ok(JAN_17 - 2 * HOUR));
+    assert_eq!(parse("2023-01-17 12:30"), Ok(JAN_17 + 21 * HOUR / 2));
+    assert_eq!(
+      parse("2023-01-17T12:30:15.5"),
+      Ok(JAN_17 + 21 * HOUR / 2 + 15_500_000)
+    );
+    // Bare times are today, which already is the 18th locally
+    assert_eq!(parse("12:30"), Ok(JAN_17 + 24 * HOUR + 21 * HOUR / 2));
+  }
+
+  #[test]
+  fn parses_dates_with_timezone() {
+    assert_eq!(parse("2023-01-17 12:30 UTC"), Ok(JAN_17 + 25 * HOUR / 2));
+    assert_eq!(parse("2023-01-17T12:30Z"), Ok(JAN_17 + 25 * HOUR / 2));
+    assert_eq!(
+      parse("2023-01-17T12:30:15.5+01:00"),
+      Ok(JAN_17 + 23 * HOUR / 2 + 15_500_000)
+    );
+    assert_eq!(parse("2023-01-17T12:30-05:30"), Ok(JAN_17 + 18 * HOUR));
+  }
+
+  #[test]
+  fn rejects_invalid_dates() {
+    for timestamp in [
+      "2023-02-29",
+      "2023-13-01",
+      "2023-00-10",
+      "2023-01-32",
+      "2023-01-17 24:00",
+      "2023-01-17 12:60",
+      "2023-01-17 12:30:61",
+      "2023-01-17 12:30+24:00",
+      "99999-01-01",
+      "0-01-01",
+      "1969-12-31",
+      "2023-01",
+      "yesterday-ish",
+    ] {
+      assert!(parse(timestamp).is_err(), "{}", timestamp);
+    }
+    assert_eq!(
+      parse("2024-02-29"),
+      Ok(1_709_164_800 * USEC_PER_SEC - 2 * HOUR)
+    );
+    assert_eq!(
+      parse("9999-12-31 23:59:59Z"),
+      Ok(253_402_300_799 * USEC_PER_SEC)
+    );
+  }
+
+  #[test]
+  fn local_time_is_consistent() {
+    // Compares calendar dates, days around daylight saving time changes aren't 24 hours long
+    let date = |usec: u64| LocalTime.date((usec / USEC_PER_SEC) as i64);
+    let now = now_usec();
+    let today = parse_timestamp("today", now).unwrap();
+    let tomorrow = parse_timestamp("tomorrow", now).unwrap();
+
+    assert!(today <= now && now < tomorrow);
+    assert_eq!(date(today), date(now));
+    assert_ne!(date(today - USEC_PER_SEC), date(today));
+    let (year, month, day) = date(now);
+    assert_eq!(
+      date(tomorrow),
+      civil_from_days(days_from_civil(year, month, day) + 1)
+    );
+    assert_eq!(date(tomorrow - USEC_PER_SEC), date(now));
+  }
+
+  #[test]
+  fn formats_rfc3339() {
+    assert_eq!(format_rfc3339(JAN_17 + 1), "2023-01-17T00:00:00.000001Z");
+    assert_eq!(format_rfc3339(0), "1970-01-01T00:00:00.000000Z");
+  }
+}
@@ -3,15 +3,32 @@ extern crate serde_derive;
 
 mod api_errors;
 mod app_state;
+mod audit;
+mod auth;
+mod config;
 mod dbus_interface;
 mod journald;
+mod policy;
+mod polkit;
+mod power;
+mod server;
+mod sse;
 mod systemd;
 
-use crate::app_state::AppState;
+use crate::{
+  api_errors::ApiError,
+  app_state::AppState,
+  audit::Audit,
+  auth::Auth,
+  config::{Config, Overrides},
+  policy::Policy,
+  polkit::Polkit,
+};
 use actix_web::{middleware::Logger, web, App, HttpServer};
 use dbus_interface::DBusInterface;
 use env_logger::Env;
-use std::sync::Mutex;
+use journald::source::JournalDirectories;
+use std::sync::{Arc, Mutex};
 
 #[macro_use]
 extern crate log;
@@ -20,29 +37,178 @@ extern crate log;
 async fn main() -> std::io::Result<()> {
   env_logger::init_from_env(Env::default().default_filter_or("info"));
 
+  if std::env::args().skip(1).any(|arg| arg == "--help") {
+    println!("{}", config::USAGE);
+    return Ok(());
+  }
+  let overrides = Overrides::from_env()
+    .parse_args(std::env::args().skip(1))
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+  let config = Config::load_with_overrides(overrides)
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+  // Takes LISTEN_* out of the environment, which must happen before any thread is started
+  let listeners = server::listeners(&config.server)?;
+  let auth = Auth::new(&config.auth)
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+  if !auth.enabled {
+    warn!("Authentication is turned off, anyone who can connect has full control");
+  }
+  let policy = Policy::new(&config.policy)
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+  if policy.enabled && !auth.enabled {
+    warn!("Policy is turned on without authentication, every request will be denied");
+  }
+  let polkit = Polkit::new(&config.polkit);
+  if polkit.enabled && unsafe { libc::geteuid() } != 0 {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::InvalidInput,
+      "polkit checks need dragond to run as root, polkit only lets root check authorization \
+       for other users",
+    ));
+  }
+
+  let audit = Audit::new(&config.audit)
+    .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+
+  let systemd_events = systemd::events::create_channel();
+  systemd::events::spawn_listener(systemd_events.clone());
+
   let state = AppState {
     dbus: Mutex::new(DBusInterface::new()),
+    systemd_events,
+    journal: Arc::new(JournalDirectories::system()),
+    auth,
+    policy,
+    polkit,
+    audit,
+    power_confirmations: Default::default(),
   };
   let app_data = web::Data::new(state);
 
-  let host = "0.0.0.0";
-  let port = 4444;
+  let tls = match &config.server.tls {
+    Some(tls) => Some(
+      server::tls::server_config(tls)
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?,
+    ),
+    None => None,
+  };
 
-  let server = HttpServer::new(move || {
+  let mut server = HttpServer::new(move || {
     App::new()
       .app_data(web::Data::clone(&app_data))
+      .app_data(
+        web::QueryConfig::default()
+          .error_handler(|err, _| ApiError::Validation(err.to_string()).into()),
+      )
+      .app_data(
+        web::JsonConfig::default()
+          .limit(server::MAX_BODY_SIZE)
+          .error_handler(|err, _| ApiError::Validation(err.to_string()).into()),
+      )
+      .app_data(
+        web::PathConfig::default()
+          .error_handler(|err, _| ApiError::Validation(err.to_string()).into()),
+      )
+      .wrap(polkit::middleware::PolkitAuthorization)
+      .wrap(policy::middleware::Authorization)
+      .wrap(auth::middleware::Authentication)
+      .wrap(audit::middleware::AuditLog)
       .wrap(Logger::new(
         "%a \"%r\" %s %bB \"%{Referer}i\" \"%{User-Agent}i\" %Ts",
       ))
       .service(
         web::scope("/systemd")
+          .service(systemd::routes::manager)
+          .service(systemd::routes::daemon_reload)
+          .service(systemd::routes::daemon_reexec)
+          .service(systemd::routes::set_log_level)
+          .service(systemd::routes::set_log_target)
+          .service(systemd::routes::set_show_status)
+          .service(systemd::routes::set_default_target)
+          .service(systemd::routes::set_environment)
+          .service(systemd::routes::unset_environment)
+          .service(systemd::routes::reset_failed)
           .service(systemd::routes::load_unit)
-          .service(systemd::routes::list_units),
+          .service(systemd::routes::list_units)
+          .service(systemd::routes::start_unit)
+          .service(systemd::routes::stop_unit)
+          .service(systemd::routes::reload_unit)
+          .service(systemd::routes::restart_unit)
+          .service(systemd::routes::try_restart_unit)
+          .service(systemd::routes::reload_or_restart_unit)
+          .service(systemd::routes::list_unit_files)
+          .service(systemd::routes::get_unit_file_state)
+          .service(systemd::routes::enable_unit_files)
+          .service(systemd::routes::disable_unit_files)
+          .service(systemd::routes::reenable_unit_files)
+          .service(systemd::routes::link_unit_files)
+          .service(systemd::routes::preset_unit_files)
+          .service(systemd::routes::mask_unit_files)
+          .service(systemd::routes::unmask_unit_files)
+          .service(systemd::routes::revert_unit_files)
+          .service(systemd::routes::get_unit_config)
+          .service(systemd::routes::put_drop_in)
+          .service(systemd::routes::delete_drop_in)
+          .service(systemd::routes::create_unit_file)
+          .service(systemd::routes::validate_unit_file)
+          .service(systemd::routes::run)
+          .service(systemd::routes::list_timers)
+          .service(systemd::routes::unit_dependencies)
+          .service(systemd::routes::analyze_time)
+          .service(systemd::routes::analyze_blame)
+          .service(systemd::routes::analyze_critical_chain)
+          .service(systemd::routes::analyze_unit_critical_chain)
+          .service(systemd::routes::list_jobs)
+          .service(systemd::routes::get_job)
+          .service(systemd::routes::cancel_job)
+          .service(systemd::routes::clear_jobs)
+          .service(systemd::routes::stream_events),
+      )
+      .service(
+        web::scope("/journald")
+          .service(journald::routes::logs)
+          .service(journald::routes::follow_logs)
+          .service(journald::routes::export_logs)
+          .service(journald::routes::unit_logs)
+          .service(journald::routes::follow_unit_logs),
+      )
+      .service(web::scope("/audit").service(audit::routes::audit_log))
+      .service(
+        web::scope("/auth")
+          .service(auth::routes::login)
+          .service(auth::routes::logout)
+          .service(auth::routes::whoami)
+          .service(auth::routes::list_sessions)
+          .service(auth::routes::revoke_session),
+      )
+      .service(
+        web::scope("/power")
+          .service(power::routes::status)
+          .service(power::routes::list_inhibitors)
+          .service(power::routes::create_confirmation)
+          .service(power::routes::cancel_scheduled_shutdown)
+          .service(power::routes::power_action),
       )
-      .service(web::scope("/journald").service(journald::routes::unit_logs))
   })
-  .bind((host, port))?;
-  info!("Server bound on {}:{}", host, port);
+  .on_connect(server::on_connect);
+
+  for listener in listeners {
+    let address = listener.describe();
+    server = match (listener, &tls) {
+      (server::Listener::Tcp(listener), Some(tls)) => {
+        info!("Listening on {} with TLS", address);
+        server.listen_rustls_0_23(listener, tls.clone())?
+      }
+      (server::Listener::Tcp(listener), None) => {
+        info!("Listening on {}", address);
+        server.listen(listener)?
+      }
+      (server::Listener::Unix(listener), _) => {
+        info!("Listening on {}", address);
+        server.listen_uds(listener)?
+      }
+    };
+  }
 
   server.run().await
 }
@@ -0,0 +1,834 @@
+//! Which action each route performs, and on which units
+
+use std::{collections::HashMap, path::Path};
+
+use actix_web::{dev::ServiceRequest, http::Method, web::Query};
+use serde_json::Value;
+
+use crate::{dbus_interface::DBusInterface, systemd::functions};
+
+/// Where the units a request is about come from
+#[derive(Clone, Copy)]
+enum UnitSource {
+  /// Request isn't about particular units
+  None,
+
+  /// `{unit}` segment of the path
+  Path,
+
+  /// Query parameter
+  Query(&'static str),
+
+  /// Query parameter, without it the request is about every unit
+  QueryOrAll(&'static str),
+
+  /// Field of the JSON body holding a unit name, or a list of unit names and unit file paths
+  Body(&'static str),
+
+  /// Body of `POST /systemd/run`, which names the transient unit without a type suffix
+  Transient,
+
+  /// Unit of the job whose id is the last path segment
+  Job,
+
+  /// Request is about every unit
+  All,
+}
+
+/// Method, path with `{unit}` and `{_}` placeholders, action and where the unit comes from
+const ROUTES: &[(Method, &str, &str, UnitSource)] = &[
+  // systemd, reading
+  (
+    Method::GET,
+    "/systemd/load-unit/{unit}",
+    "unit.read",
+    UnitSource::Path,
+  ),
+  (
+    Method::GET,
+    "/systemd/list-units",
+    "unit.read",
+    UnitSource::None,
+  ),
+  (
+    Method::GET,
+    "/systemd/unit-files",
+    "unit.read",
+    UnitSource::None,
+  ),
+  (
+    Method::GET,
+    "/systemd/unit-files/{unit}",
+    "unit.read",
+    UnitSource::Path,
+  ),
+  (
+    Method::POST,
+    "/systemd/unit-files/validate",
+    "unit.read",
+    UnitSource::None,
+  ),
+  (
+    Method::GET,
+    "/systemd/units/{unit}/config",
+    "unit.read",
+    UnitSource::Path,
+  ),
+  (
+    Method::GET,
+    "/systemd/units/{unit}/dependencies",
+    "unit.read",
+    UnitSource::Path,
+  ),
+  (
+    Method::GET,
+    "/systemd/timers",
+    "unit.read",
+    UnitSource::None,
+  ),
+  // Streams events of every unit, the units filter takes globs
+  (Method::GET, "/systemd/events", "unit.read", UnitSource::All),
+  (
+    Method::GET,
+    "/systemd/analyze/critical-chain/{unit}",
+    "unit.read",
+    UnitSource::Path,
+  ),
+  // systemd, unit jobs
+  (
+    Method::POST,
+    "/systemd/start-unit/{unit}",
+    "unit.start",
+    UnitSource::Path,
+  ),
+  (
+    Method::POST,
+    "/systemd/stop-unit/{unit}",
+    "unit.stop",
+    UnitSource::Path,
+  ),
+  (
+    Method::POST,
+    "/systemd/reload-unit/{unit}",
+    "unit.reload",
+    UnitSource::Path,
+  ),
+  (
+    Method::POST,
+    "/systemd/restart-unit/{unit}",
+    "unit.restart",
+    UnitSource::Path,
+  ),
+  (
+    Method::POST,
+    "/systemd/try-restart-unit/{unit}",
+    "unit.restart",
+    UnitSource::Path,
+  ),
+  (
+    Method::POST,
+    "/systemd/reload-or-restart-unit/{unit}",
+    "unit.restart",
+    UnitSource::Path,
+  ),
+  // systemd, unit files
+  (
+    Method::POST,
+    "/systemd/enable-unit-files",
+    "unit.enable",
+    UnitSource::Body("files"),
+  ),
+  (
+    Method::POST,
+    "/systemd/disable-unit-files",
+    "unit.enable",
+    UnitSource::Body("files"),
+  ),
+  (
+    Method::POST,
+    "/systemd/reenable-unit-files",
+    "unit.enable",
+    UnitSource::Body("files"),
+  ),
+  (
+    Method::POST,
+    "/systemd/link-unit-files",
+    "unit.enable",
+    UnitSource::Body("files"),
+  ),
+  (
+    Method::POST,
+    "/systemd/preset-unit-files",
+    "unit.enable",
+    UnitSource::Body("files"),
+  ),
+  (
+    Method::POST,
+    "/systemd/mask-unit-files",
+    "unit.enable",
+    UnitSource::Body("files"),
+  ),
+  (
+    Method::POST,
+    "/systemd/unmask-unit-files",
+    "unit.enable",
+    UnitSource::Body("files"),
+  ),
+  (
+    Method::POST,
+    "/systemd/revert-unit-files",
+    "unit.enable",
+    UnitSource::Body("files"),
+  ),
+  (
+    Method::POST,
+    "/systemd/unit-files",
+    "unit.edit",
+    UnitSource::Body("name"),
+  ),
+  (
+    Method::PUT,
+    "/systemd/units/{unit}/drop-ins/{_}",
+    "unit.edit",
+    UnitSource::Path,
+  ),
+  (
+    Method::DELETE,
+    "/systemd/units/{unit}/drop-ins/{_}",
+    "unit.edit",
+    UnitSource::Path,
+  ),
+  (
+    Method::POST,
+    "/systemd/run",
+    "unit.run",
+    UnitSource::Transient,
+  ),
+  // systemd, jobs
+  (Method::GET, "/systemd/jobs", "job.read", UnitSource::None),
+  (
+    Method::GET,
+    "/systemd/jobs/{_}",
+    "job.read",
+    UnitSource::None,
+  ),
+  (
+    Method::DELETE,
+    "/systemd/jobs",
+    "job.cancel",
+    UnitSource::All,
+  ),
+  (
+    Method::DELETE,
+    "/systemd/jobs/{_}",
+    "job.cancel",
+    UnitSource::Job,
+  ),
+  // systemd, manager
+  (
+    Method::GET,
+    "/systemd/manager",
+    "manager.read",
+    UnitSource::None,
+  ),
+  (
+    Method::GET,
+    "/systemd/analyze/time",
+    "manager.read",
+    UnitSource::None,
+  ),
+  (
+    Method::GET,
+    "/systemd/analyze/blame",
+    "manager.read",
+    UnitSource::None,
+  ),
+  (
+    Method::GET,
+    "/systemd/analyze/critical-chain",
+    "manager.read",
+    UnitSource::None,
+  ),
+  (
+    Method::POST,
+    "/systemd/manager/{_}",
+    "manager.admin",
+    UnitSource::None,
+  ),
+  (
+    Method::PUT,
+    "/systemd/manager/{_}",
+    "manager.admin",
+    UnitSource::None,
+  ),
+  (
+    Method::DELETE,
+    "/systemd/manager/{_}",
+    "manager.admin",
+    UnitSource::None,
+  ),
+  // journald, `match` parameters can select logs of any unit
+  (Method::GET, "/journald/logs", "logs.read", UnitSource::All),
+  (
+    Method::GET,
+    "/journald/logs/follow",
+    "logs.read",
+    UnitSource::All,
+  ),
+  (
+    Method::GET,
+    "/journald/export",
+    "logs.read",
+    UnitSource::QueryOrAll("unit"),
+  ),
+  (
+    Method::GET,
+    "/journald/unit-logs/{unit}",
+    "logs.read",
+    UnitSource::Path,
+  ),
+  (
+    Method::GET,
+    "/journald/unit-logs/{unit}/follow",
+    "logs.read",
+    UnitSource::Path,
+  ),
+  // power
+  (Method::GET, "/power", "power.read", UnitSource::None),
+  (
+    Method::GET,
+    "/power/inhibitors",
+    "power.read",
+    UnitSource::None,
+  ),
+  (Method::POST, "/power/{_}", "power.manage", UnitSource::None),
+  (
+    Method::DELETE,
+    "/power/scheduled",
+    "power.manage",
+    UnitSource::None,
+  ),
+  // audit
+  (
+    Method::GET,
+    "/audit",
+    "audit.read",
+    UnitSource::Query("unit"),
+  ),
+  // auth, login, logout and whoami are always allowed
+  (
+    Method::GET,
+    "/auth/sessions",
+    "auth.sessions",
+    UnitSource::None,
+  ),
+  (
+    Method::DELETE,
+    "/auth/sessions/{_}",
+    "auth.sessions",
+    UnitSource::None,
+  ),
+];
+
+/// Routes anyone authenticated may use
+const ALWAYS_ALLOWED: &[(Method, &str)] = &[
+  (Method::POST, "/auth/login"),
+  (Method::POST, "/auth/logout"),
+  (Method::GET, "/auth/whoami"),
+];
+
+/// Units a request is about
+#[derive(Debug, PartialEq)]
+pub enum Units {
+  /// Request isn't about particular units
+  None,
+  Named(Vec<String>),
+
+  /// Request is about units which couldn't be found out, or about all of them
+  Unknown,
+}
+
+/// Units which are known only from the body or from systemd
+enum Lookup {
+  Body(&'static str),
+  Transient,
+  Job(u32),
+}
+
+/// What a request does
+pub struct RequestAction {
+  pub action: &'static str,
+  pub units: Units,
+
+  /// Where units still have to be looked up, see `resolve_units`
+  lookup: Option<Lookup>,
+}
+
+impl RequestAction {
+  #[cfg(test)]
+  pub fn new(action: &'static str, units: Units) -> Self {
+    RequestAction {
+      action,
+      units,
+      lookup: None,
+    }
+  }
+
+  /// The unit, if the request is about exactly one
+  pub fn unit(&self) -> Option<&str> {
+    match &self.units {
+      Units::Named(units) if units.len() == 1 => Some(&units[0]),
+      _ => None,
+    }
+  }
+
+  /// Whether units have to be found out with `resolve_units`
+  pub fn needs_lookup(&self) -> bool {
+    self.lookup.is_some()
+  }
+
+  /// Whether `resolve_units` needs the request body
+  pub fn needs_body(&self) -> bool {
+    matches!(self.lookup, Some(Lookup::Body(_) | Lookup::Transient))
+  }
+
+  /// Finds out the units named in the body or the unit of the job. Units stay unknown if the
+  /// body can't be read, the handler refuses such requests anyway.
+  pub fn resolve_units(&mut self, body: &[u8], dbus: &DBusInterface) {
+    let units = match &self.lookup {
+      None => return,
+      Some(Lookup::Job(id)) => functions::get_job(dbus, *id).ok().map(|job| vec![job.unit]),
+      Some(Lookup::Body(field)) => serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|body| body_units(&body[field])),
+      Some(Lookup::Transient) => serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|body| transient_units(&body)),
+    };
+
+    self.lookup = None;
+    self.units = match units {
+      Some(units) if !units.is_empty() => Units::Named(units),
+      _ => Units::Unknown,
+    };
+  }
+}
+
+pub enum Classification {
+  Action(RequestAction),
+  AlwaysAllowed,
+
+  /// No route matches, these are refused so a route missing from the table can't slip through
+  Unknown,
+}
+
+pub fn classify(req: &ServiceRequest) -> Classification {
+  classify_path(req.method(), req.path(), req.query_string())
+}
+
+fn classify_path(method: &Method, path: &str, query: &str) -> Classification {
+  let path = path.trim_end_matches('/');
+  let segments: Vec<&str> = path.split('/').collect();
+
+  if ALWAYS_ALLOWED
+    .iter()
+    .any(|(allowed_method, allowed_path)| allowed_method == method && *allowed_path == path)
+  {
+    return Classification::AlwaysAllowed;
+  }
+
+  for (route_method, pattern, action, unit_source) in ROUTES {
+    if route_method != method {
+      continue;
+    }
+    let unit = match match_pattern(pattern, &segments) {
+      Some(unit) => unit,
+      None => continue,
+    };
+
+    let named = |unit: Option<String>| match unit {
+      Some(unit) => Units::Named(vec![unit]),
+      None => Units::None,
+    };
+    let (units, lookup) = match unit_source {
+      UnitSource::None => (Units::None, None),
+      UnitSource::Path => (named(unit), None),
+      UnitSource::Query(name) => (named(query_value(query, name)), None),
+      UnitSource::QueryOrAll(name) => match query_value(query, name) {
+        Some(unit) => (Units::Named(vec![unit]), None),
+        None => (Units::Unknown, None),
+      },
+      UnitSource::Body(field) => (Units::Unknown, Some(Lookup::Body(field))),
+      UnitSource::Transient => (Units::Unknown, Some(Lookup::Transient)),
+      UnitSource::Job => match segments.last().and_then(|id| id.parse().ok()) {
+        Some(id) => (Units::Unknown, Some(Lookup::Job(id))),
+        None => (Units::Unknown, None),
+      },
+      UnitSource::All => (Units::Unknown, None),
+    };
+
+    return Classification::Action(RequestAction {
+      action,
+      units,
+      lookup,
+    });
+  }
+
+  Classification::Unknown
+}
+
+fn query_value(query: &str, name: &str) -> Option<String> {
+  Query::<HashMap<String, String>>::from_query(query)
+    .ok()
+    .and_then(|query| query.get(name).cloned())
+}
+
+/// Unit names from a body field with a name or a list of names. Unit file paths, which link
+/// takes, count as the unit named like the file.
+fn body_units(field: &Value) -> Option<Vec<String>> {
+  let name = |value: &Value| {
+    let name = value.as_str()?;
+    match name.contains('/') {
+      true => Some(Path::new(name).file_name()?.to_str()?.to_owned()),
+      false => Some(name.to_owned()),
+    }
+  };
+
+  match field {
+    Value::Array(values) => values.iter().map(name).collect(),
+    value => name(value).map(|name| vec![name]),
+  }
+}
+
+/// Units `POST /systemd/run` creates, the service or scope and the timer if there is one.
+/// Generated names aren't known before the request runs.
+fn transient_units(body: &Value) -> Option<Vec<String>> {
+  let unit = body["unit"].as_str()?;
+  let base = match unit.rsplit_once('.') {
+    Some((base, "service" | "scope" | "timer")) => base,
+    _ => unit,
+  };
+
+  let mut units = vec![match body["kind"].as_str() {
+    Some("scope") => format!("{}.scope", base),
+    _ => format!("{}.service", base),
+  }];
+  if !body["onCalendar"].is_null() {
+    units.push(format!("{}.timer", base));
+  }
+  Some(units)
+}
+
+/// `Some` with the decoded `{unit}` segment if the path matches the pattern
+fn match_pattern(pattern: &str, segments: &[&str]) -> Option<Option<String>> {
+  let pattern: Vec<&str> = pattern.split('/').collect();
+  if pattern.len() != segments.len() {
+    return None;
+  }
+
+  let mut unit = None;
+  for (expected, segment) in pattern.iter().zip(segments) {
+    match *expected {
+      "{unit}" => unit = Some(percent_decode(segment)?),
+      "{_}" => {}
+      _ if expected == segment => {}
+      _ => return None,
+    }
+  }
+
+  Some(unit)
+}
+
+/// Decodes `%40` and the like, which clients use for i.e. `getty@tty1.service`
+fn percent_decode(segment: &str) -> Option<String> {
+  let bytes = segment.as_bytes();
+  let mut decoded = Vec::with_capacity(bytes.len());
+  let mut index = 0;
+
+  while index < bytes.len() {
+    match bytes[index] {
+      b'%' => {
+        let hex = segment.get(index + 1..index + 3)?;
+        decoded.push(u8::from_str_radix(hex, 16).ok()?);
+        index += 3;
+      }
+      byte => {
+        decoded.push(byte);
+        index += 1;
+      }
+    }
+  }
+
+  String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  /// Action, units and whether they still have to be looked up, `None` for unknown routes
+  fn classified(method: Method, path: &str, query: &str) -> Option<(&'static str, Units, bool)> {
+    match classify_path(&method, path, query) {
+      Classification::Action(action) => {
+        let lookup = action.needs_lookup();
+        Some((action.action, action.units, lookup))
+      }
+      Classification::AlwaysAllowed => Some(("always", Units::None, false)),
+      Classification::Unknown => None,
+    }
+  }
+
+  fn named(units: &[&str]) -> Units {
+    Units::Named(units.iter().map(|unit| unit.to_string()).collect())
+  }
+
+  #[test]
+  fn unit_deny_rules_cover_routes_reaching_any_unit() {
+    let policy = crate::policy::Policy::new(
+      &toml::from_str(
+        r#"
+          enabled = true
+
+          [[rules]]
+          groups = ["*"]
+          actions = ["logs.read", "unit.read"]
+
+          [[rules]]
+          effect = "deny"
+          groups = ["*"]
+          actions = ["logs.read", "unit.read"]
+          units = ["sshd.service"]
+        "#,
+      )
+      .unwrap(),
+    )
+    .unwrap();
+    let identity = crate::auth::dto::Identity {
+      name: "dev".to_owned(),
+      kind: crate::auth::dto::IdentityKind::Token,
+      user: None,
+      session_id: None,
+    };
+    let allowed = |path: &str, query: &str| match classify_path(&Method::GET, path, query) {
+      Classification::Action(action) => policy.check(Some(&identity), action).is_ok(),
+      _ => panic!("{} isn't a route", path),
+    };
+
+    assert!(!allowed(
+      "/journald/logs",
+      "match=_SYSTEMD_UNIT=sshd.service"
+    ));
+    assert!(!allowed("/journald/logs/follow", ""));
+    assert!(!allowed("/journald/export", ""));
+    assert!(!allowed("/journald/export", "unit=sshd.service"));
+    assert!(!allowed("/journald/unit-logs/sshd.service", ""));
+    assert!(!allowed("/systemd/events", ""));
+    assert!(allowed("/journald/export", "unit=nginx.service"));
+    assert!(allowed("/journald/unit-logs/nginx.service", ""));
+    assert!(allowed("/systemd/list-units", ""));
+  }
+
+  #[test]
+  fn classifies_routes() {
+    let cases = [
+      (
+        Method::GET,
+        "/systemd/list-units",
+        "",
+        Some(("unit.read", Units::None, false)),
+      ),
+      (
+        Method::POST,
+        "/systemd/start-unit/nginx.service",
+        "",
+        Some(("unit.start", named(&["nginx.service"]), false)),
+      ),
+      (
+        Method::PUT,
+        "/systemd/units/nginx.service/drop-ins/limits",
+        "",
+        Some(("unit.edit", named(&["nginx.service"]), false)),
+      ),
+      (
+        Method::GET,
+        "/journald/export",
+        "format=json&unit=sshd.service",
+        Some(("logs.read", named(&["sshd.service"]), false)),
+      ),
+      (
+        Method::GET,
+        "/journald/export",
+        "",
+        Some(("logs.read", Units::Unknown, false)),
+      ),
+      (
+        Method::GET,
+        "/journald/logs",
+        "match=_SYSTEMD_UNIT=sshd.service",
+        Some(("logs.read", Units::Unknown, false)),
+      ),
+      (
+        Method::GET,
+        "/journald/logs/follow",
+        "",
+        Some(("logs.read", Units::Unknown, false)),
+      ),
+      (
+        Method::GET,
+        "/journald/unit-logs/sshd.service",
+        "",
+        Some(("logs.read", named(&["sshd.service"]), false)),
+      ),
+      (
+        Method::GET,
+        "/systemd/events",
+        "units=nginx.service",
+        Some(("unit.read", Units::Unknown, false)),
+      ),
+      (
+        Method::POST,
+        "/systemd/enable-unit-files",
+        "",
+        Some(("unit.enable", Units::Unknown, true)),
+      ),
+      (
+        Method::POST,
+        "/systemd/unit-files",
+        "",
+        Some(("unit.edit", Units::Unknown, true)),
+      ),
+      (
+        Method::POST,
+        "/systemd/run",
+        "",
+        Some(("unit.run", Units::Unknown, true)),
+      ),
+      (
+        Method::DELETE,
+        "/systemd/jobs/42",
+        "",
+        Some(("job.cancel", Units::Unknown, true)),
+      ),
+      (
+        Method::DELETE,
+        "/systemd/jobs/x",
+        "",
+        Some(("job.cancel", Units::Unknown, false)),
+      ),
+      (
+        Method::DELETE,
+        "/systemd/jobs",
+        "",
+        Some(("job.cancel", Units::Unknown, false)),
+      ),
+      (
+        Method::GET,
+        "/systemd/jobs/42",
+        "",
+        Some(("job.read", Units::None, false)),
+      ),
+      (
+        Method::POST,
+        "/power/reboot",
+        "",
+        Some(("power.manage", Units::None, false)),
+      ),
+      (
+        Method::POST,
+        "/auth/login",
+        "",
+        Some(("always", Units::None, false)),
+      ),
+      (
+        Method::GET,
+        "/auth/sessions",
+        "",
+        Some(("auth.sessions", Units::None, false)),
+      ),
+    ];
+
+    for (method, path, query, expected) in cases {
+      assert_eq!(classified(method, path, query), expected, "{}", path);
+    }
+  }
+
+  #[test]
+  fn decodes_unit_names() {
+    assert_eq!(
+      classified(Method::POST, "/systemd/start-unit/getty%40tty1.service", ""),
+      Some(("unit.start", named(&["getty@tty1.service"]), false))
+    );
+    assert_eq!(
+      classified(Method::GET, "/systemd/load-unit/a%2Fb", ""),
+      Some(("unit.read", named(&["a/b"]), false))
+    );
+    // Broken escapes don't match the route at all
+    assert_eq!(classified(Method::GET, "/systemd/load-unit/a%4", ""), None);
+    assert_eq!(classified(Method::GET, "/systemd/load-unit/a%zz", ""), None);
+    assert_eq!(classified(Method::GET, "/systemd/load-unit/%ff", ""), None);
+  }
+
+  #[test]
+  fn ignores_trailing_slashes() {
+    assert_eq!(
+      classified(Method::POST, "/systemd/stop-unit/sshd.service/", ""),
+      Some(("unit.stop", named(&["sshd.service"]), false))
+    );
+    assert_eq!(
+      classified(Method::GET, "/systemd/manager/", ""),
+      Some(("manager.read", Units::None, false))
+    );
+    assert_eq!(
+      classified(Method::POST, "/auth/login/", ""),
+      Some(("always", Units::None, false))
+    );
+  }
+
+  #[test]
+  fn refuses_unknown_routes() {
+    let cases = [
+      (Method::GET, "/"),
+      (Method::GET, "/systemd"),
+      (Method::GET, "/systemd/start-unit/sshd.service"),
+      (Method::POST, "/systemd/start-unit"),
+      (Method::POST, "/systemd/start-unit/a/b"),
+      (Method::PATCH, "/systemd/units/a.service/drop-ins/x"),
+      (Method::GET, "/nothing/here"),
+      (Method::POST, "/auth/whoami"),
+    ];
+
+    for (method, path) in cases {
+      assert_eq!(classified(method, path, ""), None, "{}", path);
+    }
+  }
+
+  #[test]
+  fn reads_units_from_bodies() {
+    let body = |json: &str| serde_json::from_str::<Value>(json).unwrap();
+
+    assert_eq!(
+      body_units(&body(r#"["a.service", "/etc/systemd/user/b.service"]"#)),
+      Some(vec!["a.service".to_owned(), "b.service".to_owned()])
+    );
+    assert_eq!(
+      body_units(&body(r#""c.timer""#)),
+      Some(vec!["c.timer".to_owned()])
+    );
+    assert_eq!(body_units(&body(r#"["a.service", 1]"#)), None);
+    assert_eq!(body_units(&Value::Null), None);
+
+    assert_eq!(
+      transient_units(&body(r#"{"unit": "backup"}"#)),
+      Some(vec!["backup.service".to_owned()])
+    );
+    assert_eq!(
+      transient_units(&body(r#"{"unit": "backup.timer", "onCalendar": "daily"}"#)),
+      Some(vec!["backup.service".to_owned(), "backup.timer".to_owned()])
+    );
+    assert_eq!(
+      transient_units(&body(r#"{"unit": "shell.service", "kind": "scope"}"#)),
+      Some(vec!["shell.scope".to_owned()])
+    );
+    assert_eq!(transient_units(&body(r#"{"command": ["ls"]}"#)), None);
+  }
+}
@@ -0,0 +1,92 @@
+use std::{
+  future::{ready, Future, Ready},
+  pin::Pin,
+  rc::Rc,
+};
+
+use actix_web::{
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  web::{self, Bytes},
+  Error, HttpMessage,
+};
+
+use crate::{api_errors::ApiError, auth::dto::Identity, server, AppState};
+
+use super::actions::{self, Classification};
+
+/// Checks requests against the policy, has to run after `Authentication`
+pub struct Authorization;
+
+impl<S, B> Transform<S, ServiceRequest> for Authorization
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Transform = AuthorizationMiddleware<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ready(Ok(AuthorizationMiddleware {
+      service: Rc::new(service),
+    }))
+  }
+}
+
+pub struct AuthorizationMiddleware<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for AuthorizationMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  forward_ready!(service);
+
+  fn call(&self, mut req: ServiceRequest) -> Self::Future {
+    let service = Rc::clone(&self.service);
+
+    Box::pin(async move {
+      let state = req
+        .app_data::<web::Data<AppState<'static>>>()
+        .expect("AppState is registered as app data")
+        .clone();
+
+      if state.policy.enabled {
+        let identity = req.extensions().get::<Identity>().cloned();
+
+        match actions::classify(&req) {
+          Classification::AlwaysAllowed => {}
+          Classification::Action(mut action) => {
+            if action.needs_lookup() {
+              let body = match action.needs_body() {
+                true => server::read_body(&mut req).await?,
+                false => Bytes::new(),
+              };
+              action.resolve_units(&body, &state.dbus.lock().unwrap());
+            }
+
+            if let Err(denial) = state.policy.check(identity.as_ref(), action) {
+              info!("Denied {} {}: {}", req.method(), req.path(), denial);
+              return Err(ApiError::Policy(denial).into());
+            }
+          }
+          Classification::Unknown => {
+            return Err(
+              ApiError::NotFound(format!("No route for {} {}", req.method(), req.path())).into(),
+            )
+          }
+        }
+      }
+
+      service.call(req).await
+    })
+  }
+}
@@ -0,0 +1,425 @@
+//! Role-based authorization. Identities are put in groups, rules allow or deny groups actions,
+//! optionally only on units matching globs. Deny rules win, requests no rule allows are denied.
+
+pub mod actions;
+pub mod middleware;
+
+use glob::Pattern;
+
+use crate::{
+  auth::{self, dto::Identity},
+  config::{PolicyConfig, PolicyEffect},
+};
+
+use self::actions::{RequestAction, Units};
+
+struct Group {
+  name: String,
+  members: Vec<String>,
+  unix_groups: Vec<String>,
+}
+
+struct Rule {
+  effect: PolicyEffect,
+  groups: Vec<String>,
+  actions: Vec<Pattern>,
+  units: Option<Vec<Pattern>>,
+}
+
+impl Rule {
+  /// Allow rules limited to units match when every unit of the request matches. Deny rules
+  /// match when any does, and when the units aren't known, so they fail closed.
+  fn matches(&self, groups: &[&str], request: &RequestAction) -> bool {
+    let group_matches = self
+      .groups
+      .iter()
+      .any(|group| group == "*" || groups.contains(&group.as_str()));
+    let action_matches = self
+      .actions
+      .iter()
+      .any(|action| action.matches(request.action));
+    let deny = self.effect == PolicyEffect::Deny;
+    let unit_matches = match (&self.units, &request.units) {
+      (None, _) => true,
+      (Some(_), Units::None) => false,
+      (Some(_), Units::Unknown) => deny,
+      (Some(patterns), Units::Named(units)) => {
+        let matches = |unit: &String| patterns.iter().any(|pattern| pattern.matches(unit));
+        match deny {
+          true => units.iter().any(matches),
+          false => units.iter().all(matches),
+        }
+      }
+    };
+
+    group_matches && action_matches && unit_matches
+  }
+}
+
+/// Why a request was refused
+#[derive(Debug)]
+pub struct PolicyDenial {
+  pub identity: Option<String>,
+  pub action: &'static str,
+  pub unit: Option<String>,
+}
+
+impl std::fmt::Display for PolicyDenial {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let identity = self.identity.as_deref().unwrap_or("Anonymous request");
+    match &self.unit {
+      Some(unit) => write!(
+        f,
+        "{} isn't allowed to {} on {}",
+        identity, self.action, unit
+      ),
+      None => write!(f, "{} isn't allowed to {}", identity, self.action),
+    }
+  }
+}
+
+pub struct Policy {
+  pub enabled: bool,
+  groups: Vec<Group>,
+  rules: Vec<Rule>,
+}
+
+impl Policy {
+  pub fn new(config: &PolicyConfig) -> Result<Policy, String> {
+    let mut groups: Vec<Group> = Vec::new();
+    for group in &config.groups {
+      if group.name == "*" || groups.iter().any(|other| other.name == group.name) {
+        return Err(format!(
+          "Policy group name {} is used more than once",
+          group.name
+        ));
+      }
+
+      groups.push(Group {
+        name: group.name.clone(),
+        members: group.members.clone(),
+        unix_groups: group.unix_groups.clone(),
+      });
+    }
+
+    let glob = |pattern: &String| {
+      Pattern::new(pattern).map_err(|err| format!("Invalid glob {} in policy: {}", pattern, err))
+    };
+
+    let mut rules = Vec::new();
+    for rule in &config.rules {
+      if let Some(group) = rule
+        .groups
+        .iter()
+        .find(|name| *name != "*" && !groups.iter().any(|group| &group.name == *name))
+      {
+        return Err(format!("Policy rule refers to unknown group {}", group));
+      }
+
+      rules.push(Rule {
+        effect: rule.effect,
+        groups: rule.groups.clone(),
+        actions: rule.actions.iter().map(glob).collect::<Result<_, _>>()?,
+        units: match &rule.units {
+          Some(units) => Some(units.iter().map(glob).collect::<Result<_, _>>()?),
+          None => None,
+        },
+      });
+    }
+
+    Ok(Policy {
+      enabled: config.enabled,
+      groups,
+      rules,
+    })
+  }
+
+  /// Policy groups the identity is in. Unix groups only count for identities with a Unix
+  /// account, token names aren't user names.
+  fn groups_of(&self, identity: &Identity) -> Vec<&str> {
+    let mut unix_groups = None;
+
+    self
+      .groups
+      .iter()
+      .filter(|group| {
+        if group.members.contains(&identity.name) {
+          return true;
+        }
+        let user = match &identity.user {
+          Some(user) if !group.unix_groups.is_empty() => user,
+          _ => return false,
+        };
+
+        let member_of = unix_groups.get_or_insert_with(|| auth::user_groups(user));
+        group
+          .unix_groups
+          .iter()
+          .filter_map(|name| auth::group_id(name))
+          .any(|gid| member_of.contains(&gid))
+      })
+      .map(|group| group.name.as_str())
+      .collect()
+  }
+
+  pub fn check(
+    &self,
+    identity: Option<&Identity>,
+    request: RequestAction,
+  ) -> Result<(), PolicyDenial> {
+    if !self.enabled {
+      return Ok(());
+    }
+
+    let allowed = identity.is_some_and(|identity| {
+      let groups = self.groups_of(identity);
+      let matching = |effect| {
+        self
+          .rules
+          .iter()
+          .any(|rule| rule.effect == effect && rule.matches(&groups, &request))
+      };
+
+      !matching(PolicyEffect::Deny) && matching(PolicyEffect::Allow)
+    });
+
+    match allowed {
+      true => Ok(()),
+      false => Err(PolicyDenial {
+        identity: identity.map(|identity| identity.name.clone()),
+        action: request.action,
+        unit: match request.units {
+          Units::Named(units) => Some(units.join(", ")),
+          _ => None,
+        },
+      }),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::auth::dto::IdentityKind;
+
+  fn policy(config: &str) -> Policy {
+    Policy::new(&toml::from_str(config).unwrap()).unwrap()
+  }
+
+  fn identity(name: &str) -> Identity {
+    Identity {
+      name: name.to_owned(),
+      kind: IdentityKind::Token,
+      user: None,
+      session_id: None,
+    }
+  }
+
+  fn named(units: &[&str]) -> Units {
+    Units::Named(units.iter().map(|unit| unit.to_string()).collect())
+  }
+
+  fn allowed(policy: &Policy, name: Option<&str>, action: &'static str, units: Units) -> bool {
+    let identity = name.map(identity);
+    policy
+      .check(identity.as_ref(), RequestAction::new(action, units))
+      .is_ok()
+  }
+
+  const CONFIG: &str = r#"
+    enabled = true
+
+    [[groups]]
+    name = "admins"
+    members = ["root"]
+
+    [[groups]]
+    name = "developers"
+    members = ["dev"]
+
+    [[rules]]
+    groups = ["admins"]
+    actions = ["*"]
+
+    [[rules]]
+    groups = ["developers"]
+    actions = ["unit.read", "unit.restart", "unit.enable"]
+    units = ["app-*.service"]
+
+    [[rules]]
+    groups = ["developers"]
+    actions = ["logs.*"]
+
+    [[rules]]
+    effect = "deny"
+    groups = ["*"]
+    actions = ["unit.stop", "unit.enable", "job.cancel"]
+    units = ["sshd.service"]
+  "#;
+
+  #[test]
+  fn allows_matching_rules() {
+    let policy = policy(CONFIG);
+
+    assert!(allowed(&policy, Some("root"), "power.manage", Units::None));
+    assert!(allowed(&policy, Some("dev"), "logs.read", Units::None));
+    assert!(allowed(
+      &policy,
+      Some("dev"),
+      "unit.restart",
+      named(&["app-web.service"])
+    ));
+    assert!(!allowed(
+      &policy,
+      Some("dev"),
+      "unit.restart",
+      named(&["db.service"])
+    ));
+    assert!(!allowed(&policy, Some("dev"), "power.manage", Units::None));
+    assert!(!allowed(
+      &policy,
+      Some("stranger"),
+      "logs.read",
+      Units::None
+    ));
+  }
+
+  #[test]
+  fn deny_wins() {
+    let policy = policy(CONFIG);
+
+    assert!(allowed(
+      &policy,
+      Some("root"),
+      "unit.stop",
+      named(&["nginx.service"])
+    ));
+    assert!(!allowed(
+      &policy,
+      Some("root"),
+      "unit.stop",
+      named(&["sshd.service"])
+    ));
+  }
+
+  #[test]
+  fn star_group_includes_everyone() {
+    let policy = policy(CONFIG);
+
+    // The deny rule names just *, which everyone is in
+    assert!(!allowed(
+      &policy,
+      Some("root"),
+      "unit.stop",
+      named(&["sshd.service"])
+    ));
+    assert!(!allowed(
+      &policy,
+      Some("dev"),
+      "unit.enable",
+      named(&["sshd.service"])
+    ));
+  }
+
+  #[test]
+  fn unit_rules_need_every_unit_to_match() {
+    let policy = policy(CONFIG);
+
+    assert!(allowed(
+      &policy,
+      Some("dev"),
+      "unit.enable",
+      named(&["app-a.service", "app-b.service"])
+    ));
+    assert!(!allowed(
+      &policy,
+      Some("dev"),
+      "unit.enable",
+      named(&["app-a.service", "db.service"])
+    ));
+    // Deny rules match if any of the units does
+    assert!(!allowed(
+      &policy,
+      Some("root"),
+      "unit.enable",
+      named(&["nginx.service", "sshd.service"])
+    ));
+  }
+
+  #[test]
+  fn unknown_units_fail_closed() {
+    let policy = policy(CONFIG);
+
+    // Allow rules limited to units don't allow requests with unknown units
+    assert!(!allowed(
+      &policy,
+      Some("dev"),
+      "unit.enable",
+      Units::Unknown
+    ));
+    // Deny rules limited to units deny them
+    assert!(!allowed(
+      &policy,
+      Some("root"),
+      "job.cancel",
+      Units::Unknown
+    ));
+    assert!(allowed(
+      &policy,
+      Some("root"),
+      "job.cancel",
+      named(&["nginx.service"])
+    ));
+    // Requests which aren't about units aren't affected by rules limited to units
+    assert!(allowed(&policy, Some("root"), "unit.stop", Units::None));
+  }
+
+  #[test]
+  fn denies_requests_without_identity() {
+    assert!(!allowed(&policy(CONFIG), None, "logs.read", Units::None));
+
+    let open = policy("enabled = true\n[[rules]]\ngroups = [\"*\"]\nactions = [\"*\"]\n");
+    assert!(!allowed(&open, None, "logs.read", Units::None));
+    assert!(allowed(&open, Some("anyone"), "logs.read", Units::None));
+
+    let disabled = policy("enabled = false");
+    assert!(allowed(&disabled, None, "power.manage", Units::None));
+  }
+
+  #[test]
+  fn explains_denials() {
+    let policy = policy(CONFIG);
+    let denial = policy
+      .check(
+        Some(&identity("dev")),
+        RequestAction::new("unit.restart", named(&["a.service", "b.service"])),
+      )
+      .unwrap_err();
+    assert_eq!(
+      denial.to_string(),
+      "dev isn't allowed to unit.restart on a.service, b.service"
+    );
+
+    let denial = policy
+      .check(None, RequestAction::new("logs.read", Units::None))
+      .unwrap_err();
+    assert_eq!(
+      denial.to_string(),
+      "Anonymous request isn't allowed to logs.read"
+    );
+  }
+
+  #[test]
+  fn rejects_invalid_config() {
+    let invalid = |config: &str| Policy::new(&toml::from_str(config).unwrap()).is_err();
+
+    assert!(invalid(
+      "[[groups]]\nname = \"a\"\n[[groups]]\nname = \"a\"\n"
+    ));
+    assert!(invalid("[[groups]]\nname = \"*\"\n"));
+    assert!(invalid(
+      "[[rules]]\ngroups = [\"missing\"]\nactions = [\"*\"]\n"
+    ));
+    assert!(invalid("[[rules]]\ngroups = [\"*\"]\nactions = [\"[\"]\n"));
+  }
+}
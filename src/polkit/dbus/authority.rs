@@ -0,0 +1,37 @@
+// This code was autogenerated with `dbus-codegen-rust -m None -g -f org.freedesktop.PolicyKit1.Authority`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::blocking;
+
+pub trait OrgFreedesktopPolicyKit1Authority {
+  fn check_authorization(
+    &self,
+    subject: (&str, arg::PropMap),
+    action_id: &str,
+    details: ::std::collections::HashMap<&str, &str>,
+    flags: u32,
+    cancellation_id: &str,
+  ) -> Result<(bool, bool, ::std::collections::HashMap<String, String>), dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>>
+  OrgFreedesktopPolicyKit1Authority for blocking::Proxy<'a, C>
+{
+  fn check_authorization(
+    &self,
+    subject: (&str, arg::PropMap),
+    action_id: &str,
+    details: ::std::collections::HashMap<&str, &str>,
+    flags: u32,
+    cancellation_id: &str,
+  ) -> Result<(bool, bool, ::std::collections::HashMap<String, String>), dbus::Error> {
+    self
+      .method_call(
+        "org.freedesktop.PolicyKit1.Authority",
+        "CheckAuthorization",
+        (subject, action_id, details, flags, cancellation_id),
+      )
+      .and_then(|r: ((bool, bool, ::std::collections::HashMap<String, String>),)| Ok(r.0))
+  }
+}
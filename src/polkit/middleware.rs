@@ -0,0 +1,75 @@
+use std::{
+  future::{ready, Future, Ready},
+  pin::Pin,
+  rc::Rc,
+};
+
+use actix_web::{
+  dev::{forward_ready, Service, ServiceRequest, ServiceResponse, Transform},
+  web, Error, HttpMessage,
+};
+
+use crate::{auth::dto::Identity, AppState};
+
+use crate::policy::actions::{self, Classification};
+
+/// Checks privileged requests with polkit, has to run after `Authentication`
+pub struct PolkitAuthorization;
+
+impl<S, B> Transform<S, ServiceRequest> for PolkitAuthorization
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Transform = PolkitAuthorizationMiddleware<S>;
+  type InitError = ();
+  type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+  fn new_transform(&self, service: S) -> Self::Future {
+    ready(Ok(PolkitAuthorizationMiddleware {
+      service: Rc::new(service),
+    }))
+  }
+}
+
+pub struct PolkitAuthorizationMiddleware<S> {
+  service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for PolkitAuthorizationMiddleware<S>
+where
+  S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+  B: 'static,
+{
+  type Response = ServiceResponse<B>;
+  type Error = Error;
+  type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+  forward_ready!(service);
+
+  fn call(&self, req: ServiceRequest) -> Self::Future {
+    let service = Rc::clone(&self.service);
+
+    Box::pin(async move {
+      let state = req
+        .app_data::<web::Data<AppState<'static>>>()
+        .expect("AppState is registered as app data");
+
+      if state.polkit.enabled {
+        if let Classification::Action(action) = actions::classify(&req) {
+          let identity = req.extensions().get::<Identity>().cloned();
+          let dbus = state.dbus.lock().unwrap();
+
+          state
+            .polkit
+            .check(&dbus, identity.as_ref(), req.method(), req.path(), &action)
+            .inspect_err(|err| info!("Denied {} {}: {}", req.method(), req.path(), err))?;
+        }
+      }
+
+      service.call(req).await
+    })
+  }
+}
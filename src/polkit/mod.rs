@@ -0,0 +1,347 @@
+//! Asks polkit whether the Unix account behind a request may do what it asks for, before dragond
+//! does it. Requests are checked with the polkit actions and details systemd and logind use
+//! themselves, so rules written for systemctl apply to the panel unchanged.
+//!
+//! This is a pre-check dragond makes while running as root, it still calls systemd as root.
+//! polkit only lets root check authorization for other users, so dragond refuses to start with
+//! polkit checks on otherwise. Interactive authorization isn't used, nobody could answer it.
+
+pub mod dbus;
+pub mod middleware;
+
+use std::collections::HashMap;
+
+use ::dbus::arg::{PropMap, Variant};
+use actix_web::http::Method;
+
+use crate::{
+  api_errors::ApiError, auth, auth::dto::Identity, config::PolkitConfig,
+  dbus_interface::DBusInterface, policy::actions::RequestAction,
+};
+
+use self::dbus::authority::OrgFreedesktopPolicyKit1Authority;
+
+/// Why polkit refused a request
+#[derive(Debug)]
+pub struct PolkitDenial {
+  pub user: Option<String>,
+  pub action_id: &'static str,
+
+  /// polkit would allow it after authentication, which can't be done over the API
+  pub challenge: bool,
+}
+
+impl std::fmt::Display for PolkitDenial {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    let user = match &self.user {
+      Some(user) => user.as_str(),
+      None => return write!(f, "Request has no Unix account polkit could check"),
+    };
+    match self.challenge {
+      true => write!(
+        f,
+        "polkit wants {} to authenticate for {}, which isn't possible over the API",
+        user, self.action_id
+      ),
+      false => write!(
+        f,
+        "polkit doesn't authorize {} for {}",
+        user, self.action_id
+      ),
+    }
+  }
+}
+
+/// polkit action and details for a request
+pub struct PolkitAction {
+  pub id: &'static str,
+  pub details: Vec<(&'static str, String)>,
+}
+
+impl PolkitAction {
+  fn new(id: &'static str) -> Self {
+    Self {
+      id,
+      details: Vec::new(),
+    }
+  }
+}
+
+pub struct Polkit {
+  pub enabled: bool,
+}
+
+impl Polkit {
+  pub fn new(config: &PolkitConfig) -> Polkit {
+    Polkit {
+      enabled: config.enabled,
+    }
+  }
+
+  /// Checks the request with polkit, requests that don't need privileges always pass
+  pub fn check(
+    &self,
+    dbus: &DBusInterface,
+    identity: Option<&Identity>,
+    method: &Method,
+    path: &str,
+    request: &RequestAction,
+  ) -> Result<(), ApiError> {
+    if !self.enabled {
+      return Ok(());
+    }
+    let action = match polkit_action(method, path, request) {
+      Some(action) => action,
+      None => return Ok(()),
+    };
+
+    let denied = |user: Option<&String>, challenge| {
+      ApiError::Polkit(PolkitDenial {
+        user: user.cloned(),
+        action_id: action.id,
+        challenge,
+      })
+    };
+    let user = match identity.and_then(|identity| identity.user.as_ref()) {
+      Some(user) => user,
+      None => return Err(denied(None, false)),
+    };
+    let uid = auth::user_id(user).ok_or_else(|| denied(Some(user), false))?;
+
+    let details: HashMap<&str, &str> = action
+      .details
+      .iter()
+      .map(|(key, value)| (*key, value.as_str()))
+      .collect();
+
+    // No AllowUserInteraction flag, nobody could answer an authentication dialog
+    let (authorized, challenge, _) = dbus.polkit_authority().check_authorization(
+      ("unix-process", subject(uid)?),
+      action.id,
+      details,
+      0,
+      "",
+    )?;
+
+    match authorized {
+      true => Ok(()),
+      false => Err(denied(Some(user), challenge)),
+    }
+  }
+}
+
+/// dragond's own process acting as `uid`. polkit needs the start time to tell the process apart
+/// from a later one with the same pid.
+fn subject(uid: libc::uid_t) -> Result<PropMap, ApiError> {
+  let stat = std::fs::read_to_string("/proc/self/stat")?;
+  // Start time is field 22. The command name may contain spaces, so fields are counted from the
+  // state, field 3, after its closing parenthesis.
+  let start_time = stat
+    .rsplit_once(')')
+    .and_then(|(_, fields)| fields.split_whitespace().nth(19))
+    .and_then(|start_time| start_time.parse::<u64>().ok())
+    .ok_or_else(|| {
+      ApiError::Io(std::io::Error::other(
+        "Can't read process start time from /proc/self/stat",
+      ))
+    })?;
+
+  let mut subject = PropMap::new();
+  subject.insert("pid".to_owned(), Variant(Box::new(std::process::id())));
+  subject.insert("start-time".to_owned(), Variant(Box::new(start_time)));
+  subject.insert("uid".to_owned(), Variant(Box::new(uid as i32)));
+  Ok(subject)
+}
+
+/// polkit action systemd or logind checks for the same operation, `None` for requests that
+/// don't need privileges
+fn polkit_action(method: &Method, path: &str, request: &RequestAction) -> Option<PolkitAction> {
+  let path = path.trim_end_matches('/');
+  let last_segment = path.rsplit('/').next().unwrap_or_default();
+
+  let action = match request.action {
+    "unit.start" | "unit.stop" | "unit.reload" | "unit.restart" => {
+      let mut action = PolkitAction::new("org.freedesktop.systemd1.manage-units");
+      // Paths are /systemd/{verb}-unit/{unit}, systemd passes the same verbs
+      let verb = path
+        .split('/')
+        .nth(2)
+        .and_then(|segment| segment.strip_suffix("-unit"));
+      if let (Some(unit), Some(verb)) = (request.unit(), verb) {
+        action.details.push(("unit", unit.to_owned()));
+        action.details.push(("verb", verb.to_owned()));
+      }
+      action
+    }
+    "unit.run" | "job.cancel" => PolkitAction::new("org.freedesktop.systemd1.manage-units"),
+    "unit.enable" | "unit.edit" => PolkitAction::new("org.freedesktop.systemd1.manage-unit-files"),
+    "manager.admin" => PolkitAction::new(match last_segment {
+      "reload" | "reexecute" => "org.freedesktop.systemd1.reload-daemon",
+      "environment" => "org.freedesktop.systemd1.set-environment",
+      "default-target" => "org.freedesktop.systemd1.manage-unit-files",
+      _ => "org.freedesktop.systemd1.manage-units",
+    }),
+    "power.manage" => PolkitAction::new(match (method, last_segment) {
+      (&Method::POST, "confirmations") => return None,
+      (_, "poweroff") | (&Method::DELETE, "scheduled") => "org.freedesktop.login1.power-off",
+      (_, "halt") => "org.freedesktop.login1.halt",
+      (_, "suspend") => "org.freedesktop.login1.suspend",
+      (_, "hibernate") | (_, "hybrid-sleep") => "org.freedesktop.login1.hibernate",
+      _ => "org.freedesktop.login1.reboot",
+    }),
+    _ => return None,
+  };
+
+  Some(action)
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::test::TestRequest;
+
+  use super::*;
+  use crate::policy::actions::{self, Classification};
+
+  /// polkit action of a request, classified like the middleware does
+  fn polkit_for(method: Method, path: &str) -> Option<PolkitAction> {
+    let req = TestRequest::default()
+      .method(method.clone())
+      .uri(path)
+      .to_srv_request();
+    match actions::classify(&req) {
+      Classification::Action(request) => polkit_action(&method, req.path(), &request),
+      _ => panic!("{} {} isn't a route", method, path),
+    }
+  }
+
+  fn action_for(method: Method, path: &str) -> Option<&'static str> {
+    polkit_for(method, path).map(|action| action.id)
+  }
+
+  #[test]
+  fn unit_verbs_pass_unit_and_verb() {
+    let action = polkit_for(Method::POST, "/systemd/restart-unit/a.service/").unwrap();
+    assert_eq!(action.id, "org.freedesktop.systemd1.manage-units");
+    assert_eq!(
+      action.details,
+      vec![
+        ("unit", "a.service".to_owned()),
+        ("verb", "restart".to_owned())
+      ]
+    );
+
+    let action = polkit_for(Method::POST, "/systemd/try-restart-unit/a.service").unwrap();
+    assert_eq!(action.details[1], ("verb", "try-restart".to_owned()));
+
+    // Without a single unit there's nothing to put in the details
+    let request = RequestAction::new("unit.start", actions::Units::Unknown);
+    let action = polkit_action(&Method::POST, "/systemd/start-unit/a.service", &request).unwrap();
+    assert!(action.details.is_empty());
+  }
+
+  #[test]
+  fn maps_systemd_routes() {
+    let manage_units = Some("org.freedesktop.systemd1.manage-units");
+    let manage_unit_files = Some("org.freedesktop.systemd1.manage-unit-files");
+    let reload_daemon = Some("org.freedesktop.systemd1.reload-daemon");
+    let set_environment = Some("org.freedesktop.systemd1.set-environment");
+
+    let cases = [
+      (Method::POST, "/systemd/run", manage_units),
+      (Method::DELETE, "/systemd/jobs/7", manage_units),
+      (Method::DELETE, "/systemd/jobs", manage_units),
+      (
+        Method::POST,
+        "/systemd/enable-unit-files",
+        manage_unit_files,
+      ),
+      (Method::POST, "/systemd/mask-unit-files", manage_unit_files),
+      (Method::POST, "/systemd/unit-files", manage_unit_files),
+      (
+        Method::PUT,
+        "/systemd/units/a.service/drop-ins/limits",
+        manage_unit_files,
+      ),
+      (
+        Method::DELETE,
+        "/systemd/units/a.service/drop-ins/limits",
+        manage_unit_files,
+      ),
+      (Method::POST, "/systemd/manager/reload", reload_daemon),
+      (Method::POST, "/systemd/manager/reexecute", reload_daemon),
+      (Method::PUT, "/systemd/manager/environment", set_environment),
+      (
+        Method::DELETE,
+        "/systemd/manager/environment",
+        set_environment,
+      ),
+      (
+        Method::PUT,
+        "/systemd/manager/default-target",
+        manage_unit_files,
+      ),
+      (Method::PUT, "/systemd/manager/log-level", manage_units),
+      (Method::POST, "/systemd/manager/reset-failed", manage_units),
+    ];
+    for (method, path, expected) in cases {
+      assert_eq!(action_for(method, path), expected, "{path}");
+    }
+  }
+
+  #[test]
+  fn maps_power_routes() {
+    let cases = [
+      (
+        Method::POST,
+        "/power/poweroff",
+        Some("org.freedesktop.login1.power-off"),
+      ),
+      (
+        Method::POST,
+        "/power/reboot",
+        Some("org.freedesktop.login1.reboot"),
+      ),
+      (
+        Method::POST,
+        "/power/halt",
+        Some("org.freedesktop.login1.halt"),
+      ),
+      (
+        Method::POST,
+        "/power/suspend",
+        Some("org.freedesktop.login1.suspend"),
+      ),
+      (
+        Method::POST,
+        "/power/hibernate",
+        Some("org.freedesktop.login1.hibernate"),
+      ),
+      (
+        Method::POST,
+        "/power/hybrid-sleep",
+        Some("org.freedesktop.login1.hibernate"),
+      ),
+      (
+        Method::DELETE,
+        "/power/scheduled",
+        Some("org.freedesktop.login1.power-off"),
+      ),
+      (Method::POST, "/power/confirmations", None),
+    ];
+    for (method, path, expected) in cases {
+      assert_eq!(action_for(method, path), expected, "{path}");
+    }
+  }
+
+  #[test]
+  fn reads_need_no_authorization() {
+    for path in [
+      "/systemd/list-units",
+      "/systemd/manager",
+      "/journald/logs",
+      "/power",
+    ] {
+      assert_eq!(action_for(Method::GET, path), None, "{path}");
+    }
+  }
+}
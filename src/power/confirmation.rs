@@ -0,0 +1,138 @@
+//! One-time tokens which have to be requested before a power action, so a single stray
+//! request can't reboot the machine
+
+use std::{
+  collections::HashMap,
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+use crate::{api_errors::ApiError, auth};
+
+use super::dto::PowerAction;
+
+/// How long a token can be used after it was issued
+pub const CONFIRMATION_TTL: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+pub struct Confirmations {
+  tokens: Mutex<HashMap<String, Issued>>,
+}
+
+struct Issued {
+  action: PowerAction,
+
+  /// Name of the identity which asked for the token, `None` without authentication
+  issuer: Option<String>,
+  expires: Instant,
+}
+
+impl Confirmations {
+  /// New token for the action, valid for `CONFIRMATION_TTL` and only for the same identity
+  pub fn issue(&self, action: PowerAction, issuer: Option<&str>) -> Result<String, ApiError> {
+    let token = auth::random_hex(16)?;
+    let now = Instant::now();
+
+    let mut tokens = self.tokens.lock().unwrap();
+    tokens.retain(|_, issued| issued.expires > now);
+    tokens.insert(
+      token.clone(),
+      Issued {
+        action,
+        issuer: issuer.map(str::to_owned),
+        expires: now + CONFIRMATION_TTL,
+      },
+    );
+
+    Ok(token)
+  }
+
+  /// Uses up the token, it has to be unexpired and issued for the same action to the same
+  /// identity
+  pub fn consume(
+    &self,
+    token: &str,
+    action: PowerAction,
+    identity: Option<&str>,
+  ) -> Result<(), ApiError> {
+    let mut tokens = self.tokens.lock().unwrap();
+
+    let issued = match tokens.remove(token) {
+      Some(issued) if issued.expires > Instant::now() && issued.issuer.as_deref() == identity => {
+        issued
+      }
+      _ => {
+        return Err(ApiError::Forbidden(
+          "Confirmation token is invalid or expired".to_owned(),
+        ))
+      }
+    };
+
+    match issued.action == action {
+      true => Ok(()),
+      false => Err(ApiError::Forbidden(format!(
+        "Confirmation token was issued for {}, not {}",
+        issued.action.as_str(),
+        action.as_str()
+      ))),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn tokens_can_be_used_once() {
+    let confirmations = Confirmations::default();
+    let token = confirmations
+      .issue(PowerAction::Reboot, Some("admin"))
+      .unwrap();
+
+    assert!(confirmations
+      .consume(&token, PowerAction::Reboot, Some("admin"))
+      .is_ok());
+    assert!(confirmations
+      .consume(&token, PowerAction::Reboot, Some("admin"))
+      .is_err());
+  }
+
+  #[test]
+  fn tokens_are_bound_to_action() {
+    let confirmations = Confirmations::default();
+    let token = confirmations.issue(PowerAction::Reboot, None).unwrap();
+
+    assert!(confirmations
+      .consume(&token, PowerAction::Poweroff, None)
+      .is_err());
+    // Wrong action used the token up as well
+    assert!(confirmations
+      .consume(&token, PowerAction::Reboot, None)
+      .is_err());
+  }
+
+  #[test]
+  fn tokens_are_bound_to_issuer() {
+    let confirmations = Confirmations::default();
+
+    let token = confirmations
+      .issue(PowerAction::Reboot, Some("admin"))
+      .unwrap();
+    assert!(confirmations
+      .consume(&token, PowerAction::Reboot, Some("operator"))
+      .is_err());
+
+    let token = confirmations
+      .issue(PowerAction::Reboot, Some("admin"))
+      .unwrap();
+    assert!(confirmations
+      .consume(&token, PowerAction::Reboot, None)
+      .is_err());
+
+    let token = confirmations.issue(PowerAction::Reboot, None).unwrap();
+    assert!(confirmations
+      .consume(&token, PowerAction::Reboot, Some("admin"))
+      .is_err());
+  }
+}
@@ -0,0 +1,223 @@
+// This code was autogenerated with `dbus-codegen-rust -m None -g -f org.freedesktop.login1.Manager`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::blocking;
+
+pub trait OrgFreedesktopLogin1Manager {
+  fn power_off(&self, interactive: bool) -> Result<(), dbus::Error>;
+  fn reboot(&self, interactive: bool) -> Result<(), dbus::Error>;
+  fn halt(&self, interactive: bool) -> Result<(), dbus::Error>;
+  fn suspend(&self, interactive: bool) -> Result<(), dbus::Error>;
+  fn hibernate(&self, interactive: bool) -> Result<(), dbus::Error>;
+  fn hybrid_sleep(&self, interactive: bool) -> Result<(), dbus::Error>;
+  fn can_power_off(&self) -> Result<String, dbus::Error>;
+  fn can_reboot(&self) -> Result<String, dbus::Error>;
+  fn can_halt(&self) -> Result<String, dbus::Error>;
+  fn can_suspend(&self) -> Result<String, dbus::Error>;
+  fn can_hibernate(&self) -> Result<String, dbus::Error>;
+  fn can_hybrid_sleep(&self) -> Result<String, dbus::Error>;
+  fn schedule_shutdown(&self, type_: &str, usec: u64) -> Result<(), dbus::Error>;
+  fn cancel_scheduled_shutdown(&self) -> Result<bool, dbus::Error>;
+  fn set_wall_message(&self, wall_message: &str, enable: bool) -> Result<(), dbus::Error>;
+  fn list_inhibitors(&self)
+    -> Result<Vec<(String, String, String, String, u32, u32)>, dbus::Error>;
+  fn enable_wall_messages(&self) -> Result<bool, dbus::Error>;
+  fn set_enable_wall_messages(&self, value: bool) -> Result<(), dbus::Error>;
+  fn wall_message(&self) -> Result<String, dbus::Error>;
+  fn set_wall_message_(&self, value: String) -> Result<(), dbus::Error>;
+  fn block_inhibited(&self) -> Result<String, dbus::Error>;
+  fn delay_inhibited(&self) -> Result<String, dbus::Error>;
+  fn inhibit_delay_max_usec(&self) -> Result<u64, dbus::Error>;
+  fn preparing_for_shutdown(&self) -> Result<bool, dbus::Error>;
+  fn preparing_for_sleep(&self) -> Result<bool, dbus::Error>;
+  fn scheduled_shutdown(&self) -> Result<(String, u64), dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> OrgFreedesktopLogin1Manager
+  for blocking::Proxy<'a, C>
+{
+  fn power_off(&self, interactive: bool) -> Result<(), dbus::Error> {
+    self.method_call("org.freedesktop.login1.Manager", "PowerOff", (interactive,))
+  }
+
+  fn reboot(&self, interactive: bool) -> Result<(), dbus::Error> {
+    self.method_call("org.freedesktop.login1.Manager", "Reboot", (interactive,))
+  }
+
+  fn halt(&self, interactive: bool) -> Result<(), dbus::Error> {
+    self.method_call("org.freedesktop.login1.Manager", "Halt", (interactive,))
+  }
+
+  fn suspend(&self, interactive: bool) -> Result<(), dbus::Error> {
+    self.method_call("org.freedesktop.login1.Manager", "Suspend", (interactive,))
+  }
+
+  fn hibernate(&self, interactive: bool) -> Result<(), dbus::Error> {
+    self.method_call(
+      "org.freedesktop.login1.Manager",
+      "Hibernate",
+      (interactive,),
+    )
+  }
+
+  fn hybrid_sleep(&self, interactive: bool) -> Result<(), dbus::Error> {
+    self.method_call(
+      "org.freedesktop.login1.Manager",
+      "HybridSleep",
+      (interactive,),
+    )
+  }
+
+  fn can_power_off(&self) -> Result<String, dbus::Error> {
+    self
+      .method_call("org.freedesktop.login1.Manager", "CanPowerOff", ())
+      .and_then(|r: (String,)| Ok(r.0))
+  }
+
+  fn can_reboot(&self) -> Result<String, dbus::Error> {
+    self
+      .method_call("org.freedesktop.login1.Manager", "CanReboot", ())
+      .and_then(|r: (String,)| Ok(r.0))
+  }
+
+  fn can_halt(&self) -> Result<String, dbus::Error> {
+    self
+      .method_call("org.freedesktop.login1.Manager", "CanHalt", ())
+      .and_then(|r: (String,)| Ok(r.0))
+  }
+
+  fn can_suspend(&self) -> Result<String, dbus::Error> {
+    self
+      .method_call("org.freedesktop.login1.Manager", "CanSuspend", ())
+      .and_then(|r: (String,)| Ok(r.0))
+  }
+
+  fn can_hibernate(&self) -> Result<String, dbus::Error> {
+    self
+      .method_call("org.freedesktop.login1.Manager", "CanHibernate", ())
+      .and_then(|r: (String,)| Ok(r.0))
+  }
+
+  fn can_hybrid_sleep(&self) -> Result<String, dbus::Error> {
+    self
+      .method_call("org.freedesktop.login1.Manager", "CanHybridSleep", ())
+      .and_then(|r: (String,)| Ok(r.0))
+  }
+
+  fn schedule_shutdown(&self, type_: &str, usec: u64) -> Result<(), dbus::Error> {
+    self.method_call(
+      "org.freedesktop.login1.Manager",
+      "ScheduleShutdown",
+      (type_, usec),
+    )
+  }
+
+  fn cancel_scheduled_shutdown(&self) -> Result<bool, dbus::Error> {
+    self
+      .method_call(
+        "org.freedesktop.login1.Manager",
+        "CancelScheduledShutdown",
+        (),
+      )
+      .and_then(|r: (bool,)| Ok(r.0))
+  }
+
+  fn set_wall_message(&self, wall_message: &str, enable: bool) -> Result<(), dbus::Error> {
+    self.method_call(
+      "org.freedesktop.login1.Manager",
+      "SetWallMessage",
+      (wall_message, enable),
+    )
+  }
+
+  fn list_inhibitors(
+    &self,
+  ) -> Result<Vec<(String, String, String, String, u32, u32)>, dbus::Error> {
+    self
+      .method_call("org.freedesktop.login1.Manager", "ListInhibitors", ())
+      .and_then(|r: (Vec<(String, String, String, String, u32, u32)>,)| Ok(r.0))
+  }
+
+  fn enable_wall_messages(&self) -> Result<bool, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.login1.Manager",
+      "EnableWallMessages",
+    )
+  }
+
+  fn wall_message(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.login1.Manager",
+      "WallMessage",
+    )
+  }
+
+  fn block_inhibited(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.login1.Manager",
+      "BlockInhibited",
+    )
+  }
+
+  fn delay_inhibited(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.login1.Manager",
+      "DelayInhibited",
+    )
+  }
+
+  fn inhibit_delay_max_usec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.login1.Manager",
+      "InhibitDelayMaxUSec",
+    )
+  }
+
+  fn preparing_for_shutdown(&self) -> Result<bool, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.login1.Manager",
+      "PreparingForShutdown",
+    )
+  }
+
+  fn preparing_for_sleep(&self) -> Result<bool, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.login1.Manager",
+      "PreparingForSleep",
+    )
+  }
+
+  fn scheduled_shutdown(&self) -> Result<(String, u64), dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.login1.Manager",
+      "ScheduledShutdown",
+    )
+  }
+
+  fn set_enable_wall_messages(&self, value: bool) -> Result<(), dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
+      &self,
+      "org.freedesktop.login1.Manager",
+      "EnableWallMessages",
+      value,
+    )
+  }
+
+  fn set_wall_message_(&self, value: String) -> Result<(), dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
+      &self,
+      "org.freedesktop.login1.Manager",
+      "WallMessage",
+      value,
+    )
+  }
+}
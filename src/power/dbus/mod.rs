@@ -0,0 +1,3 @@
+// Bindings below are generated by dbus-codegen-rust, so lints are silenced for them.
+#[allow(clippy::all, dead_code)]
+pub mod login1;
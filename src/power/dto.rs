@@ -0,0 +1,142 @@
+use serde::Serialize;
+
+use crate::systemd::dto::JobDto;
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Debug)]
+#[serde(rename_all = "kebab-case")]
+pub enum PowerAction {
+  Reboot,
+  Poweroff,
+  Halt,
+  Kexec,
+  Suspend,
+  Hibernate,
+  HybridSleep,
+}
+
+impl PowerAction {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PowerAction::Reboot => "reboot",
+      PowerAction::Poweroff => "poweroff",
+      PowerAction::Halt => "halt",
+      PowerAction::Kexec => "kexec",
+      PowerAction::Suspend => "suspend",
+      PowerAction::Hibernate => "hibernate",
+      PowerAction::HybridSleep => "hybrid-sleep",
+    }
+  }
+
+  /// Sleep states can't be scheduled, logind only schedules shutdowns
+  pub fn is_shutdown(&self) -> bool {
+    matches!(
+      self,
+      PowerAction::Reboot | PowerAction::Poweroff | PowerAction::Halt | PowerAction::Kexec
+    )
+  }
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmationRequest {
+  pub action: PowerAction,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfirmationDto {
+  /// Pass it as `confirmationToken` with the action, it can be used once
+  pub token: String,
+  pub action: PowerAction,
+
+  /// Seconds the token stays valid
+  pub expires_in: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerRequest {
+  /// Token from `POST /power/confirmations`, issued for the same action to the same identity
+  pub confirmation_token: String,
+
+  /// Schedule the shutdown this many seconds from now instead of doing it right away
+  pub delay_seconds: Option<u64>,
+
+  /// Schedule the shutdown at this time, in microseconds since the epoch
+  pub at: Option<u64>,
+
+  /// Shown to logged in users while the shutdown is scheduled. Replaces logind's wall
+  /// message, which isn't restored if the shutdown gets cancelled.
+  pub wall_message: Option<String>,
+
+  /// Skip stopping units, same as `systemctl --force`. Only for immediate shutdowns.
+  #[serde(default)]
+  pub force: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledShutdownDto {
+  pub action: String,
+
+  /// Microseconds since the epoch
+  pub at: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerActionDto {
+  pub action: PowerAction,
+
+  /// Set when the action was scheduled instead of started right away
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub scheduled: Option<ScheduledShutdownDto>,
+
+  /// Job starting the shutdown target, not set for forced shutdowns and sleep
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub job: Option<JobDto>,
+}
+
+pub type InhibitorTuple = (String, String, String, String, u32, u32);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InhibitorDto {
+  /// What is inhibited, i.e. "shutdown", "sleep" or "idle"
+  pub what: Vec<String>,
+
+  /// Program which took the lock
+  pub who: String,
+  pub why: String,
+
+  /// "block" stops the action, "delay" only holds it off for a moment
+  pub mode: String,
+  pub uid: u32,
+  pub pid: u32,
+}
+
+impl From<InhibitorTuple> for InhibitorDto {
+  fn from(value: InhibitorTuple) -> Self {
+    Self {
+      what: value.0.split(':').map(str::to_owned).collect(),
+      who: value.1,
+      why: value.2,
+      mode: value.3,
+      uid: value.4,
+      pid: value.5,
+    }
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerStatusDto {
+  pub scheduled_shutdown: Option<ScheduledShutdownDto>,
+  pub wall_message: String,
+  pub enable_wall_messages: bool,
+
+  /// Actions blocked or delayed by inhibitors
+  pub block_inhibited: Vec<String>,
+  pub delay_inhibited: Vec<String>,
+  pub preparing_for_shutdown: bool,
+  pub inhibitors: Vec<InhibitorDto>,
+}
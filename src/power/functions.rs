@@ -0,0 +1,160 @@
+use crate::{
+  api_errors::ApiError,
+  dbus_interface::DBusInterface,
+  journald::time::now_usec,
+  systemd::{dbus::manager::OrgFreedesktopSystemd1Manager, dto::JobDto},
+};
+
+use super::{
+  confirmation::Confirmations,
+  dbus::login1::OrgFreedesktopLogin1Manager,
+  dto::{
+    InhibitorDto, PowerAction, PowerActionDto, PowerRequest, PowerStatusDto, ScheduledShutdownDto,
+  },
+};
+
+pub fn status(dbus: &DBusInterface) -> Result<PowerStatusDto, dbus::Error> {
+  let login1 = dbus.login1_manager();
+  let split = |text: String| -> Vec<String> {
+    text
+      .split(':')
+      .filter(|part| !part.is_empty())
+      .map(str::to_owned)
+      .collect()
+  };
+
+  Ok(PowerStatusDto {
+    scheduled_shutdown: scheduled_shutdown(dbus)?,
+    wall_message: login1.wall_message()?,
+    enable_wall_messages: login1.enable_wall_messages()?,
+    block_inhibited: split(login1.block_inhibited()?),
+    delay_inhibited: split(login1.delay_inhibited()?),
+    preparing_for_shutdown: login1.preparing_for_shutdown()?,
+    inhibitors: list_inhibitors(dbus)?,
+  })
+}
+
+pub fn list_inhibitors(dbus: &DBusInterface) -> Result<Vec<InhibitorDto>, dbus::Error> {
+  let inhibitors = dbus.login1_manager().list_inhibitors()?;
+  Ok(inhibitors.into_iter().map(InhibitorDto::from).collect())
+}
+
+fn scheduled_shutdown(dbus: &DBusInterface) -> Result<Option<ScheduledShutdownDto>, dbus::Error> {
+  let (action, at) = dbus.login1_manager().scheduled_shutdown()?;
+
+  Ok(match at {
+    0 => None,
+    at => Some(ScheduledShutdownDto { action, at }),
+  })
+}
+
+/// Runs or schedules the action. Shutdowns start the matching target like `systemctl reboot`,
+/// sleep goes through logind. The confirmation token is used up only once the request is
+/// known to be valid, it has to be issued to the same `identity`.
+pub fn power_action(
+  dbus: &DBusInterface,
+  confirmations: &Confirmations,
+  identity: Option<&str>,
+  action: PowerAction,
+  request: &PowerRequest,
+) -> Result<PowerActionDto, ApiError> {
+  let at = match (request.delay_seconds, request.at) {
+    (Some(_), Some(_)) => {
+      return Err(ApiError::Validation(
+        "Only one of delaySeconds and at can be given".to_owned(),
+      ))
+    }
+    (Some(delay), None) => Some(now_usec().saturating_add(delay.saturating_mul(1_000_000))),
+    (None, Some(at)) if at <= now_usec() => {
+      return Err(ApiError::Validation(
+        "at has to be in the future".to_owned(),
+      ))
+    }
+    (None, at) => at,
+  };
+
+  if let Some(at) = at {
+    return schedule_shutdown(dbus, confirmations, identity, action, at, request);
+  }
+
+  if request.wall_message.is_some() {
+    return Err(ApiError::Validation(
+      "wallMessage can only be used with a scheduled shutdown".to_owned(),
+    ));
+  }
+  if request.force && !action.is_shutdown() {
+    return Err(ApiError::Validation(format!(
+      "force can't be used with {}",
+      action.as_str()
+    )));
+  }
+
+  confirmations.consume(&request.confirmation_token, action, identity)?;
+  let manager = dbus.systemd_manager();
+  let login1 = dbus.login1_manager();
+  let job = match (action, request.force) {
+    (PowerAction::Suspend, _) => login1.suspend(false).map(|_| None)?,
+    (PowerAction::Hibernate, _) => login1.hibernate(false).map(|_| None)?,
+    (PowerAction::HybridSleep, _) => login1.hybrid_sleep(false).map(|_| None)?,
+    (PowerAction::Reboot, true) => manager.reboot().map(|_| None)?,
+    (PowerAction::Poweroff, true) => manager.power_off().map(|_| None)?,
+    (PowerAction::Halt, true) => manager.halt().map(|_| None)?,
+    (PowerAction::Kexec, true) => manager.kexec().map(|_| None)?,
+    (_, false) => {
+      let target = format!("{}.target", action.as_str());
+      let path = manager.start_unit(&target, "replace-irreversibly")?;
+      Some(JobDto::new(path, &target, "start"))
+    }
+  };
+
+  Ok(PowerActionDto {
+    action,
+    scheduled: None,
+    job,
+  })
+}
+
+/// The wall message replaces the one logind has and stays after the shutdown is cancelled,
+/// same as with `shutdown`
+fn schedule_shutdown(
+  dbus: &DBusInterface,
+  confirmations: &Confirmations,
+  identity: Option<&str>,
+  action: PowerAction,
+  at: u64,
+  request: &PowerRequest,
+) -> Result<PowerActionDto, ApiError> {
+  if !action.is_shutdown() {
+    return Err(ApiError::Validation(format!(
+      "{} can't be scheduled, only reboot, poweroff, halt and kexec can",
+      action.as_str()
+    )));
+  }
+  if request.force {
+    return Err(ApiError::Validation(
+      "force can't be used with a scheduled shutdown".to_owned(),
+    ));
+  }
+
+  confirmations.consume(&request.confirmation_token, action, identity)?;
+  let login1 = dbus.login1_manager();
+  if let Some(message) = &request.wall_message {
+    login1.set_wall_message(message, true)?;
+  }
+  login1.schedule_shutdown(action.as_str(), at)?;
+
+  Ok(PowerActionDto {
+    action,
+    scheduled: Some(ScheduledShutdownDto {
+      action: action.as_str().to_owned(),
+      at,
+    }),
+    job: None,
+  })
+}
+
+/// Cancels the scheduled shutdown, `false` if none was scheduled. A wall message set when
+/// scheduling is left as it is.
+pub fn cancel_scheduled_shutdown(dbus: &DBusInterface) -> Result<bool, dbus::Error> {
+  dbus.login1_manager().cancel_scheduled_shutdown()
+}
@@ -0,0 +1,5 @@
+pub mod confirmation;
+pub mod dbus;
+pub mod dto;
+pub mod functions;
+pub mod routes;
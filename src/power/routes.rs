@@ -0,0 +1,112 @@
+use crate::{
+  api_errors::ApiError,
+  auth::dto::Identity,
+  power::{
+    confirmation::CONFIRMATION_TTL,
+    dto::{ConfirmationDto, ConfirmationRequest, PowerAction, PowerRequest},
+    functions,
+  },
+  AppState,
+};
+use actix_web::{
+  delete, get, http::header::ContentType, post, web, HttpMessage, HttpRequest, HttpResponse,
+  Responder,
+};
+
+#[get("")]
+async fn status(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let status = functions::status(&dbus)?;
+
+  let serialized = serde_json::to_string(&status).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[get("/inhibitors")]
+async fn list_inhibitors(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let inhibitors = functions::list_inhibitors(&dbus)?;
+
+  let serialized = serde_json::to_string(&inhibitors).unwrap_or("[]".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[post("/confirmations")]
+async fn create_confirmation(
+  state: web::Data<AppState<'static>>,
+  req: HttpRequest,
+  request: web::Json<ConfirmationRequest>,
+) -> Result<impl Responder, ApiError> {
+  let identity = identity_name(&req);
+  let confirmation = ConfirmationDto {
+    token: state
+      .power_confirmations
+      .issue(request.action, identity.as_deref())?,
+    action: request.action,
+    expires_in: CONFIRMATION_TTL.as_secs(),
+  };
+
+  let serialized = serde_json::to_string(&confirmation).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Created()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[post("/{action}")]
+async fn power_action(
+  state: web::Data<AppState<'static>>,
+  req: HttpRequest,
+  path: web::Path<PowerAction>,
+  request: web::Json<PowerRequest>,
+) -> Result<impl Responder, ApiError> {
+  let identity = identity_name(&req);
+  let dbus = state.dbus.lock().unwrap();
+  let result = functions::power_action(
+    &dbus,
+    &state.power_confirmations,
+    identity.as_deref(),
+    path.into_inner(),
+    &request,
+  )?;
+
+  let serialized = serde_json::to_string(&result).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Accepted()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[delete("/scheduled")]
+async fn cancel_scheduled_shutdown(
+  state: web::Data<AppState<'static>>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  if !functions::cancel_scheduled_shutdown(&dbus)? {
+    return Err(ApiError::NotFound("No shutdown is scheduled".to_owned()));
+  }
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+/// Confirmation tokens can only be used by the identity they were issued to
+fn identity_name(req: &HttpRequest) -> Option<String> {
+  req
+    .extensions()
+    .get::<Identity>()
+    .map(|identity| identity.name.clone())
+}
@@ -0,0 +1,74 @@
+//! Sockets systemd passes to socket activated services, see sd_listen_fds(3)
+
+use std::{
+  io,
+  net::TcpListener,
+  os::{
+    fd::{FromRawFd, RawFd},
+    unix::net::UnixListener,
+  },
+};
+
+use super::Listener;
+
+/// First passed file descriptor, the ones before are stdin, stdout and stderr
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Listeners passed with `LISTEN_FDS`, empty when dragond wasn't socket activated. Environment
+/// variables are removed, so child processes don't take the sockets for theirs. Changing the
+/// environment races with threads reading it, so this has to be called before any is started.
+pub fn listeners() -> io::Result<Vec<Listener>> {
+  let pid = std::env::var("LISTEN_PID").ok();
+  let count = std::env::var("LISTEN_FDS").ok();
+  std::env::remove_var("LISTEN_PID");
+  std::env::remove_var("LISTEN_FDS");
+  std::env::remove_var("LISTEN_FDNAMES");
+
+  // LISTEN_PID tells whether the sockets are meant for this process or a parent
+  if pid.and_then(|pid| pid.parse::<u32>().ok()) != Some(std::process::id()) {
+    return Ok(Vec::new());
+  }
+  let count = match count.and_then(|count| count.parse::<RawFd>().ok()) {
+    Some(count) => count,
+    None => return Ok(Vec::new()),
+  };
+
+  (LISTEN_FDS_START..LISTEN_FDS_START + count)
+    .map(|fd| {
+      unsafe {
+        libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC);
+      }
+
+      match socket_family(fd)? {
+        libc::AF_UNIX => Ok(Listener::Unix(unsafe { UnixListener::from_raw_fd(fd) })),
+        libc::AF_INET | libc::AF_INET6 => {
+          Ok(Listener::Tcp(unsafe { TcpListener::from_raw_fd(fd) }))
+        }
+        family => Err(io::Error::new(
+          io::ErrorKind::InvalidInput,
+          format!(
+            "Passed file descriptor {} has unsupported family {}",
+            fd, family
+          ),
+        )),
+      }
+    })
+    .collect()
+}
+
+fn socket_family(fd: RawFd) -> io::Result<libc::c_int> {
+  unsafe {
+    let mut address: libc::sockaddr_storage = std::mem::zeroed();
+    let mut length = std::mem::size_of::<libc::sockaddr_storage>() as libc::socklen_t;
+    let status = libc::getsockname(
+      fd,
+      &mut address as *mut libc::sockaddr_storage as *mut libc::sockaddr,
+      &mut length,
+    );
+
+    match status {
+      0 => Ok(address.ss_family as libc::c_int),
+      _ => Err(io::Error::last_os_error()),
+    }
+  }
+}
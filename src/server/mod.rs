@@ -0,0 +1,229 @@
+//! Sockets dragond listens on: TCP addresses, optionally with TLS, Unix sockets, and sockets
+//! passed by systemd socket activation
+
+pub mod activation;
+pub mod tls;
+
+use std::{
+  any::Any,
+  cell::RefCell,
+  fs,
+  future::poll_fn,
+  io,
+  net::{TcpListener, ToSocketAddrs},
+  os::unix::{
+    fs::{FileTypeExt, PermissionsExt},
+    net::UnixListener,
+  },
+  pin::Pin,
+  rc::Rc,
+  task::{Context, Poll},
+};
+
+use actix_tls::accept::rustls_0_23::TlsStream;
+use actix_web::{
+  dev::{Extensions, Payload, ServiceRequest},
+  error::PayloadError,
+  rt::net::TcpStream,
+  web::{Bytes, BytesMut},
+  Error, HttpMessage,
+};
+use futures_core::Stream;
+use sha2::{Digest, Sha256};
+
+use crate::{api_errors::ApiError, auth::ClientCertificate, config::ServerConfig};
+
+/// Largest request body, for JSON handlers and middlewares reading the body alike
+pub const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+pub enum Listener {
+  Tcp(TcpListener),
+  Unix(UnixListener),
+}
+
+impl Listener {
+  pub fn describe(&self) -> String {
+    let address = match self {
+      Listener::Tcp(listener) => listener.local_addr().map(|address| address.to_string()),
+      Listener::Unix(listener) => {
+        listener
+          .local_addr()
+          .map(|address| match address.as_pathname() {
+            Some(path) => format!("unix:{}", path.display()),
+            None => "unix socket".to_owned(),
+          })
+      }
+    };
+    address.unwrap_or_else(|_| "unknown address".to_owned())
+  }
+}
+
+/// Sockets passed by systemd if there are any, addresses from `bind` otherwise
+pub fn listeners(config: &ServerConfig) -> io::Result<Vec<Listener>> {
+  let activated = activation::listeners()?;
+  if !activated.is_empty() {
+    info!("Using {} sockets passed by systemd", activated.len());
+    return Ok(activated);
+  }
+
+  if config.bind.is_empty() {
+    return Err(io::Error::new(
+      io::ErrorKind::InvalidInput,
+      "No addresses to listen on",
+    ));
+  }
+
+  config
+    .bind
+    .iter()
+    .map(|address| match address.strip_prefix("unix:") {
+      Some(path) => bind_unix(path, config.socket_mode).map(Listener::Unix),
+      None => bind_tcp(address).map(Listener::Tcp),
+    })
+    .collect()
+}
+
+fn bind_tcp(address: &str) -> io::Result<TcpListener> {
+  let addresses: Vec<_> = address
+    .to_socket_addrs()
+    .map_err(|err| io::Error::new(err.kind(), format!("Invalid address {}: {}", address, err)))?
+    .collect();
+
+  TcpListener::bind(&addresses[..])
+    .map_err(|err| io::Error::new(err.kind(), format!("Can't listen on {}: {}", address, err)))
+}
+
+/// Binds the socket, replacing a socket file left behind by a previous run
+fn bind_unix(path: &str, mode: u32) -> io::Result<UnixListener> {
+  if let Ok(meta) = fs::symlink_metadata(path) {
+    if !meta.file_type().is_socket() {
+      return Err(io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} exists and isn't a socket", path),
+      ));
+    }
+    fs::remove_file(path)?;
+  }
+
+  let listener = UnixListener::bind(path)
+    .map_err(|err| io::Error::new(err.kind(), format!("Can't listen on {}: {}", path, err)))?;
+  fs::set_permissions(path, fs::Permissions::from_mode(mode))?;
+  Ok(listener)
+}
+
+/// Stores the client certificate of TLS connections in connection data, for authentication
+pub fn on_connect(connection: &dyn Any, data: &mut Extensions) {
+  let certificate = connection
+    .downcast_ref::<TlsStream<TcpStream>>()
+    .and_then(|stream| stream.get_ref().1.peer_certificates())
+    .and_then(|certificates| certificates.first());
+
+  if let Some(certificate) = certificate {
+    data.insert(ClientCertificate {
+      fingerprint: Sha256::digest(certificate).into(),
+    });
+  }
+}
+
+/// Reads the whole body in a middleware, and puts it back for the handler
+pub async fn read_body(req: &mut ServiceRequest) -> Result<Bytes, Error> {
+  let mut payload = req.take_payload();
+  let mut body = BytesMut::new();
+
+  while let Some(chunk) = poll_fn(|cx| Pin::new(&mut payload).poll_next(cx)).await {
+    body.extend_from_slice(&chunk?);
+    if body.len() > MAX_BODY_SIZE {
+      return Err(ApiError::Validation("Request body is too large".to_owned()).into());
+    }
+  }
+
+  let body = body.freeze();
+  req.set_payload(Payload::from(body.clone()));
+  Ok(body)
+}
+
+/// Copy of a request body, made while the handler reads it
+#[derive(Clone, Default)]
+pub struct BodyCopy(Rc<RefCell<(BytesMut, bool)>>);
+
+impl BodyCopy {
+  /// The copied body, `None` when it was larger than the limit
+  pub fn take(&self) -> Option<Bytes> {
+    let (body, truncated) = &mut *self.0.borrow_mut();
+    match truncated {
+      true => None,
+      false => Some(std::mem::take(body).freeze()),
+    }
+  }
+}
+
+/// Copies the body up to `limit` bytes as it is read. Unlike `read_body`, nothing is read
+/// before the handler does, so the handler's limits apply.
+pub fn copy_body(req: &mut ServiceRequest, limit: usize) -> BodyCopy {
+  let copy = BodyCopy::default();
+  let payload = CopiedPayload {
+    payload: req.take_payload(),
+    copy: copy.clone(),
+    limit,
+  };
+  req.set_payload(Payload::from(
+    Box::pin(payload) as Pin<Box<dyn Stream<Item = Result<Bytes, PayloadError>>>>
+  ));
+  copy
+}
+
+struct CopiedPayload {
+  payload: Payload,
+  copy: BodyCopy,
+  limit: usize,
+}
+
+impl Stream for CopiedPayload {
+  type Item = Result<Bytes, PayloadError>;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    let poll = Pin::new(&mut self.payload).poll_next(cx);
+    if let Poll::Ready(Some(Ok(chunk))) = &poll {
+      let (body, truncated) = &mut *self.copy.0.borrow_mut();
+      match body.len() + chunk.len() > self.limit {
+        true => {
+          *truncated = true;
+          body.clear();
+        }
+        false if !*truncated => body.extend_from_slice(chunk),
+        false => {}
+      }
+    }
+    poll
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use actix_web::test::TestRequest;
+
+  use super::*;
+
+  #[actix_web::test]
+  async fn copies_body_as_it_is_read() {
+    let mut req = TestRequest::post()
+      .set_payload(r#"{"unit":"a.service"}"#)
+      .to_srv_request();
+    let copy = copy_body(&mut req, 1024);
+    assert_eq!(copy.take().as_deref(), Some(&b""[..]));
+
+    let body = read_body(&mut req).await.unwrap();
+    assert_eq!(copy.take(), Some(body));
+  }
+
+  #[actix_web::test]
+  async fn leaves_out_large_bodies() {
+    let mut req = TestRequest::post()
+      .set_payload("x".repeat(100))
+      .to_srv_request();
+    let copy = copy_body(&mut req, 10);
+
+    assert_eq!(read_body(&mut req).await.unwrap().len(), 100);
+    assert_eq!(copy.take(), None);
+  }
+}
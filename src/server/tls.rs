@@ -0,0 +1,152 @@
+//! rustls setup. The certificate and key are reloaded when their files change, so renewed
+//! certificates are picked up without a restart.
+
+use std::{
+  fs,
+  sync::{Arc, RwLock},
+  time::{Duration, SystemTime},
+};
+
+use rustls::{
+  crypto::{ring, CryptoProvider},
+  pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+  server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier},
+  sign::CertifiedKey,
+  RootCertStore, ServerConfig,
+};
+
+use crate::config::{ClientAuth, TlsConfig};
+
+pub fn server_config(config: &TlsConfig) -> Result<ServerConfig, String> {
+  let provider = Arc::new(ring::default_provider());
+  let resolver = Arc::new(ReloadingCertificate::new(config, &provider)?);
+  if config.reload_interval > 0 {
+    resolver.spawn_watcher(Duration::from_secs(config.reload_interval));
+  }
+
+  let builder = ServerConfig::builder_with_provider(Arc::clone(&provider))
+    .with_safe_default_protocol_versions()
+    .map_err(|err| format!("Can't set up TLS: {}", err))?;
+
+  let builder = match &config.client_ca {
+    Some(client_ca) => {
+      let mut roots = RootCertStore::empty();
+      for certificate in read_certificates(client_ca)? {
+        roots
+          .add(certificate)
+          .map_err(|err| format!("Invalid client CA in {}: {}", client_ca, err))?;
+      }
+
+      let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+      let verifier = match config.client_auth {
+        ClientAuth::Required => verifier,
+        ClientAuth::Optional => verifier.allow_unauthenticated(),
+      };
+      builder.with_client_cert_verifier(
+        verifier
+          .build()
+          .map_err(|err| format!("Can't set up client certificates: {}", err))?,
+      )
+    }
+    None => builder.with_no_client_auth(),
+  };
+
+  Ok(builder.with_cert_resolver(resolver))
+}
+
+fn read_certificates(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+  let certificates = CertificateDer::pem_file_iter(path)
+    .and_then(|certificates| certificates.collect::<Result<Vec<_>, _>>())
+    .map_err(|err| format!("Can't read certificates from {}: {}", path, err))?;
+
+  match certificates.is_empty() {
+    true => Err(format!("{} has no certificates", path)),
+    false => Ok(certificates),
+  }
+}
+
+fn load(
+  certificate_path: &str,
+  key_path: &str,
+  provider: &CryptoProvider,
+) -> Result<CertifiedKey, String> {
+  let certificates = read_certificates(certificate_path)?;
+  let key = PrivateKeyDer::from_pem_file(key_path)
+    .map_err(|err| format!("Can't read private key from {}: {}", key_path, err))?;
+
+  CertifiedKey::from_der(certificates, key, provider).map_err(|err| {
+    format!(
+      "Certificate {} doesn't work with its key: {}",
+      certificate_path, err
+    )
+  })
+}
+
+fn modified(path: &str) -> Option<SystemTime> {
+  fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Serves the certificate and key from the config, reloading them when the files change
+#[derive(Debug)]
+struct ReloadingCertificate {
+  certificate_path: String,
+  key_path: String,
+  provider: Arc<CryptoProvider>,
+  current: RwLock<Arc<CertifiedKey>>,
+}
+
+impl ReloadingCertificate {
+  fn new(config: &TlsConfig, provider: &Arc<CryptoProvider>) -> Result<Self, String> {
+    let current = load(&config.certificate, &config.key, provider)?;
+
+    Ok(ReloadingCertificate {
+      certificate_path: config.certificate.clone(),
+      key_path: config.key.clone(),
+      provider: Arc::clone(provider),
+      current: RwLock::new(Arc::new(current)),
+    })
+  }
+
+  /// Checks the files every `interval`. A broken certificate or key is logged and the previous
+  /// one stays in use, so a half-finished renewal doesn't take the API down.
+  fn spawn_watcher(self: &Arc<Self>, interval: Duration) {
+    let resolver = Arc::clone(self);
+
+    std::thread::spawn(move || {
+      let stamps = || {
+        (
+          modified(&resolver.certificate_path),
+          modified(&resolver.key_path),
+        )
+      };
+      let mut last = stamps();
+
+      loop {
+        std::thread::sleep(interval);
+        let current = stamps();
+        if current == last {
+          continue;
+        }
+        last = current;
+
+        match load(
+          &resolver.certificate_path,
+          &resolver.key_path,
+          &resolver.provider,
+        ) {
+          Ok(certified) => {
+            *resolver.current.write().unwrap() = Arc::new(certified);
+            info!("Reloaded TLS certificate {}", resolver.certificate_path);
+          }
+          Err(err) => error!("Keeping the previous TLS certificate: {}", err),
+        }
+      }
+    });
+  }
+}
+
+impl ResolvesServerCert for ReloadingCertificate {
+  fn resolve(&self, _client_hello: ClientHello<'_>) -> Option<Arc<CertifiedKey>> {
+    Some(Arc::clone(&self.current.read().unwrap()))
+  }
+}
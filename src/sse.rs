@@ -0,0 +1,95 @@
+use std::{
+  pin::Pin,
+  task::{Context, Poll},
+  time::Duration,
+};
+
+use actix_web::{
+  http::header::{self, CacheControl, CacheDirective},
+  web::Bytes,
+  HttpResponse,
+};
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+/// How often a comment is sent to idle clients, keeps proxies from closing the connection and
+/// lets producers notice that the client went away
+pub const KEEPALIVE_INTERVAL: Duration = Duration::from_secs(15);
+
+/// How many formatted events can wait for a slow client
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Sending half of a Server-Sent Events response
+#[derive(Clone)]
+pub struct SseSender {
+  sender: mpsc::Sender<Bytes>,
+}
+
+impl SseSender {
+  /// Sends one event. Returns false if the client disconnected.
+  pub async fn send(&self, event: &str, id: Option<&str>, data: &str) -> bool {
+    self
+      .sender
+      .send(format_event(event, id, data))
+      .await
+      .is_ok()
+  }
+
+  pub async fn keepalive(&self) -> bool {
+    self
+      .sender
+      .send(Bytes::from_static(b": keepalive\n\n"))
+      .await
+      .is_ok()
+  }
+}
+
+/// Creates a streaming `text/event-stream` response and a sender for pushing events into it.
+/// Stream ends when every sender is dropped.
+pub fn channel() -> (SseSender, HttpResponse) {
+  let (sender, receiver) = mpsc::channel(CHANNEL_CAPACITY);
+
+  let response = HttpResponse::Ok()
+    .insert_header((header::CONTENT_TYPE, "text/event-stream"))
+    .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+    // Tells nginx not to buffer the stream
+    .insert_header(("X-Accel-Buffering", "no"))
+    .streaming(ReceiverStream::new(receiver));
+
+  (SseSender { sender }, response)
+}
+
+fn format_event(event: &str, id: Option<&str>, data: &str) -> Bytes {
+  let mut message = format!("event: {}\n", event);
+
+  if let Some(id) = id {
+    message.push_str(&format!("id: {}\n", id));
+  }
+
+  // Multiline data has to be split into multiple data fields
+  for line in data.lines() {
+    message.push_str(&format!("data: {}\n", line));
+  }
+  message.push('\n');
+
+  Bytes::from(message)
+}
+
+/// Body stream of chunks pushed through a channel, ends when every sender is dropped
+pub struct ReceiverStream {
+  receiver: mpsc::Receiver<Bytes>,
+}
+
+impl ReceiverStream {
+  pub fn new(receiver: mpsc::Receiver<Bytes>) -> Self {
+    ReceiverStream { receiver }
+  }
+}
+
+impl Stream for ReceiverStream {
+  type Item = Result<Bytes, actix_web::Error>;
+
+  fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    self.receiver.poll_recv(cx).map(|bytes| bytes.map(Ok))
+  }
+}
@@ -0,0 +1,282 @@
+//! Boot performance, like `systemd-analyze time`, `blame` and `critical-chain` show it
+
+use std::{cmp::Reverse, collections::HashMap, ops::Deref};
+
+use dbus::arg::{prop_cast, PropMap};
+
+use crate::{api_errors::ApiError, dbus_interface::DBusInterface};
+
+use super::{
+  dbus::{manager::OrgFreedesktopSystemd1Manager, unit::OrgFreedesktopSystemd1Unit},
+  dto::{BlameEntry, BootTimesDto, CriticalChainDto, CriticalChainNode},
+  functions,
+};
+
+/// How deep the critical chain is followed, real chains are a few dozen units at most
+const MAX_CHAIN_DEPTH: usize = 128;
+
+/// Chains are cut off after this many nodes. With a large fuzz, units many others wait for
+/// would be walked again on every path to them.
+const MAX_CHAIN_NODES: usize = 1000;
+
+/// Largest fuzz taken, a minute already takes in most of a typical boot
+pub const MAX_CHAIN_FUZZ_USEC: u64 = 60 * 1_000_000;
+
+/// When a unit started and finished activating, in microseconds since boot
+#[derive(Clone, Copy)]
+struct UnitTimes {
+  activating: u64,
+  activated: u64,
+}
+
+impl UnitTimes {
+  fn from_properties(properties: &PropMap) -> UnitTimes {
+    let timestamp = |property: &str| prop_cast::<u64>(properties, property).copied().unwrap_or(0);
+
+    let activating = timestamp("InactiveExitTimestampMonotonic");
+    let mut activated = timestamp("ActiveEnterTimestampMonotonic");
+
+    // Units which already stopped again, i.e. oneshot services, finished when they
+    // deactivated
+    let deactivating = timestamp("ActiveExitTimestampMonotonic");
+    if activated == 0 && deactivating > activating {
+      activated = deactivating;
+    }
+
+    UnitTimes {
+      activating,
+      activated,
+    }
+  }
+
+  /// Time the unit took to activate, 0 if it's unknown
+  fn duration(&self) -> u64 {
+    match self.activating > 0 && self.activated > self.activating {
+      true => self.activated - self.activating,
+      false => 0,
+    }
+  }
+}
+
+/// Time spent in each boot phase, same as `systemd-analyze time` adds up
+pub fn boot_times(dbus: &DBusInterface) -> Result<BootTimesDto, dbus::Error> {
+  let manager = dbus.systemd_manager();
+
+  // Firmware and loader timestamps count backwards from the kernel start
+  let firmware = manager.firmware_timestamp_monotonic()?;
+  let loader = manager.loader_timestamp_monotonic()?;
+  let initrd = manager.init_rdtimestamp_monotonic()?;
+  let userspace = manager.userspace_timestamp_monotonic()?;
+  let finish = manager.finish_timestamp_monotonic()?;
+
+  let phase = |start: u64, end: u64| match start > 0 && end > start {
+    true => Some(end - start),
+    false => None,
+  };
+  let finished = finish > 0;
+
+  let default_target = manager.get_default_target()?;
+  let target_path = manager.load_unit(&default_target)?;
+  let reached = dbus
+    .systemd_unit(target_path.deref())
+    .active_enter_timestamp_monotonic()?;
+
+  Ok(BootTimesDto {
+    finished,
+    firmware_usec: phase(loader, firmware).or((firmware > 0).then_some(firmware)),
+    loader_usec: (loader > 0).then_some(loader),
+    kernel_usec: match initrd > 0 {
+      true => initrd,
+      false => userspace,
+    },
+    initrd_usec: phase(initrd, userspace),
+    userspace_usec: finished.then(|| finish.saturating_sub(userspace)),
+    total_usec: finished.then_some(firmware + finish),
+    security_usec: phase(
+      manager.security_start_timestamp_monotonic()?,
+      manager.security_finish_timestamp_monotonic()?,
+    ),
+    generators_usec: phase(
+      manager.generators_start_timestamp_monotonic()?,
+      manager.generators_finish_timestamp_monotonic()?,
+    ),
+    units_load_usec: phase(
+      manager.units_load_start_timestamp_monotonic()?,
+      manager.units_load_finish_timestamp_monotonic()?,
+    ),
+    default_target,
+    default_target_reached_usec: (reached > 0).then(|| reached.saturating_sub(userspace)),
+  })
+}
+
+/// Loaded units sorted by the time they took to activate, longest first. Units which never
+/// activated are left out.
+pub fn blame(dbus: &DBusInterface) -> Result<Vec<BlameEntry>, dbus::Error> {
+  let mut entries = Vec::new();
+
+  for unit in functions::list_units(dbus)? {
+    let properties = functions::unit_properties(dbus, &unit.object_path)?;
+    let times = UnitTimes::from_properties(&properties);
+
+    if times.duration() > 0 {
+      entries.push(BlameEntry {
+        unit: unit.name,
+        activating_usec: times.activating,
+        activated_usec: times.activated,
+        duration_usec: times.duration(),
+      });
+    }
+  }
+
+  entries.sort_by_key(|entry| Reverse(entry.duration_usec));
+  Ok(entries)
+}
+
+/// Follows `After=` dependencies of a unit back to the start of userspace, taking the ones
+/// which activated last, like `systemd-analyze critical-chain`. Dependencies activating
+/// within `fuzz_usec` of the last one are followed too, up to `MAX_CHAIN_NODES` units.
+pub fn critical_chain(
+  dbus: &DBusInterface,
+  unit: Option<&str>,
+  fuzz_usec: u64,
+) -> Result<CriticalChainDto, ApiError> {
+  let manager = dbus.systemd_manager();
+  let userspace = manager.userspace_timestamp_monotonic()?;
+  let finish = manager.finish_timestamp_monotonic()?;
+  if finish == 0 {
+    return Err(ApiError::Conflict(
+      "Bootup is not yet finished, critical chain isn't known yet".to_owned(),
+    ));
+  }
+
+  let unit = match unit {
+    Some(unit) => unit.to_owned(),
+    None => manager.get_default_target()?,
+  };
+
+  let mut chain = ChainWalker {
+    dbus,
+    userspace,
+    finish,
+    fuzz_usec: fuzz_usec.min(MAX_CHAIN_FUZZ_USEC),
+    units: HashMap::new(),
+    nodes: 1,
+    truncated: false,
+  };
+  let (id, times, after) = chain.unit(&unit, true)?;
+
+  let root = CriticalChainNode {
+    activated_usec: times.activated.saturating_sub(userspace),
+    duration_usec: times.duration(),
+    children: chain.follow(&after, &mut vec![id.clone()])?,
+    unit: id,
+  };
+  Ok(CriticalChainDto {
+    root,
+    truncated: chain.truncated,
+  })
+}
+
+struct ChainWalker<'a> {
+  dbus: &'a DBusInterface<'a>,
+  userspace: u64,
+  finish: u64,
+  fuzz_usec: u64,
+
+  /// Units looked up so far, `None` for units which aren't loaded
+  units: HashMap<String, Option<(UnitTimes, Vec<String>)>>,
+
+  /// Nodes in the chain so far, and whether any were left out for `MAX_CHAIN_NODES`
+  nodes: usize,
+  truncated: bool,
+}
+
+impl ChainWalker<'_> {
+  /// Id, times and `After=` dependencies of a unit. Only the starting unit is loaded if it
+  /// isn't already, dependencies which aren't loaded never activated anyway.
+  fn unit(&self, name: &str, load: bool) -> Result<(String, UnitTimes, Vec<String>), ApiError> {
+    let manager = self.dbus.systemd_manager();
+    let path = match load {
+      true => manager.load_unit(name)?,
+      false => manager.get_unit(name)?,
+    };
+
+    let properties = functions::unit_properties(self.dbus, path.deref())?;
+    let id = prop_cast::<String>(&properties, "Id")
+      .cloned()
+      .unwrap_or_else(|| name.to_owned());
+    let after = prop_cast::<Vec<String>>(&properties, "After")
+      .cloned()
+      .unwrap_or_default();
+
+    Ok((id, UnitTimes::from_properties(&properties), after))
+  }
+
+  fn cached(&mut self, name: &str) -> Option<(UnitTimes, Vec<String>)> {
+    if let Some(cached) = self.units.get(name) {
+      return cached.clone();
+    }
+
+    let unit = self
+      .unit(name, false)
+      .ok()
+      .map(|(_, times, after)| (times, after));
+    self.units.insert(name.to_owned(), unit.clone());
+    unit
+  }
+
+  /// Dependencies which activated during boot, `path` is the chain so far to detect cycles
+  fn follow(
+    &mut self,
+    after: &[String],
+    path: &mut Vec<String>,
+  ) -> Result<Vec<CriticalChainNode>, ApiError> {
+    if path.len() >= MAX_CHAIN_DEPTH {
+      return Ok(Vec::new());
+    }
+
+    let mut candidates = Vec::new();
+    for name in after {
+      if let Some((times, after)) = self.cached(name) {
+        if times.activated > 0 && times.activated <= self.finish {
+          candidates.push((name.clone(), times, after));
+        }
+      }
+    }
+
+    let latest = match candidates.iter().map(|(_, times, _)| times.activated).max() {
+      Some(latest) => latest,
+      None => return Ok(Vec::new()),
+    };
+    candidates.retain(|(_, times, _)| latest - times.activated <= self.fuzz_usec);
+    candidates.sort_by_key(|(_, times, _)| Reverse(times.activated));
+
+    let mut nodes = Vec::new();
+    for (name, times, after) in candidates {
+      if self.nodes >= MAX_CHAIN_NODES {
+        self.truncated = true;
+        break;
+      }
+      self.nodes += 1;
+
+      let children = match path.contains(&name) {
+        true => Vec::new(),
+        false => {
+          path.push(name.clone());
+          let children = self.follow(&after, path)?;
+          path.pop();
+          children
+        }
+      };
+
+      nodes.push(CriticalChainNode {
+        unit: name,
+        activated_usec: times.activated.saturating_sub(self.userspace),
+        duration_usec: times.duration(),
+        children,
+      });
+    }
+
+    Ok(nodes)
+  }
+}
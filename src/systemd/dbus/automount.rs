@@ -0,0 +1,57 @@
+// This code was autogenerated with `dbus-codegen-rust -m None -g -f org.freedesktop.systemd1.Automount`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::blocking;
+
+pub trait OrgFreedesktopSystemd1Automount {
+  fn where_(&self) -> Result<String, dbus::Error>;
+  fn extra_options(&self) -> Result<String, dbus::Error>;
+  fn directory_mode(&self) -> Result<u32, dbus::Error>;
+  fn timeout_idle_usec(&self) -> Result<u64, dbus::Error>;
+  fn result(&self) -> Result<String, dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>>
+  OrgFreedesktopSystemd1Automount for blocking::Proxy<'a, C>
+{
+  fn where_(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Automount",
+      "Where",
+    )
+  }
+
+  fn extra_options(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Automount",
+      "ExtraOptions",
+    )
+  }
+
+  fn directory_mode(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Automount",
+      "DirectoryMode",
+    )
+  }
+
+  fn timeout_idle_usec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Automount",
+      "TimeoutIdleUSec",
+    )
+  }
+
+  fn result(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Automount",
+      "Result",
+    )
+  }
+}
@@ -0,0 +1,21 @@
+// This code was autogenerated with `dbus-codegen-rust -m None -g -f org.freedesktop.systemd1.Device`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::blocking;
+
+pub trait OrgFreedesktopSystemd1Device {
+  fn sys_fspath(&self) -> Result<String, dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> OrgFreedesktopSystemd1Device
+  for blocking::Proxy<'a, C>
+{
+  fn sys_fspath(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Device",
+      "SysFSPath",
+    )
+  }
+}
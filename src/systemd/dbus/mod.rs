@@ -1,3 +1,27 @@
+// Bindings below are generated by dbus-codegen-rust, so lints are silenced for them.
+#[allow(clippy::all, dead_code)]
+pub mod automount;
+#[allow(clippy::all, dead_code)]
+pub mod device;
+#[allow(clippy::all, dead_code)]
+pub mod job;
+#[allow(clippy::all, dead_code)]
 pub mod manager;
+#[allow(clippy::all, dead_code)]
+pub mod mount;
+#[allow(clippy::all, dead_code)]
+pub mod path;
+#[allow(clippy::all, dead_code)]
+pub mod scope;
+#[allow(clippy::all, dead_code)]
 pub mod service;
+#[allow(clippy::all, dead_code)]
+pub mod slice;
+#[allow(clippy::all, dead_code)]
+pub mod socket;
+#[allow(clippy::all, dead_code)]
+pub mod swap;
+#[allow(clippy::all, dead_code)]
+pub mod timer;
+#[allow(clippy::all, dead_code)]
 pub mod unit;
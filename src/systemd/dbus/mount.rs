@@ -0,0 +1,165 @@
+// This code was autogenerated with `dbus-codegen-rust -m None -g -f org.freedesktop.systemd1.Mount`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::blocking;
+
+pub trait OrgFreedesktopSystemd1Mount {
+  fn where_(&self) -> Result<String, dbus::Error>;
+  fn what(&self) -> Result<String, dbus::Error>;
+  fn options(&self) -> Result<String, dbus::Error>;
+  fn type_(&self) -> Result<String, dbus::Error>;
+  fn timeout_usec(&self) -> Result<u64, dbus::Error>;
+  fn control_pid(&self) -> Result<u32, dbus::Error>;
+  fn directory_mode(&self) -> Result<u32, dbus::Error>;
+  fn sloppy_options(&self) -> Result<bool, dbus::Error>;
+  fn lazy_unmount(&self) -> Result<bool, dbus::Error>;
+  fn force_unmount(&self) -> Result<bool, dbus::Error>;
+  fn read_write_only(&self) -> Result<bool, dbus::Error>;
+  fn result(&self) -> Result<String, dbus::Error>;
+  fn slice(&self) -> Result<String, dbus::Error>;
+  fn control_group(&self) -> Result<String, dbus::Error>;
+  fn memory_current(&self) -> Result<u64, dbus::Error>;
+  fn cpuusage_nsec(&self) -> Result<u64, dbus::Error>;
+  fn tasks_current(&self) -> Result<u64, dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> OrgFreedesktopSystemd1Mount
+  for blocking::Proxy<'a, C>
+{
+  fn where_(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "Where",
+    )
+  }
+
+  fn what(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "What",
+    )
+  }
+
+  fn options(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "Options",
+    )
+  }
+
+  fn type_(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "Type",
+    )
+  }
+
+  fn timeout_usec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "TimeoutUSec",
+    )
+  }
+
+  fn control_pid(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "ControlPID",
+    )
+  }
+
+  fn directory_mode(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "DirectoryMode",
+    )
+  }
+
+  fn sloppy_options(&self) -> Result<bool, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "SloppyOptions",
+    )
+  }
+
+  fn lazy_unmount(&self) -> Result<bool, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "LazyUnmount",
+    )
+  }
+
+  fn force_unmount(&self) -> Result<bool, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "ForceUnmount",
+    )
+  }
+
+  fn read_write_only(&self) -> Result<bool, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "ReadWriteOnly",
+    )
+  }
+
+  fn result(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "Result",
+    )
+  }
+
+  fn slice(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "Slice",
+    )
+  }
+
+  fn control_group(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "ControlGroup",
+    )
+  }
+
+  fn memory_current(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "MemoryCurrent",
+    )
+  }
+
+  fn cpuusage_nsec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "CPUUsageNSec",
+    )
+  }
+
+  fn tasks_current(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Mount",
+      "TasksCurrent",
+    )
+  }
+}
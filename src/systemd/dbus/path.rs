@@ -0,0 +1,57 @@
+// This code was autogenerated with `dbus-codegen-rust -m None -g -f org.freedesktop.systemd1.Path`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::blocking;
+
+pub trait OrgFreedesktopSystemd1Path {
+  fn unit(&self) -> Result<String, dbus::Error>;
+  fn paths(&self) -> Result<Vec<(String, String)>, dbus::Error>;
+  fn make_directory(&self) -> Result<bool, dbus::Error>;
+  fn directory_mode(&self) -> Result<u32, dbus::Error>;
+  fn result(&self) -> Result<String, dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> OrgFreedesktopSystemd1Path
+  for blocking::Proxy<'a, C>
+{
+  fn unit(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Path",
+      "Unit",
+    )
+  }
+
+  fn paths(&self) -> Result<Vec<(String, String)>, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Path",
+      "Paths",
+    )
+  }
+
+  fn make_directory(&self) -> Result<bool, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Path",
+      "MakeDirectory",
+    )
+  }
+
+  fn directory_mode(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Path",
+      "DirectoryMode",
+    )
+  }
+
+  fn result(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Path",
+      "Result",
+    )
+  }
+}
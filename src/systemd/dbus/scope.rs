@@ -0,0 +1,93 @@
+// This code was autogenerated with `dbus-codegen-rust -m None -g -f org.freedesktop.systemd1.Scope`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::blocking;
+
+pub trait OrgFreedesktopSystemd1Scope {
+  fn controller(&self) -> Result<String, dbus::Error>;
+  fn timeout_stop_usec(&self) -> Result<u64, dbus::Error>;
+  fn runtime_max_usec(&self) -> Result<u64, dbus::Error>;
+  fn result(&self) -> Result<String, dbus::Error>;
+  fn slice(&self) -> Result<String, dbus::Error>;
+  fn control_group(&self) -> Result<String, dbus::Error>;
+  fn memory_current(&self) -> Result<u64, dbus::Error>;
+  fn cpuusage_nsec(&self) -> Result<u64, dbus::Error>;
+  fn tasks_current(&self) -> Result<u64, dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> OrgFreedesktopSystemd1Scope
+  for blocking::Proxy<'a, C>
+{
+  fn controller(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Scope",
+      "Controller",
+    )
+  }
+
+  fn timeout_stop_usec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Scope",
+      "TimeoutStopUSec",
+    )
+  }
+
+  fn runtime_max_usec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Scope",
+      "RuntimeMaxUSec",
+    )
+  }
+
+  fn result(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Scope",
+      "Result",
+    )
+  }
+
+  fn slice(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Scope",
+      "Slice",
+    )
+  }
+
+  fn control_group(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Scope",
+      "ControlGroup",
+    )
+  }
+
+  fn memory_current(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Scope",
+      "MemoryCurrent",
+    )
+  }
+
+  fn cpuusage_nsec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Scope",
+      "CPUUsageNSec",
+    )
+  }
+
+  fn tasks_current(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Scope",
+      "TasksCurrent",
+    )
+  }
+}
@@ -0,0 +1,48 @@
+// This code was autogenerated with `dbus-codegen-rust -m None -g -f org.freedesktop.systemd1.Slice`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::blocking;
+
+pub trait OrgFreedesktopSystemd1Slice {
+  fn control_group(&self) -> Result<String, dbus::Error>;
+  fn memory_current(&self) -> Result<u64, dbus::Error>;
+  fn cpuusage_nsec(&self) -> Result<u64, dbus::Error>;
+  fn tasks_current(&self) -> Result<u64, dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> OrgFreedesktopSystemd1Slice
+  for blocking::Proxy<'a, C>
+{
+  fn control_group(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Slice",
+      "ControlGroup",
+    )
+  }
+
+  fn memory_current(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Slice",
+      "MemoryCurrent",
+    )
+  }
+
+  fn cpuusage_nsec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Slice",
+      "CPUUsageNSec",
+    )
+  }
+
+  fn tasks_current(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Slice",
+      "TasksCurrent",
+    )
+  }
+}
@@ -0,0 +1,201 @@
+// This code was autogenerated with `dbus-codegen-rust -m None -g -f org.freedesktop.systemd1.Socket`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::blocking;
+
+pub trait OrgFreedesktopSystemd1Socket {
+  fn bind_ipv6_only(&self) -> Result<String, dbus::Error>;
+  fn backlog(&self) -> Result<u32, dbus::Error>;
+  fn timeout_usec(&self) -> Result<u64, dbus::Error>;
+  fn listen(&self) -> Result<Vec<(String, String)>, dbus::Error>;
+  fn accept(&self) -> Result<bool, dbus::Error>;
+  fn nconnections(&self) -> Result<u32, dbus::Error>;
+  fn naccepted(&self) -> Result<u32, dbus::Error>;
+  fn nrefused(&self) -> Result<u32, dbus::Error>;
+  fn max_connections(&self) -> Result<u32, dbus::Error>;
+  fn socket_user(&self) -> Result<String, dbus::Error>;
+  fn socket_group(&self) -> Result<String, dbus::Error>;
+  fn socket_mode(&self) -> Result<u32, dbus::Error>;
+  fn directory_mode(&self) -> Result<u32, dbus::Error>;
+  fn file_descriptor_name(&self) -> Result<String, dbus::Error>;
+  fn control_pid(&self) -> Result<u32, dbus::Error>;
+  fn result(&self) -> Result<String, dbus::Error>;
+  fn slice(&self) -> Result<String, dbus::Error>;
+  fn control_group(&self) -> Result<String, dbus::Error>;
+  fn memory_current(&self) -> Result<u64, dbus::Error>;
+  fn cpuusage_nsec(&self) -> Result<u64, dbus::Error>;
+  fn tasks_current(&self) -> Result<u64, dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> OrgFreedesktopSystemd1Socket
+  for blocking::Proxy<'a, C>
+{
+  fn bind_ipv6_only(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "BindIPv6Only",
+    )
+  }
+
+  fn backlog(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "Backlog",
+    )
+  }
+
+  fn timeout_usec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "TimeoutUSec",
+    )
+  }
+
+  fn listen(&self) -> Result<Vec<(String, String)>, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "Listen",
+    )
+  }
+
+  fn accept(&self) -> Result<bool, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "Accept",
+    )
+  }
+
+  fn nconnections(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "NConnections",
+    )
+  }
+
+  fn naccepted(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "NAccepted",
+    )
+  }
+
+  fn nrefused(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "NRefused",
+    )
+  }
+
+  fn max_connections(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "MaxConnections",
+    )
+  }
+
+  fn socket_user(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "SocketUser",
+    )
+  }
+
+  fn socket_group(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "SocketGroup",
+    )
+  }
+
+  fn socket_mode(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "SocketMode",
+    )
+  }
+
+  fn directory_mode(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "DirectoryMode",
+    )
+  }
+
+  fn file_descriptor_name(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "FileDescriptorName",
+    )
+  }
+
+  fn control_pid(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "ControlPID",
+    )
+  }
+
+  fn result(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "Result",
+    )
+  }
+
+  fn slice(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "Slice",
+    )
+  }
+
+  fn control_group(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "ControlGroup",
+    )
+  }
+
+  fn memory_current(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "MemoryCurrent",
+    )
+  }
+
+  fn cpuusage_nsec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "CPUUsageNSec",
+    )
+  }
+
+  fn tasks_current(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Socket",
+      "TasksCurrent",
+    )
+  }
+}
@@ -0,0 +1,111 @@
+// This code was autogenerated with `dbus-codegen-rust -m None -g -f org.freedesktop.systemd1.Swap`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus::blocking;
+
+pub trait OrgFreedesktopSystemd1Swap {
+  fn what(&self) -> Result<String, dbus::Error>;
+  fn priority(&self) -> Result<i32, dbus::Error>;
+  fn options(&self) -> Result<String, dbus::Error>;
+  fn timeout_usec(&self) -> Result<u64, dbus::Error>;
+  fn control_pid(&self) -> Result<u32, dbus::Error>;
+  fn result(&self) -> Result<String, dbus::Error>;
+  fn slice(&self) -> Result<String, dbus::Error>;
+  fn control_group(&self) -> Result<String, dbus::Error>;
+  fn memory_current(&self) -> Result<u64, dbus::Error>;
+  fn cpuusage_nsec(&self) -> Result<u64, dbus::Error>;
+  fn tasks_current(&self) -> Result<u64, dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> OrgFreedesktopSystemd1Swap
+  for blocking::Proxy<'a, C>
+{
+  fn what(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "What",
+    )
+  }
+
+  fn priority(&self) -> Result<i32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "Priority",
+    )
+  }
+
+  fn options(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "Options",
+    )
+  }
+
+  fn timeout_usec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "TimeoutUSec",
+    )
+  }
+
+  fn control_pid(&self) -> Result<u32, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "ControlPID",
+    )
+  }
+
+  fn result(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "Result",
+    )
+  }
+
+  fn slice(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "Slice",
+    )
+  }
+
+  fn control_group(&self) -> Result<String, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "ControlGroup",
+    )
+  }
+
+  fn memory_current(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "MemoryCurrent",
+    )
+  }
+
+  fn cpuusage_nsec(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "CPUUsageNSec",
+    )
+  }
+
+  fn tasks_current(&self) -> Result<u64, dbus::Error> {
+    <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+      &self,
+      "org.freedesktop.systemd1.Swap",
+      "TasksCurrent",
+    )
+  }
+}
@@ -0,0 +1,246 @@
+//! Walking unit dependencies, like `systemctl list-dependencies` and `systemd-analyze dot` do
+
+use std::{
+  collections::{HashSet, VecDeque},
+  ops::Deref,
+};
+
+use dbus::arg::prop_cast;
+
+use crate::dbus_interface::DBusInterface;
+
+use super::{
+  dbus::manager::OrgFreedesktopSystemd1Manager,
+  dto::{DependencyEdge, DependencyGraphDto, DependencyNode},
+  functions,
+};
+
+/// Graphs are cut off after this many units, `--all` on a whole system reaches thousands
+pub const MAX_NODES: usize = 1000;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DependencyDirection {
+  /// Units the unit depends on
+  #[default]
+  Forward,
+
+  /// Units depending on the unit
+  Reverse,
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DependencyType {
+  Requires,
+  Requisite,
+  Wants,
+  BindsTo,
+  PartOf,
+  Upholds,
+  Conflicts,
+  Before,
+  After,
+  Triggers,
+  OnSuccess,
+  OnFailure,
+  PropagatesReloadTo,
+  PropagatesStopTo,
+}
+
+impl DependencyType {
+  pub const ALL: [DependencyType; 14] = [
+    DependencyType::Requires,
+    DependencyType::Requisite,
+    DependencyType::Wants,
+    DependencyType::BindsTo,
+    DependencyType::PartOf,
+    DependencyType::Upholds,
+    DependencyType::Conflicts,
+    DependencyType::Before,
+    DependencyType::After,
+    DependencyType::Triggers,
+    DependencyType::OnSuccess,
+    DependencyType::OnFailure,
+    DependencyType::PropagatesReloadTo,
+    DependencyType::PropagatesStopTo,
+  ];
+
+  /// Types `systemctl list-dependencies` follows by default
+  pub const DEFAULT: [DependencyType; 4] = [
+    DependencyType::Requires,
+    DependencyType::Requisite,
+    DependencyType::Wants,
+    DependencyType::BindsTo,
+  ];
+
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      DependencyType::Requires => "requires",
+      DependencyType::Requisite => "requisite",
+      DependencyType::Wants => "wants",
+      DependencyType::BindsTo => "binds-to",
+      DependencyType::PartOf => "part-of",
+      DependencyType::Upholds => "upholds",
+      DependencyType::Conflicts => "conflicts",
+      DependencyType::Before => "before",
+      DependencyType::After => "after",
+      DependencyType::Triggers => "triggers",
+      DependencyType::OnSuccess => "on-success",
+      DependencyType::OnFailure => "on-failure",
+      DependencyType::PropagatesReloadTo => "propagates-reload-to",
+      DependencyType::PropagatesStopTo => "propagates-stop-to",
+    }
+  }
+
+  pub fn parse(name: &str) -> Option<DependencyType> {
+    Self::ALL.into_iter().find(|kind| kind.as_str() == name)
+  }
+
+  /// Unit property listing units this one depends on, and the one listing units depending on
+  /// this one
+  fn properties(&self) -> (&'static str, &'static str) {
+    match self {
+      DependencyType::Requires => ("Requires", "RequiredBy"),
+      DependencyType::Requisite => ("Requisite", "RequisiteOf"),
+      DependencyType::Wants => ("Wants", "WantedBy"),
+      DependencyType::BindsTo => ("BindsTo", "BoundBy"),
+      DependencyType::PartOf => ("PartOf", "ConsistsOf"),
+      DependencyType::Upholds => ("Upholds", "UpheldBy"),
+      DependencyType::Conflicts => ("Conflicts", "ConflictedBy"),
+      DependencyType::Before => ("Before", "After"),
+      DependencyType::After => ("After", "Before"),
+      DependencyType::Triggers => ("Triggers", "TriggeredBy"),
+      DependencyType::OnSuccess => ("OnSuccess", "OnSuccessOf"),
+      DependencyType::OnFailure => ("OnFailure", "OnFailureOf"),
+      DependencyType::PropagatesReloadTo => ("PropagatesReloadTo", "ReloadPropagatedFrom"),
+      DependencyType::PropagatesStopTo => ("PropagatesStopTo", "StopPropagatedFrom"),
+    }
+  }
+
+  /// Edge style in DOT output, colors follow `systemd-analyze dot`
+  fn dot_color(&self) -> &'static str {
+    match self {
+      DependencyType::Requires | DependencyType::BindsTo => "black",
+      DependencyType::Requisite => "darkblue",
+      DependencyType::Wants | DependencyType::PartOf | DependencyType::Upholds => "grey66",
+      DependencyType::Conflicts => "red",
+      DependencyType::Before | DependencyType::After => "green",
+      _ => "darkgrey",
+    }
+  }
+}
+
+/// Walks dependencies breadth first, up to `depth` steps away from the root unit. Every unit
+/// is listed once, at the depth it was first reached at.
+pub fn dependency_graph(
+  dbus: &DBusInterface,
+  root: &str,
+  direction: DependencyDirection,
+  types: &[DependencyType],
+  depth: usize,
+) -> Result<DependencyGraphDto, dbus::Error> {
+  let mut nodes: Vec<DependencyNode> = Vec::new();
+  let mut edges = Vec::new();
+  let mut queue = VecDeque::from([(root.to_owned(), 0)]);
+  let mut seen = HashSet::from([root.to_owned()]);
+  let mut truncated = false;
+
+  while let Some((name, node_depth)) = queue.pop_front() {
+    let unit_path = dbus.systemd_manager().load_unit(&name)?;
+    let properties = functions::unit_properties(dbus, unit_path.deref())?;
+    let text = |property: &str| prop_cast::<String>(&properties, property).cloned();
+
+    // Dependencies are listed by unit id, only the root could be an alias
+    let id = text("Id").unwrap_or(name);
+    seen.insert(id.clone());
+    nodes.push(DependencyNode {
+      name: id.clone(),
+      description: text("Description").unwrap_or_default(),
+      load_state: text("LoadState").unwrap_or_default(),
+      active_state: text("ActiveState").unwrap_or_default(),
+      sub_state: text("SubState").unwrap_or_default(),
+      depth: node_depth,
+    });
+
+    if node_depth >= depth {
+      continue;
+    }
+
+    for kind in types {
+      let (forward, reverse) = kind.properties();
+      let property = match direction {
+        DependencyDirection::Forward => forward,
+        DependencyDirection::Reverse => reverse,
+      };
+      let units = prop_cast::<Vec<String>>(&properties, property)
+        .cloned()
+        .unwrap_or_default();
+
+      for unit in units {
+        if !seen.contains(&unit) {
+          if seen.len() >= MAX_NODES {
+            truncated = true;
+            continue;
+          }
+          seen.insert(unit.clone());
+          queue.push_back((unit.clone(), node_depth + 1));
+        }
+
+        // Edges always point from the dependent unit to its dependency
+        let (from, to) = match direction {
+          DependencyDirection::Forward => (id.clone(), unit),
+          DependencyDirection::Reverse => (unit, id.clone()),
+        };
+        edges.push(DependencyEdge {
+          from,
+          to,
+          dependency_type: *kind,
+        });
+      }
+    }
+  }
+
+  Ok(DependencyGraphDto {
+    root: nodes[0].name.clone(),
+    direction,
+    nodes,
+    edges,
+    truncated,
+  })
+}
+
+/// Graphviz source of the graph, render it with i.e. `dot -Tsvg`
+pub fn to_dot(graph: &DependencyGraphDto) -> String {
+  let mut dot = format!("digraph \"{}\" {{\n", escape(&graph.root));
+
+  for node in &graph.nodes {
+    let style = match node.active_state.as_str() {
+      "failed" => ", color=red",
+      "active" => ", color=darkgreen",
+      _ => "",
+    };
+    dot.push_str(&format!(
+      "  \"{}\" [shape=box{}];\n",
+      escape(&node.name),
+      style
+    ));
+  }
+
+  for edge in &graph.edges {
+    dot.push_str(&format!(
+      "  \"{}\" -> \"{}\" [label=\"{}\", color={}];\n",
+      escape(&edge.from),
+      escape(&edge.to),
+      edge.dependency_type.as_str(),
+      edge.dependency_type.dot_color()
+    ));
+  }
+
+  dot.push_str("}\n");
+  dot
+}
+
+fn escape(text: &str) -> String {
+  text.replace('\\', "\\\\").replace('"', "\\\"")
+}
@@ -1,10 +1,26 @@
-use std::ops::Deref;
+use std::{
+  collections::BTreeMap,
+  ops::Deref,
+  time::{SystemTime, UNIX_EPOCH},
+};
 
 use sysinfo::{Pid, PidExt, ProcessExt, System, SystemExt};
 
 use serde::Serialize;
 
-use super::dbus::{service::OrgFreedesktopSystemd1Service, unit::OrgFreedesktopSystemd1Unit};
+use super::{
+  dbus::{
+    automount::OrgFreedesktopSystemd1Automount, device::OrgFreedesktopSystemd1Device,
+    job::OrgFreedesktopSystemd1Job, manager::OrgFreedesktopSystemd1Manager,
+    mount::OrgFreedesktopSystemd1Mount, path::OrgFreedesktopSystemd1Path,
+    scope::OrgFreedesktopSystemd1Scope, service::OrgFreedesktopSystemd1Service,
+    slice::OrgFreedesktopSystemd1Slice, socket::OrgFreedesktopSystemd1Socket,
+    swap::OrgFreedesktopSystemd1Swap, timer::OrgFreedesktopSystemd1Timer,
+    unit::OrgFreedesktopSystemd1Unit,
+  },
+  dependencies::{DependencyDirection, DependencyType},
+  unit_file::{Diagnostic, UnitFile, Validation},
+};
 
 type ExecDataTuple = (String, Vec<String>, bool, u64, u64, u64, u64, u32, i32, i32);
 
@@ -37,14 +53,14 @@ pub struct ExecData {
   /// Defined in linux kernel as
   ///
   /// ```c
-  /// #define __SI_CHLD	(4 << 16)
+  /// #define __SI_CHLD       (4 << 16)
   /// // ...
-  /// #define CLD_EXITED	(__SI_CHLD|1)	/* child has exited */
-  /// #define CLD_KILLED	(__SI_CHLD|2)	/* child was killed */
-  /// #define CLD_DUMPED	(__SI_CHLD|3)	/* child terminated abnormally */
-  /// #define CLD_TRAPPED	(__SI_CHLD|4)	/* traced child has trapped */
-  /// #define CLD_STOPPED	(__SI_CHLD|5)	/* child has stopped */
-  /// #define CLD_CONTINUED	(__SI_CHLD|6)	/* stopped child has continued */
+  /// #define CLD_EXITED      (__SI_CHLD|1)   /* child has exited */
+  /// #define CLD_KILLED      (__SI_CHLD|2)   /* child was killed */
+  /// #define CLD_DUMPED      (__SI_CHLD|3)   /* child terminated abnormally */
+  /// #define CLD_TRAPPED     (__SI_CHLD|4)   /* traced child has trapped */
+  /// #define CLD_STOPPED     (__SI_CHLD|5)   /* child has stopped */
+  /// #define CLD_CONTINUED   (__SI_CHLD|6)   /* stopped child has continued */
   ///
   /// But from what I saw systemd just returns `1, 2, 3, 4, 5, 6`
   /// ```
@@ -94,17 +110,21 @@ pub struct UnitDto {
   pub sub_state: String,
   pub load_error: (String, String),
   pub fragment_path: String,
+
+  /// Drop-in files, in the order they're applied
+  pub drop_in_paths: Vec<String>,
   pub unit_file_state: String,
   pub unit_file_preset: String,
   pub state_change_timestamp: u64,
 
+  /// Properties specific to the unit type
   #[serde(flatten)]
-  service: Option<ServiceDto>,
+  type_properties: Option<UnitTypeDto>,
 }
 
 impl UnitDto {
-  pub fn add_service(&mut self, service: ServiceDto) {
-    self.service = Some(service);
+  pub fn add_type_properties(&mut self, properties: UnitTypeDto) {
+    self.type_properties = Some(properties);
   }
 
   pub fn create_from_proxy(
@@ -121,10 +141,11 @@ impl UnitDto {
       sub_state: proxy.sub_state()?,
       load_error: proxy.load_error()?,
       fragment_path: proxy.fragment_path()?,
+      drop_in_paths: proxy.drop_in_paths()?,
       unit_file_state: proxy.unit_file_state()?,
       unit_file_preset: proxy.unit_file_preset()?,
       state_change_timestamp: proxy.state_change_timestamp()?,
-      service: None,
+      type_properties: None,
     })
   }
 }
@@ -179,6 +200,493 @@ impl ServiceDto {
   }
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CalendarTimer {
+  /// Setting the timer comes from, i.e. "OnCalendar"
+  pub base: String,
+
+  /// Calendar expression, i.e. "Mon *-*-* 00:00:00"
+  pub expression: String,
+
+  /// Next elapse of this expression, in microseconds since the epoch
+  pub next_elapse_usec: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonotonicTimer {
+  /// Setting the timer comes from, i.e. "OnBootUSec" or "OnUnitActiveUSec"
+  pub base: String,
+
+  /// Time after the base event, in microseconds
+  pub value_usec: u64,
+
+  /// Next elapse of this timer, in microseconds since the boot
+  pub next_elapse_usec: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimerDto {
+  /// The unit the timer activates
+  pub unit: String,
+  pub timers_calendar: Vec<CalendarTimer>,
+  pub timers_monotonic: Vec<MonotonicTimer>,
+
+  /// Next elapse of calendar timers, in microseconds since the epoch, 0 if there's none
+  pub next_elapse_usec_realtime: u64,
+
+  /// Next elapse of monotonic timers, in microseconds since the boot, 0 if there's none
+  pub next_elapse_usec_monotonic: u64,
+
+  /// The sooner of both next elapses, in microseconds since the epoch. Same thing
+  /// `systemctl list-timers` shows as NEXT.
+  pub next_elapse: Option<u64>,
+
+  /// When the timer last triggered, in microseconds since the epoch, 0 if it never did
+  pub last_trigger_usec: u64,
+  pub last_trigger_usec_monotonic: u64,
+  pub result: String,
+  pub accuracy_usec: u64,
+  pub randomized_delay_usec: u64,
+
+  /// Whether a missed elapse (i.e. while the machine was off) is caught up on start
+  pub persistent: bool,
+  pub wake_system: bool,
+  pub remain_after_elapse: bool,
+}
+
+impl TimerDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Timer,
+  ) -> Result<TimerDto, dbus::Error> {
+    let next_elapse_usec_realtime = proxy.next_elapse_usec_realtime()?;
+    let next_elapse_usec_monotonic = proxy.next_elapse_usec_monotonic()?;
+
+    Ok(TimerDto {
+      unit: proxy.unit()?,
+      timers_calendar: proxy
+        .timers_calendar()?
+        .into_iter()
+        .map(|(base, expression, next_elapse_usec)| CalendarTimer {
+          base,
+          expression,
+          next_elapse_usec,
+        })
+        .collect(),
+      timers_monotonic: proxy
+        .timers_monotonic()?
+        .into_iter()
+        .map(|(base, value_usec, next_elapse_usec)| MonotonicTimer {
+          base,
+          value_usec,
+          next_elapse_usec,
+        })
+        .collect(),
+      next_elapse_usec_realtime,
+      next_elapse_usec_monotonic,
+      next_elapse: next_elapse(next_elapse_usec_realtime, next_elapse_usec_monotonic),
+      last_trigger_usec: proxy.last_trigger_usec()?,
+      last_trigger_usec_monotonic: proxy.last_trigger_usec_monotonic()?,
+      result: proxy.result()?,
+      accuracy_usec: proxy.accuracy_usec()?,
+      randomized_delay_usec: proxy.randomized_delay_usec()?,
+      persistent: proxy.persistent()?,
+      wake_system: proxy.wake_system()?,
+      remain_after_elapse: proxy.remain_after_elapse()?,
+    })
+  }
+}
+
+/// Converts the monotonic next elapse to wall clock time and picks the sooner one, the same
+/// way systemctl does
+fn next_elapse(realtime: u64, monotonic: u64) -> Option<u64> {
+  let converted = match monotonic {
+    0 => None,
+    _ => boot_clock()
+      .map(|(now_realtime, now_boot)| (now_realtime + monotonic).saturating_sub(now_boot)),
+  };
+
+  match (realtime, converted) {
+    (0, converted) => converted,
+    (realtime, Some(converted)) => Some(realtime.min(converted)),
+    (realtime, None) => Some(realtime),
+  }
+}
+
+/// Current wall clock time and time since boot, both in microseconds
+fn boot_clock() -> Option<(u64, u64)> {
+  let uptime = std::fs::read_to_string("/proc/uptime").ok()?;
+  let uptime: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+  let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+
+  Some((now.as_micros() as u64, (uptime * 1_000_000.0) as u64))
+}
+
+/// Timer unit, as `systemctl list-timers` lists it
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimerListEntry {
+  pub name: String,
+  pub description: String,
+  pub active_state: String,
+  pub sub_state: String,
+
+  #[serde(flatten)]
+  pub timer: TimerDto,
+}
+
+/// Properties of a unit type, flattened into `UnitDto`
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum UnitTypeDto {
+  Service(ServiceDto),
+  Timer(TimerDto),
+  Socket(SocketDto),
+  Mount(MountDto),
+  Automount(AutomountDto),
+  Path(PathDto),
+  Swap(SwapDto),
+  Scope(ScopeDto),
+  Slice(SliceDto),
+  Device(DeviceDto),
+  Target(TargetDto),
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketListen {
+  /// i.e. "Stream", "Datagram" or "FIFO"
+  #[serde(rename = "type")]
+  pub type_: String,
+
+  /// i.e. "0.0.0.0:22" or "/run/dbus/system_bus_socket"
+  pub address: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SocketDto {
+  pub listen: Vec<SocketListen>,
+
+  /// Whether a service instance is spawned for each connection
+  pub accept: bool,
+
+  /// Connections currently open, only counted when accept is set
+  pub n_connections: u32,
+
+  /// Connections accepted since the socket was started
+  pub n_accepted: u32,
+  pub n_refused: u32,
+  pub max_connections: u32,
+  pub backlog: u32,
+  pub bind_ipv6_only: String,
+  pub socket_user: String,
+  pub socket_group: String,
+  pub socket_mode: u32,
+  pub directory_mode: u32,
+  pub file_descriptor_name: String,
+  pub timeout_usec: u64,
+  pub control_pid: u32,
+  pub result: String,
+  pub slice: String,
+  pub control_group: String,
+  pub memory_current: u64,
+  pub cpu_usage_nsec: u64,
+  pub tasks_current: u64,
+}
+
+impl SocketDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Socket,
+  ) -> Result<SocketDto, dbus::Error> {
+    Ok(SocketDto {
+      listen: proxy
+        .listen()?
+        .into_iter()
+        .map(|(type_, address)| SocketListen { type_, address })
+        .collect(),
+      accept: proxy.accept()?,
+      n_connections: proxy.nconnections()?,
+      n_accepted: proxy.naccepted()?,
+      n_refused: proxy.nrefused()?,
+      max_connections: proxy.max_connections()?,
+      backlog: proxy.backlog()?,
+      bind_ipv6_only: proxy.bind_ipv6_only()?,
+      socket_user: proxy.socket_user()?,
+      socket_group: proxy.socket_group()?,
+      socket_mode: proxy.socket_mode()?,
+      directory_mode: proxy.directory_mode()?,
+      file_descriptor_name: proxy.file_descriptor_name()?,
+      timeout_usec: proxy.timeout_usec()?,
+      control_pid: proxy.control_pid()?,
+      result: proxy.result()?,
+      slice: proxy.slice()?,
+      control_group: proxy.control_group()?,
+      memory_current: proxy.memory_current()?,
+      cpu_usage_nsec: proxy.cpuusage_nsec()?,
+      tasks_current: proxy.tasks_current()?,
+    })
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MountDto {
+  /// Mounted device or resource, i.e. "/dev/sda1"
+  pub what: String,
+
+  /// Mount point
+  #[serde(rename = "where")]
+  pub where_: String,
+
+  /// File system type
+  #[serde(rename = "type")]
+  pub type_: String,
+  pub options: String,
+  pub sloppy_options: bool,
+  pub lazy_unmount: bool,
+  pub force_unmount: bool,
+  pub read_write_only: bool,
+  pub directory_mode: u32,
+  pub timeout_usec: u64,
+  pub control_pid: u32,
+  pub result: String,
+  pub slice: String,
+  pub control_group: String,
+  pub memory_current: u64,
+  pub cpu_usage_nsec: u64,
+  pub tasks_current: u64,
+}
+
+impl MountDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Mount,
+  ) -> Result<MountDto, dbus::Error> {
+    Ok(MountDto {
+      what: proxy.what()?,
+      where_: proxy.where_()?,
+      type_: proxy.type_()?,
+      options: proxy.options()?,
+      sloppy_options: proxy.sloppy_options()?,
+      lazy_unmount: proxy.lazy_unmount()?,
+      force_unmount: proxy.force_unmount()?,
+      read_write_only: proxy.read_write_only()?,
+      directory_mode: proxy.directory_mode()?,
+      timeout_usec: proxy.timeout_usec()?,
+      control_pid: proxy.control_pid()?,
+      result: proxy.result()?,
+      slice: proxy.slice()?,
+      control_group: proxy.control_group()?,
+      memory_current: proxy.memory_current()?,
+      cpu_usage_nsec: proxy.cpuusage_nsec()?,
+      tasks_current: proxy.tasks_current()?,
+    })
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomountDto {
+  /// Mount point
+  #[serde(rename = "where")]
+  pub where_: String,
+  pub extra_options: String,
+  pub directory_mode: u32,
+
+  /// Idle time after which the mount point is unmounted, 0 if never
+  pub timeout_idle_usec: u64,
+  pub result: String,
+}
+
+impl AutomountDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Automount,
+  ) -> Result<AutomountDto, dbus::Error> {
+    Ok(AutomountDto {
+      where_: proxy.where_()?,
+      extra_options: proxy.extra_options()?,
+      directory_mode: proxy.directory_mode()?,
+      timeout_idle_usec: proxy.timeout_idle_usec()?,
+      result: proxy.result()?,
+    })
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathCondition {
+  /// Setting the path comes from, i.e. "PathChanged"
+  #[serde(rename = "type")]
+  pub type_: String,
+  pub path: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathDto {
+  /// The unit the path activates
+  pub unit: String,
+  pub paths: Vec<PathCondition>,
+  pub make_directory: bool,
+  pub directory_mode: u32,
+  pub result: String,
+}
+
+impl PathDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Path,
+  ) -> Result<PathDto, dbus::Error> {
+    Ok(PathDto {
+      unit: proxy.unit()?,
+      paths: proxy
+        .paths()?
+        .into_iter()
+        .map(|(type_, path)| PathCondition { type_, path })
+        .collect(),
+      make_directory: proxy.make_directory()?,
+      directory_mode: proxy.directory_mode()?,
+      result: proxy.result()?,
+    })
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapDto {
+  /// Swap device or file
+  pub what: String,
+  pub priority: i32,
+  pub options: String,
+  pub timeout_usec: u64,
+  pub control_pid: u32,
+  pub result: String,
+  pub slice: String,
+  pub control_group: String,
+  pub memory_current: u64,
+  pub cpu_usage_nsec: u64,
+  pub tasks_current: u64,
+}
+
+impl SwapDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Swap,
+  ) -> Result<SwapDto, dbus::Error> {
+    Ok(SwapDto {
+      what: proxy.what()?,
+      priority: proxy.priority()?,
+      options: proxy.options()?,
+      timeout_usec: proxy.timeout_usec()?,
+      control_pid: proxy.control_pid()?,
+      result: proxy.result()?,
+      slice: proxy.slice()?,
+      control_group: proxy.control_group()?,
+      memory_current: proxy.memory_current()?,
+      cpu_usage_nsec: proxy.cpuusage_nsec()?,
+      tasks_current: proxy.tasks_current()?,
+    })
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScopeDto {
+  /// D-Bus name of whoever manages the scope, empty if nobody does
+  pub controller: String,
+  pub timeout_stop_usec: u64,
+  pub runtime_max_usec: u64,
+  pub result: String,
+  pub slice: String,
+  pub control_group: String,
+  pub memory_current: u64,
+  pub cpu_usage_nsec: u64,
+  pub tasks_current: u64,
+}
+
+impl ScopeDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Scope,
+  ) -> Result<ScopeDto, dbus::Error> {
+    Ok(ScopeDto {
+      controller: proxy.controller()?,
+      timeout_stop_usec: proxy.timeout_stop_usec()?,
+      runtime_max_usec: proxy.runtime_max_usec()?,
+      result: proxy.result()?,
+      slice: proxy.slice()?,
+      control_group: proxy.control_group()?,
+      memory_current: proxy.memory_current()?,
+      cpu_usage_nsec: proxy.cpuusage_nsec()?,
+      tasks_current: proxy.tasks_current()?,
+    })
+  }
+}
+
+/// Slices only group other units, usage is the sum of everything inside
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SliceDto {
+  pub control_group: String,
+  pub memory_current: u64,
+  pub cpu_usage_nsec: u64,
+  pub tasks_current: u64,
+}
+
+impl SliceDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Slice,
+  ) -> Result<SliceDto, dbus::Error> {
+    Ok(SliceDto {
+      control_group: proxy.control_group()?,
+      memory_current: proxy.memory_current()?,
+      cpu_usage_nsec: proxy.cpuusage_nsec()?,
+      tasks_current: proxy.tasks_current()?,
+    })
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceDto {
+  /// i.e. "/sys/devices/pci0000:00/0000:00:1f.2/ata1/host0/target0:0:0/0:0:0:0/block/sda"
+  pub sys_fs_path: String,
+}
+
+impl DeviceDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Device,
+  ) -> Result<DeviceDto, dbus::Error> {
+    Ok(DeviceDto {
+      sys_fs_path: proxy.sys_fspath()?,
+    })
+  }
+}
+
+/// Targets have no properties of their own on D-Bus, what they pull in is what matters
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TargetDto {
+  pub wants: Vec<String>,
+  pub requires: Vec<String>,
+  pub wanted_by: Vec<String>,
+  pub required_by: Vec<String>,
+  pub conflicts: Vec<String>,
+}
+
+impl TargetDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Unit,
+  ) -> Result<TargetDto, dbus::Error> {
+    Ok(TargetDto {
+      wants: proxy.wants()?,
+      requires: proxy.requires()?,
+      wanted_by: proxy.wanted_by()?,
+      required_by: proxy.required_by()?,
+      conflicts: proxy.conflicts()?,
+    })
+  }
+}
+
 pub type UnitListEntryTuple = (
   String,
   String,
@@ -242,3 +750,717 @@ impl From<UnitListEntryTuple> for UnitListEntry {
     }
   }
 }
+
+/// Tells systemd how to deal with already queued jobs when enqueuing a new one.
+/// See `--job-mode` in `man systemctl` for the meaning of each mode.
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum JobMode {
+  #[default]
+  Replace,
+  Fail,
+  Isolate,
+  IgnoreDependencies,
+  IgnoreRequirements,
+  ReplaceIrreversibly,
+  Flush,
+  Triggering,
+  RestartDependencies,
+}
+
+impl JobMode {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      JobMode::Replace => "replace",
+      JobMode::Fail => "fail",
+      JobMode::Isolate => "isolate",
+      JobMode::IgnoreDependencies => "ignore-dependencies",
+      JobMode::IgnoreRequirements => "ignore-requirements",
+      JobMode::ReplaceIrreversibly => "replace-irreversibly",
+      JobMode::Flush => "flush",
+      JobMode::Triggering => "triggering",
+      JobMode::RestartDependencies => "restart-dependencies",
+    }
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobDto {
+  /// The numeric job id, `None` if systemd returned a path without one
+  pub id: Option<u32>,
+
+  /// The job object path
+  pub object_path: String,
+
+  /// Name of the unit the job was enqueued for
+  pub unit: String,
+
+  /// The job type as string
+  pub job_type: String,
+
+  /// Final result of the job, only present if request waited for the job to finish.
+  /// One of "done", "canceled", "timeout", "failed", "dependency" or "skipped"
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub result: Option<String>,
+}
+
+impl JobDto {
+  pub fn new(path: dbus::Path<'static>, unit: &str, job_type: &str) -> Self {
+    let object_path = path.deref().to_owned();
+
+    Self {
+      id: job_id_from_path(&object_path),
+      object_path,
+      unit: unit.to_owned(),
+      job_type: job_type.to_owned(),
+      result: None,
+    }
+  }
+}
+
+/// Job paths look like `/org/freedesktop/systemd1/job/1234`, where last segment is the job id
+fn job_id_from_path(path: &str) -> Option<u32> {
+  path
+    .strip_prefix("/org/freedesktop/systemd1/job/")?
+    .parse()
+    .ok()
+}
+
+pub type JobListEntryTuple = (
+  u32,
+  String,
+  String,
+  String,
+  dbus::Path<'static>,
+  dbus::Path<'static>,
+);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JobListEntry {
+  /// The numeric job id
+  pub id: u32,
+
+  /// The primary unit name for this job
+  pub unit: String,
+
+  /// The job type as string
+  pub job_type: String,
+
+  /// The job state as string, "waiting" or "running"
+  pub state: String,
+
+  /// The job object path
+  pub object_path: String,
+
+  /// The unit object path
+  pub unit_object_path: String,
+}
+
+impl From<JobListEntryTuple> for JobListEntry {
+  fn from(value: JobListEntryTuple) -> Self {
+    Self {
+      id: value.0,
+      unit: value.1,
+      job_type: value.2,
+      state: value.3,
+      object_path: value.4.deref().to_owned(),
+      unit_object_path: value.5.deref().to_owned(),
+    }
+  }
+}
+
+impl JobListEntry {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Job,
+    object_path: &str,
+  ) -> Result<JobListEntry, dbus::Error> {
+    let (unit, unit_object_path) = proxy.unit()?;
+
+    Ok(JobListEntry {
+      id: proxy.id()?,
+      unit,
+      job_type: proxy.job_type()?,
+      state: proxy.state()?,
+      object_path: object_path.to_owned(),
+      unit_object_path: unit_object_path.deref().to_owned(),
+    })
+  }
+}
+
+/// Body of requests changing unit files, see `man systemctl` for the meaning of the flags
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitFilesRequest {
+  /// Unit names, or absolute paths of unit files to link
+  pub files: Vec<String>,
+
+  /// Change files only until next reboot, in /run
+  #[serde(default)]
+  pub runtime: bool,
+
+  /// Replace symlinks which are in the way. Not used by disable, unmask and revert.
+  #[serde(default)]
+  pub force: bool,
+
+  /// Only used by preset
+  #[serde(default)]
+  pub mode: PresetMode,
+
+  /// Skip reloading systemd after files changed, same as `systemctl --no-reload`
+  #[serde(default)]
+  pub no_reload: bool,
+}
+
+#[derive(Deserialize, Clone, Copy, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum PresetMode {
+  #[default]
+  Full,
+  EnableOnly,
+  DisableOnly,
+}
+
+impl PresetMode {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      PresetMode::Full => "full",
+      PresetMode::EnableOnly => "enable-only",
+      PresetMode::DisableOnly => "disable-only",
+    }
+  }
+}
+
+type UnitFileChangeTuple = (String, String, String);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitFileChange {
+  /// "symlink" or "unlink"
+  pub change_type: String,
+
+  /// Path of the symlink which was created or removed
+  pub file: String,
+
+  /// Where the created symlink points to, empty for removed ones
+  pub destination: String,
+}
+
+impl From<UnitFileChangeTuple> for UnitFileChange {
+  fn from(value: UnitFileChangeTuple) -> Self {
+    Self {
+      change_type: value.0,
+      file: value.1,
+      destination: value.2,
+    }
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitFileChangesDto {
+  /// Whether unit files have an [Install] section. Only known for enable, reenable and preset.
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub carries_install_info: Option<bool>,
+
+  pub changes: Vec<UnitFileChange>,
+}
+
+impl UnitFileChangesDto {
+  pub fn new(carries_install_info: Option<bool>, changes: Vec<UnitFileChangeTuple>) -> Self {
+    Self {
+      carries_install_info,
+      changes: changes.into_iter().map(UnitFileChange::from).collect(),
+    }
+  }
+}
+
+type UnitFileListEntryTuple = (String, String);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitFileListEntry {
+  /// Unit name, i.e. "sshd.service"
+  pub name: String,
+
+  /// Path of the unit file
+  pub path: String,
+
+  /// Enablement state, i.e. "enabled", "disabled", "static" or "masked"
+  pub state: String,
+}
+
+impl From<UnitFileListEntryTuple> for UnitFileListEntry {
+  fn from(value: UnitFileListEntryTuple) -> Self {
+    Self {
+      name: value.0.rsplit('/').next().unwrap_or_default().to_owned(),
+      path: value.0,
+      state: value.1,
+    }
+  }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitFileStateDto {
+  pub name: String,
+
+  /// Enablement state, same values as in unit file list
+  pub state: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitFileContentDto {
+  pub path: String,
+  pub content: String,
+}
+
+/// One setting after applying all files, values are in the order they were assigned.
+/// Single-value settings use the last value.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveSettingDto {
+  pub key: String,
+  pub values: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EffectiveSectionDto {
+  pub name: String,
+  pub settings: Vec<EffectiveSettingDto>,
+}
+
+/// Unit file together with its drop-ins, like `systemctl cat` shows
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitConfigDto {
+  pub name: String,
+
+  /// Missing for units without a unit file, i.e. transient ones
+  pub fragment: Option<UnitFileContentDto>,
+  pub drop_ins: Vec<UnitFileContentDto>,
+
+  /// Settings after merging fragment and drop-ins
+  pub effective: Vec<EffectiveSectionDto>,
+}
+
+/// Body of a request writing a drop-in
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DropInRequest {
+  pub content: String,
+
+  /// Write to /run, so the drop-in is gone after reboot
+  #[serde(default)]
+  pub runtime: bool,
+}
+
+/// Body of a request creating a unit file
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewUnitFileRequest {
+  /// Unit name with type suffix, i.e. "backup.service"
+  pub name: String,
+  pub content: String,
+
+  /// Write to /run, so the unit file is gone after reboot
+  #[serde(default)]
+  pub runtime: bool,
+
+  /// Replace the unit file if it exists
+  #[serde(default)]
+  pub overwrite: bool,
+}
+
+/// Body of a request validating unit file content
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateUnitFileRequest {
+  pub content: String,
+
+  /// Name of the unit the content is for, its suffix selects the keys to check. Without it,
+  /// the unit type is guessed from the sections.
+  pub name: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnitFileValidationDto {
+  /// No errors, the content can be saved. Warnings don't count.
+  pub valid: bool,
+  pub unit_type: Option<String>,
+  pub errors: Vec<Diagnostic>,
+  pub warnings: Vec<Diagnostic>,
+
+  /// Everything that could be parsed
+  pub unit_file: UnitFile,
+}
+
+impl From<Validation> for UnitFileValidationDto {
+  fn from(validation: Validation) -> Self {
+    UnitFileValidationDto {
+      valid: validation.errors.is_empty(),
+      unit_type: validation.unit_type.map(str::to_owned),
+      errors: validation.errors,
+      warnings: validation.warnings,
+      unit_file: validation.file,
+    }
+  }
+}
+
+/// Type of transient unit to run a command in, see `--scope` in `man systemd-run`
+#[derive(Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TransientKind {
+  /// systemd starts the command and collects its output into the journal
+  #[default]
+  Service,
+
+  /// dragond starts the command and moves it into the scope, output is discarded
+  Scope,
+}
+
+/// Body of a request running a command in a transient unit, like `systemd-run` does
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRequest {
+  /// Program and its arguments. Program is looked up in PATH if it isn't an absolute path.
+  pub command: Vec<String>,
+
+  /// Unit name with or without type suffix, generated if not set
+  pub unit: Option<String>,
+  pub description: Option<String>,
+
+  #[serde(default)]
+  pub kind: TransientKind,
+  pub working_directory: Option<String>,
+
+  /// Variables for the command, commands in scopes get only these and PATH
+  #[serde(default)]
+  pub environment: BTreeMap<String, String>,
+  pub user: Option<String>,
+  pub group: Option<String>,
+
+  /// Memory limit in bytes, with optional K, M, G or T suffix (base 1024), or "infinity"
+  pub memory_max: Option<String>,
+
+  /// CPU time limit in percent of one CPU, i.e. "150%"
+  pub cpu_quota: Option<String>,
+
+  /// Calendar expression, i.e. "daily". If set, a transient timer is started which runs the
+  /// service, instead of running it right away.
+  pub on_calendar: Option<String>,
+
+  /// Keep the service active after the command exits, so its state can still be inspected
+  #[serde(default)]
+  pub remain_after_exit: bool,
+
+  #[serde(default)]
+  pub mode: JobMode,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransientUnitDto {
+  /// Unit which was started, the timer if `onCalendar` was set
+  pub unit: String,
+
+  /// Unit running the command, follow its logs with `/journald/unit-logs/{name}/follow`
+  pub command_unit: String,
+  pub job: JobDto,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyNode {
+  pub name: String,
+  pub description: String,
+  pub load_state: String,
+  pub active_state: String,
+  pub sub_state: String,
+
+  /// Steps away from the root unit, root is 0
+  pub depth: usize,
+}
+
+/// `from` has a dependency of `type` on `to`, i.e. "multi-user.target" requires "basic.target"
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyEdge {
+  pub from: String,
+  pub to: String,
+
+  #[serde(rename = "type")]
+  pub dependency_type: DependencyType,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyGraphDto {
+  pub root: String,
+  pub direction: DependencyDirection,
+
+  /// Units in the order they were reached, root first
+  pub nodes: Vec<DependencyNode>,
+  pub edges: Vec<DependencyEdge>,
+
+  /// Whether units were left out because the graph got too big
+  pub truncated: bool,
+}
+
+/// Boot phase durations in microseconds, `None` for phases the system didn't go through or
+/// which didn't finish yet
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BootTimesDto {
+  /// Whether bootup finished, until then only phases before userspace are known
+  pub finished: bool,
+  pub firmware_usec: Option<u64>,
+  pub loader_usec: Option<u64>,
+  pub kernel_usec: u64,
+  pub initrd_usec: Option<u64>,
+  pub userspace_usec: Option<u64>,
+
+  /// Firmware and loader time plus everything since the kernel started
+  pub total_usec: Option<u64>,
+  pub security_usec: Option<u64>,
+  pub generators_usec: Option<u64>,
+  pub units_load_usec: Option<u64>,
+  pub default_target: String,
+
+  /// When the default target became active, counted from the start of userspace
+  pub default_target_reached_usec: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlameEntry {
+  pub unit: String,
+
+  /// Monotonic timestamps, microseconds since boot
+  pub activating_usec: u64,
+  pub activated_usec: u64,
+  pub duration_usec: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CriticalChainDto {
+  /// The unit the chain was asked for
+  pub root: CriticalChainNode,
+
+  /// Whether dependencies were left out because the chain got too big
+  pub truncated: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CriticalChainNode {
+  pub unit: String,
+
+  /// When the unit became active, counted from the start of userspace
+  pub activated_usec: u64,
+
+  /// Time the unit took to activate, 0 for units which activate instantly like targets
+  pub duration_usec: u64,
+
+  /// Dependencies the unit waited for, usually just the one which activated last
+  pub children: Vec<CriticalChainNode>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManagerDto {
+  /// systemd version, i.e. "252.22-1"
+  pub version: String,
+
+  /// Compile time features, i.e. "+PAM", "-SELINUX"
+  pub features: Vec<String>,
+
+  /// Virtualization or container technology systemd runs in, empty on bare metal
+  pub virtualization: String,
+  pub architecture: String,
+
+  /// Reasons the system is tainted, i.e. "unmerged-usr" or "local-hwclock"
+  pub tainted: Vec<String>,
+
+  /// "initializing", "starting", "running", "degraded", "maintenance" or "stopping"
+  pub system_state: String,
+
+  /// Number of units in the failed state
+  pub n_failed_units: u32,
+
+  /// Number of queued jobs
+  pub n_jobs: u32,
+
+  /// Bootup progress between 0 and 1, 1 once bootup finished
+  pub progress: f64,
+  pub default_target: String,
+
+  /// Environment passed to every spawned process, as "NAME=value"
+  pub environment: Vec<String>,
+  pub log_level: String,
+  pub log_target: String,
+}
+
+impl ManagerDto {
+  pub fn create_from_proxy(
+    proxy: &impl OrgFreedesktopSystemd1Manager,
+  ) -> Result<ManagerDto, dbus::Error> {
+    let split = |text: String, separator: char| -> Vec<String> {
+      text
+        .split(separator)
+        .filter(|part| !part.is_empty())
+        .map(|part| part.to_owned())
+        .collect()
+    };
+
+    Ok(ManagerDto {
+      version: proxy.version()?,
+      features: split(proxy.features()?, ' '),
+      virtualization: proxy.virtualization()?,
+      architecture: proxy.architecture()?,
+      tainted: split(proxy.tainted()?, ':'),
+      system_state: proxy.system_state()?,
+      n_failed_units: proxy.nfailed_units()?,
+      n_jobs: proxy.njobs()?,
+      progress: proxy.progress()?,
+      default_target: proxy.get_default_target()?,
+      environment: proxy.environment()?,
+      log_level: proxy.log_level()?,
+      log_target: proxy.log_target()?,
+    })
+  }
+}
+
+/// See `--log-level` in `man systemd`
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogLevel {
+  Emerg,
+  Alert,
+  Crit,
+  Err,
+  Warning,
+  Notice,
+  Info,
+  Debug,
+}
+
+impl LogLevel {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      LogLevel::Emerg => "emerg",
+      LogLevel::Alert => "alert",
+      LogLevel::Crit => "crit",
+      LogLevel::Err => "err",
+      LogLevel::Warning => "warning",
+      LogLevel::Notice => "notice",
+      LogLevel::Info => "info",
+      LogLevel::Debug => "debug",
+    }
+  }
+}
+
+/// See `--log-target` in `man systemd`
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogTarget {
+  Console,
+  Journal,
+  Kmsg,
+  JournalOrKmsg,
+  Auto,
+  Null,
+}
+
+impl LogTarget {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      LogTarget::Console => "console",
+      LogTarget::Journal => "journal",
+      LogTarget::Kmsg => "kmsg",
+      LogTarget::JournalOrKmsg => "journal-or-kmsg",
+      LogTarget::Auto => "auto",
+      LogTarget::Null => "null",
+    }
+  }
+}
+
+/// See `--show-status` in `man systemd`
+#[derive(Deserialize, Clone, Copy)]
+#[serde(rename_all = "kebab-case")]
+pub enum ShowStatus {
+  Yes,
+  No,
+  Auto,
+  Error,
+
+  /// Goes back to what was configured before it was changed at runtime
+  Default,
+}
+
+impl ShowStatus {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      ShowStatus::Yes => "yes",
+      ShowStatus::No => "no",
+      ShowStatus::Auto => "auto",
+      ShowStatus::Error => "error",
+      ShowStatus::Default => "",
+    }
+  }
+}
+
+#[derive(Deserialize)]
+pub struct LogLevelRequest {
+  pub level: LogLevel,
+}
+
+#[derive(Deserialize)]
+pub struct LogTargetRequest {
+  pub target: LogTarget,
+}
+
+#[derive(Deserialize)]
+pub struct ShowStatusRequest {
+  pub mode: ShowStatus,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DefaultTargetRequest {
+  /// Target unit, i.e. "multi-user.target"
+  pub target: String,
+
+  /// Replace the default.target symlink even if it isn't one systemd created
+  #[serde(default)]
+  pub force: bool,
+
+  /// Skip reloading systemd after the symlink changed, same as `systemctl --no-reload`
+  #[serde(default)]
+  pub no_reload: bool,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn takes_job_id_from_path() {
+    let job = |path: &str| {
+      JobDto::new(
+        dbus::Path::new(path.to_owned()).unwrap(),
+        "a.service",
+        "start",
+      )
+    };
+
+    assert_eq!(job("/org/freedesktop/systemd1/job/1234").id, Some(1234));
+    assert_eq!(job("/org/freedesktop/systemd1/job/x").id, None);
+    assert_eq!(job("/org/freedesktop/systemd1/unit/1234").id, None);
+  }
+}
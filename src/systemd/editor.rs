@@ -0,0 +1,321 @@
+//! Reading and writing unit files and drop-ins, like `systemctl cat` and `systemctl edit` do
+
+use std::{
+  fs,
+  io::{self, ErrorKind},
+  path::{Path, PathBuf},
+};
+
+use crate::{api_errors::ApiError, dbus_interface::DBusInterface};
+
+use super::{
+  dbus::manager::OrgFreedesktopSystemd1Manager,
+  dto::{
+    EffectiveSectionDto, EffectiveSettingDto, NewUnitFileRequest, UnitConfigDto, UnitFileContentDto,
+  },
+  functions,
+  unit_file::{self, UnitFile},
+  unit_keys,
+};
+
+/// Where administrators put their unit files and drop-ins
+const SYSTEM_UNIT_DIR: &str = "/etc/systemd/system";
+
+/// Same as `SYSTEM_UNIT_DIR`, but gone after reboot
+const RUNTIME_UNIT_DIR: &str = "/run/systemd/system";
+
+/// Reads unit file and drop-ins of a unit and merges them into effective settings
+pub fn read_config(dbus: &DBusInterface, unit_name: &str) -> Result<UnitConfigDto, ApiError> {
+  let unit = functions::load_unit_data(dbus, unit_name)?;
+  if unit.load_state == "not-found" {
+    return Err(ApiError::NotFound(format!("Unit {} not found", unit_name)));
+  }
+
+  let fragment = match unit.fragment_path.is_empty() {
+    true => None,
+    false => Some(read_file(&unit.fragment_path)?),
+  };
+
+  let drop_ins = unit
+    .drop_in_paths
+    .iter()
+    .map(|path| read_file(path))
+    .collect::<Result<Vec<_>, _>>()?;
+
+  // Files systemd already loaded are shown as they are, even if this parser disagrees
+  let files: Vec<UnitFile> = fragment
+    .iter()
+    .chain(&drop_ins)
+    .map(|file| unit_file::parse(&file.content).0)
+    .collect();
+
+  Ok(UnitConfigDto {
+    name: unit.id,
+    fragment,
+    drop_ins,
+    effective: merge(&files),
+  })
+}
+
+/// Creates or replaces a drop-in of a unit and reloads systemd
+pub fn write_drop_in(
+  dbus: &DBusInterface,
+  unit_name: &str,
+  drop_in: &str,
+  content: &str,
+  runtime: bool,
+) -> Result<UnitConfigDto, ApiError> {
+  let path = drop_in_path(unit_name, drop_in, runtime)?;
+  validate_content(content, unit_file::unit_type(unit_name))?;
+
+  write_file(&path, content)?;
+  dbus.systemd_manager().reload()?;
+
+  read_config(dbus, unit_name)
+}
+
+/// Removes a drop-in of a unit, together with its directory if it's left empty, and reloads
+/// systemd
+pub fn delete_drop_in(
+  dbus: &DBusInterface,
+  unit_name: &str,
+  drop_in: &str,
+  runtime: bool,
+) -> Result<(), ApiError> {
+  let path = drop_in_path(unit_name, drop_in, runtime)?;
+
+  fs::remove_file(&path)?;
+  if let Some(directory) = path.parent() {
+    // Fails if there are other drop-ins, which is fine
+    let _ = fs::remove_dir(directory);
+  }
+
+  dbus.systemd_manager().reload()?;
+  Ok(())
+}
+
+/// Writes a new unit file and reloads systemd, so the unit can be started or enabled
+pub fn create_unit_file(
+  dbus: &DBusInterface,
+  request: &NewUnitFileRequest,
+) -> Result<UnitConfigDto, ApiError> {
+  if !unit_file::is_valid_unit_name(&request.name) {
+    return Err(ApiError::Validation(format!(
+      "Invalid unit name {}",
+      request.name
+    )));
+  }
+  validate_content(&request.content, unit_file::unit_type(&request.name))?;
+
+  let path = unit_dir(request.runtime).join(&request.name);
+  if path.exists() && !request.overwrite {
+    return Err(ApiError::Conflict(format!(
+      "Unit file {} already exists",
+      path.display()
+    )));
+  }
+
+  write_file(&path, &request.content)?;
+  dbus.systemd_manager().reload()?;
+
+  read_config(dbus, &request.name)
+}
+
+/// Applies files in order. Every assignment of a list setting adds a value, others replace
+/// the previous value. Empty assignment removes all values assigned before it.
+fn merge(files: &[UnitFile]) -> Vec<EffectiveSectionDto> {
+  let mut sections: Vec<EffectiveSectionDto> = Vec::new();
+
+  for section in files.iter().flat_map(|file| &file.sections) {
+    let index = match sections.iter().position(|s| s.name == section.name) {
+      Some(index) => index,
+      None => {
+        sections.push(EffectiveSectionDto {
+          name: section.name.to_owned(),
+          settings: Vec::new(),
+        });
+        sections.len() - 1
+      }
+    };
+    let settings = &mut sections[index].settings;
+
+    for entry in &section.entries {
+      let setting = match settings.iter_mut().position(|s| s.key == entry.key) {
+        Some(index) => &mut settings[index],
+        None => {
+          settings.push(EffectiveSettingDto {
+            key: entry.key.to_owned(),
+            values: Vec::new(),
+          });
+          settings.last_mut().unwrap()
+        }
+      };
+
+      if entry.reset || !unit_keys::is_list_key(&entry.key) {
+        setting.values.clear();
+      }
+      if !entry.reset {
+        setting.values.push(entry.value.to_owned());
+      }
+    }
+  }
+
+  sections
+}
+
+/// Refuses content with errors, warnings are fine since systemd only ignores those settings
+fn validate_content(content: &str, unit_type: Option<&'static str>) -> Result<(), ApiError> {
+  let validation = unit_file::validate(content, unit_type);
+
+  if !validation.errors.is_empty() {
+    let errors: Vec<String> = validation.errors.iter().map(ToString::to_string).collect();
+    return Err(ApiError::Validation(format!(
+      "Invalid unit file: {}",
+      errors.join("; ")
+    )));
+  }
+  if validation.file.sections.is_empty() {
+    return Err(ApiError::Validation("Unit file has no sections".to_owned()));
+  }
+
+  Ok(())
+}
+
+fn unit_dir(runtime: bool) -> PathBuf {
+  match runtime {
+    true => PathBuf::from(RUNTIME_UNIT_DIR),
+    false => PathBuf::from(SYSTEM_UNIT_DIR),
+  }
+}
+
+/// Path of a drop-in, `.conf` is appended to the name if it's missing, like systemd expects
+fn drop_in_path(unit_name: &str, drop_in: &str, runtime: bool) -> Result<PathBuf, ApiError> {
+  if !unit_file::is_valid_unit_name(unit_name) {
+    return Err(ApiError::Validation(format!(
+      "Invalid unit name {}",
+      unit_name
+    )));
+  }
+
+  let valid_drop_in = !drop_in.is_empty()
+    && !drop_in.starts_with('.')
+    && drop_in
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || "-_.@".contains(c));
+  if !valid_drop_in {
+    return Err(ApiError::Validation(format!(
+      "Invalid drop-in name {}",
+      drop_in
+    )));
+  }
+
+  let file_name = match drop_in.ends_with(".conf") {
+    true => drop_in.to_owned(),
+    false => format!("{}.conf", drop_in),
+  };
+
+  Ok(
+    unit_dir(runtime)
+      .join(format!("{}.d", unit_name))
+      .join(file_name),
+  )
+}
+
+fn read_file(path: &str) -> Result<UnitFileContentDto, ApiError> {
+  let content = fs::read(path)?;
+
+  Ok(UnitFileContentDto {
+    path: path.to_owned(),
+    content: String::from_utf8_lossy(&content).into_owned(),
+  })
+}
+
+/// Writes into a temporary file first and renames it, so systemd never sees a half-written
+/// file
+fn write_file(path: &Path, content: &str) -> io::Result<()> {
+  let directory = path
+    .parent()
+    .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "path has no parent"))?;
+  fs::create_dir_all(directory)?;
+
+  let mut temporary = path.as_os_str().to_owned();
+  temporary.push(".dragond-tmp");
+
+  fs::write(&temporary, content)?;
+  fs::rename(&temporary, path).inspect_err(|_| {
+    let _ = fs::remove_file(&temporary);
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn merged(files: &[&str]) -> Vec<(String, String, Vec<String>)> {
+    let files: Vec<UnitFile> = files.iter().map(|file| unit_file::parse(file).0).collect();
+    merge(&files)
+      .into_iter()
+      .flat_map(|section| {
+        section
+          .settings
+          .into_iter()
+          .map(move |setting| (section.name.clone(), setting.key, setting.values))
+      })
+      .collect()
+  }
+
+  fn setting(section: &str, key: &str, values: &[&str]) -> (String, String, Vec<String>) {
+    (
+      section.to_owned(),
+      key.to_owned(),
+      values.iter().map(|value| value.to_string()).collect(),
+    )
+  }
+
+  #[test]
+  fn keeps_last_value_of_single_value_settings() {
+    let settings = merged(&[
+      "[Service]\nType=simple\nUser=nobody\nType=forking\n",
+      "[Service]\nUser=root\n",
+    ]);
+    assert_eq!(
+      settings,
+      vec![
+        setting("Service", "Type", &["forking"]),
+        setting("Service", "User", &["root"]),
+      ]
+    );
+  }
+
+  #[test]
+  fn collects_values_of_list_settings() {
+    let settings = merged(&[
+      "[Unit]\nAfter=a.service\nConditionPathExists=/a\n[Service]\nExecStartPre=/bin/a\n",
+      "[Unit]\nAfter=b.service\nConditionPathExists=/b\n[Service]\nExecStartPre=/bin/b\n",
+    ]);
+    assert_eq!(
+      settings,
+      vec![
+        setting("Unit", "After", &["a.service", "b.service"]),
+        setting("Unit", "ConditionPathExists", &["/a", "/b"]),
+        setting("Service", "ExecStartPre", &["/bin/a", "/bin/b"]),
+      ]
+    );
+  }
+
+  #[test]
+  fn empty_assignment_resets_settings() {
+    let settings = merged(&[
+      "[Service]\nExecStart=/bin/a\nEnvironment=A=1\nUser=nobody\n",
+      "[Service]\nExecStart=\nExecStart=/bin/b\nEnvironment=\nUser=\n",
+    ]);
+    assert_eq!(
+      settings,
+      vec![
+        setting("Service", "ExecStart", &["/bin/b"]),
+        setting("Service", "Environment", &[]),
+        setting("Service", "User", &[]),
+      ]
+    );
+  }
+}
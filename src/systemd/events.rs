@@ -0,0 +1,450 @@
+use std::{thread, time::Duration};
+
+use dbus::{
+  arg::{ArgType, RefArg},
+  blocking::Connection,
+  message::{MatchRule, SignalArgs},
+  Message,
+};
+use glob::Pattern;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tokio::sync::broadcast;
+
+use crate::{
+  dbus_interface::{SYSTEMD_DESTINATION, SYSTEMD_MANAGER_PATH},
+  sse::{self, SseSender},
+};
+
+use super::{
+  dbus::manager::{
+    OrgFreedesktopDBusPropertiesPropertiesChanged, OrgFreedesktopSystemd1Manager,
+    OrgFreedesktopSystemd1ManagerJobNew, OrgFreedesktopSystemd1ManagerJobRemoved,
+    OrgFreedesktopSystemd1ManagerReloading, OrgFreedesktopSystemd1ManagerStartupFinished,
+    OrgFreedesktopSystemd1ManagerUnitFilesChanged, OrgFreedesktopSystemd1ManagerUnitNew,
+    OrgFreedesktopSystemd1ManagerUnitRemoved,
+  },
+  functions::unit_name_from_path,
+};
+
+/// How many events can be buffered for a slow receiver before it starts missing them
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// How long to wait before reconnecting after the listener lost its bus connection
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Object paths of all units live under this path
+const SYSTEMD_UNIT_PATH_PREFIX: &str = "/org/freedesktop/systemd1/unit";
+
+/// Values of `type` field of serialized events
+pub const EVENT_TYPES: [&str; 8] = [
+  "unit-new",
+  "unit-removed",
+  "job-new",
+  "job-removed",
+  "startup-finished",
+  "unit-files-changed",
+  "reloading",
+  "properties-changed",
+];
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum SystemdEvent {
+  #[serde(rename_all = "camelCase")]
+  UnitNew { unit: String, object_path: String },
+
+  #[serde(rename_all = "camelCase")]
+  UnitRemoved { unit: String, object_path: String },
+
+  #[serde(rename_all = "camelCase")]
+  JobNew {
+    id: u32,
+    object_path: String,
+    unit: String,
+  },
+
+  /// Result is one of "done", "canceled", "timeout", "failed", "dependency" or "skipped"
+  #[serde(rename_all = "camelCase")]
+  JobRemoved {
+    id: u32,
+    object_path: String,
+    unit: String,
+    result: String,
+  },
+
+  /// Sent once after boot finished, all values are durations in microseconds
+  #[serde(rename_all = "camelCase")]
+  StartupFinished {
+    firmware: u64,
+    loader: u64,
+    kernel: u64,
+    initrd: u64,
+    userspace: u64,
+    total: u64,
+  },
+
+  /// Unit files on disk were enabled, disabled, masked, ...
+  UnitFilesChanged,
+
+  /// Sent with `active: true` when daemon-reload starts and `active: false` when it's done
+  Reloading { active: bool },
+
+  /// Properties of a unit changed, i.e. its active state
+  #[serde(rename_all = "camelCase")]
+  PropertiesChanged {
+    unit: String,
+    interface: String,
+    changed_properties: Map<String, Value>,
+    invalidated_properties: Vec<String>,
+  },
+}
+
+impl SystemdEvent {
+  /// Same value as the serialized `type` field
+  pub fn event_type(&self) -> &'static str {
+    match self {
+      SystemdEvent::UnitNew { .. } => "unit-new",
+      SystemdEvent::UnitRemoved { .. } => "unit-removed",
+      SystemdEvent::JobNew { .. } => "job-new",
+      SystemdEvent::JobRemoved { .. } => "job-removed",
+      SystemdEvent::StartupFinished { .. } => "startup-finished",
+      SystemdEvent::UnitFilesChanged => "unit-files-changed",
+      SystemdEvent::Reloading { .. } => "reloading",
+      SystemdEvent::PropertiesChanged { .. } => "properties-changed",
+    }
+  }
+
+  /// Name of the unit this event is about, None for manager-wide events
+  pub fn unit(&self) -> Option<&str> {
+    match self {
+      SystemdEvent::UnitNew { unit, .. }
+      | SystemdEvent::UnitRemoved { unit, .. }
+      | SystemdEvent::JobNew { unit, .. }
+      | SystemdEvent::JobRemoved { unit, .. }
+      | SystemdEvent::PropertiesChanged { unit, .. } => Some(unit),
+      _ => None,
+    }
+  }
+}
+
+pub fn create_channel() -> broadcast::Sender<SystemdEvent> {
+  let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+  sender
+}
+
+/// Starts a thread with its own bus connection, which subscribes to systemd manager signals
+/// and forwards them to everyone listening on `sender`.
+pub fn spawn_listener(sender: broadcast::Sender<SystemdEvent>) {
+  thread::Builder::new()
+    .name("systemd-events".to_owned())
+    .spawn(move || loop {
+      if let Err(err) = listen(&sender) {
+        error!("systemd signal listener failed: {}", err);
+      }
+      thread::sleep(RECONNECT_DELAY);
+    })
+    .expect("Failed to spawn systemd signal listener thread");
+}
+
+/// Registers a match for signal `S` which converts it with `convert` and broadcasts the result
+fn forward<S, F>(
+  conn: &Connection,
+  rule: MatchRule<'static>,
+  sender: &broadcast::Sender<SystemdEvent>,
+  convert: F,
+) -> Result<(), dbus::Error>
+where
+  S: dbus::arg::ReadAll,
+  F: Fn(S, &Message) -> Option<SystemdEvent> + Send + 'static,
+{
+  let tx = sender.clone();
+  conn.add_match(rule, move |signal: S, _: &Connection, message: &Message| {
+    if let Some(event) = convert(signal, message) {
+      // Sending only fails when nobody listens, which is fine
+      let _ = tx.send(event);
+    }
+    true
+  })?;
+  Ok(())
+}
+
+fn listen(sender: &broadcast::Sender<SystemdEvent>) -> Result<(), dbus::Error> {
+  let conn = Connection::new_system()?;
+  let manager = conn.with_proxy(
+    SYSTEMD_DESTINATION,
+    SYSTEMD_MANAGER_PATH,
+    Duration::from_secs(5),
+  );
+
+  // Without a subscription systemd doesn't emit job and unit signals at all
+  manager.subscribe()?;
+
+  forward(
+    &conn,
+    OrgFreedesktopSystemd1ManagerUnitNew::match_rule(None, None).static_clone(),
+    sender,
+    |signal: OrgFreedesktopSystemd1ManagerUnitNew, _| {
+      Some(SystemdEvent::UnitNew {
+        unit: signal.id,
+        object_path: signal.unit.to_string(),
+      })
+    },
+  )?;
+
+  forward(
+    &conn,
+    OrgFreedesktopSystemd1ManagerUnitRemoved::match_rule(None, None).static_clone(),
+    sender,
+    |signal: OrgFreedesktopSystemd1ManagerUnitRemoved, _| {
+      Some(SystemdEvent::UnitRemoved {
+        unit: signal.id,
+        object_path: signal.unit.to_string(),
+      })
+    },
+  )?;
+
+  forward(
+    &conn,
+    OrgFreedesktopSystemd1ManagerJobNew::match_rule(None, None).static_clone(),
+    sender,
+    |signal: OrgFreedesktopSystemd1ManagerJobNew, _| {
+      Some(SystemdEvent::JobNew {
+        id: signal.id,
+        object_path: signal.job.to_string(),
+        unit: signal.unit,
+      })
+    },
+  )?;
+
+  forward(
+    &conn,
+    OrgFreedesktopSystemd1ManagerJobRemoved::match_rule(None, None).static_clone(),
+    sender,
+    |signal: OrgFreedesktopSystemd1ManagerJobRemoved, _| {
+      Some(SystemdEvent::JobRemoved {
+        id: signal.id,
+        object_path: signal.job.to_string(),
+        unit: signal.unit,
+        result: signal.result,
+      })
+    },
+  )?;
+
+  forward(
+    &conn,
+    OrgFreedesktopSystemd1ManagerStartupFinished::match_rule(None, None).static_clone(),
+    sender,
+    |signal: OrgFreedesktopSystemd1ManagerStartupFinished, _| {
+      Some(SystemdEvent::StartupFinished {
+        firmware: signal.firmware,
+        loader: signal.loader,
+        kernel: signal.kernel,
+        initrd: signal.initrd,
+        userspace: signal.userspace,
+        total: signal.total,
+      })
+    },
+  )?;
+
+  forward(
+    &conn,
+    OrgFreedesktopSystemd1ManagerUnitFilesChanged::match_rule(None, None).static_clone(),
+    sender,
+    |_: OrgFreedesktopSystemd1ManagerUnitFilesChanged, _| Some(SystemdEvent::UnitFilesChanged),
+  )?;
+
+  forward(
+    &conn,
+    OrgFreedesktopSystemd1ManagerReloading::match_rule(None, None).static_clone(),
+    sender,
+    |signal: OrgFreedesktopSystemd1ManagerReloading, _| {
+      Some(SystemdEvent::Reloading {
+        active: signal.active,
+      })
+    },
+  )?;
+
+  // Sender isn't part of the rule, because signals come from systemd's unique bus name
+  // and the well known name wouldn't match them locally
+  let properties_rule = OrgFreedesktopDBusPropertiesPropertiesChanged::match_rule(None, None)
+    .with_namespaced_path(SYSTEMD_UNIT_PATH_PREFIX)
+    .static_clone();
+
+  forward(
+    &conn,
+    properties_rule,
+    sender,
+    |signal: OrgFreedesktopDBusPropertiesPropertiesChanged, message| {
+      let unit = unit_name_from_path(&message.path()?)?;
+
+      let changed_properties = signal
+        .changed_properties
+        .iter()
+        .map(|(name, value)| (name.to_owned(), refarg_to_json(value)))
+        .collect();
+
+      Some(SystemdEvent::PropertiesChanged {
+        unit,
+        interface: signal.interface_name,
+        changed_properties,
+        invalidated_properties: signal.invalidated_properties,
+      })
+    },
+  )?;
+
+  info!("Listening for systemd signals");
+
+  loop {
+    conn.process(Duration::from_secs(1))?;
+  }
+}
+
+/// Converts any D-Bus value to JSON. Dictionaries become objects, arrays and structs become arrays.
+fn refarg_to_json(value: &dyn RefArg) -> Value {
+  match value.arg_type() {
+    ArgType::Boolean => Value::Bool(value.as_i64() == Some(1)),
+    ArgType::Byte | ArgType::UInt16 | ArgType::UInt32 | ArgType::UInt64 => {
+      value.as_u64().map(Value::from).unwrap_or(Value::Null)
+    }
+    ArgType::Int16 | ArgType::Int32 | ArgType::Int64 | ArgType::UnixFd => {
+      value.as_i64().map(Value::from).unwrap_or(Value::Null)
+    }
+    ArgType::Double => value.as_f64().map(Value::from).unwrap_or(Value::Null),
+    ArgType::String | ArgType::ObjectPath | ArgType::Signature => {
+      value.as_str().map(Value::from).unwrap_or(Value::Null)
+    }
+    ArgType::Variant => value
+      .as_iter()
+      .and_then(|mut inner| inner.next().map(refarg_to_json))
+      .unwrap_or(Value::Null),
+    ArgType::Array if value.signature().starts_with("a{") => {
+      let mut object = Map::new();
+      if let Some(mut items) = value.as_iter() {
+        while let (Some(key), Some(item)) = (items.next(), items.next()) {
+          let key = match key.as_str() {
+            Some(key) => key.to_owned(),
+            None => refarg_to_json(key).to_string(),
+          };
+          object.insert(key, refarg_to_json(item));
+        }
+      }
+      Value::Object(object)
+    }
+    ArgType::Array | ArgType::Struct | ArgType::DictEntry => Value::Array(
+      value
+        .as_iter()
+        .map(|items| items.map(refarg_to_json).collect())
+        .unwrap_or_default(),
+    ),
+    ArgType::Invalid => Value::Null,
+  }
+}
+
+/// Waits until systemd reports that the job with given object path is gone and returns its result.
+///
+/// Receiver has to be subscribed before the job was enqueued, otherwise the signal can be missed.
+/// Returns None if the job didn't finish before the timeout.
+pub async fn wait_for_job(
+  mut receiver: broadcast::Receiver<SystemdEvent>,
+  object_path: &str,
+  timeout: Duration,
+) -> Option<String> {
+  let wait = async {
+    loop {
+      match receiver.recv().await {
+        Ok(SystemdEvent::JobRemoved {
+          object_path: path,
+          result,
+          ..
+        }) if path == object_path => return Some(result),
+        Ok(_) => continue,
+        Err(broadcast::error::RecvError::Lagged(missed)) => {
+          warn!("Job waiter missed {} systemd events", missed);
+          continue;
+        }
+        Err(broadcast::error::RecvError::Closed) => return None,
+      }
+    }
+  };
+
+  tokio::time::timeout(timeout, wait).await.ok().flatten()
+}
+
+/// Decides which events a client is interested in
+pub struct EventFilter {
+  units: Vec<Pattern>,
+  types: Vec<String>,
+}
+
+impl EventFilter {
+  /// Both arguments are comma separated lists, `units` of unit name globs and `types` of
+  /// event types. Missing list means everything.
+  pub fn parse(units: Option<&str>, types: Option<&str>) -> Result<EventFilter, String> {
+    let units = split_list(units)
+      .map(|unit| Pattern::new(unit).map_err(|err| format!("Invalid unit glob {}: {}", unit, err)))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let types = split_list(types)
+      .map(|event_type| match EVENT_TYPES.contains(&event_type) {
+        true => Ok(event_type.to_owned()),
+        false => Err(format!(
+          "Unknown event type {}, expected one of: {}",
+          event_type,
+          EVENT_TYPES.join(", ")
+        )),
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(EventFilter { units, types })
+  }
+
+  /// Unit globs only apply to events about a unit, manager-wide events like `reloading`
+  /// are filtered only by type
+  pub fn matches(&self, event: &SystemdEvent) -> bool {
+    if !self.types.is_empty() && !self.types.iter().any(|t| t == event.event_type()) {
+      return false;
+    }
+
+    match event.unit() {
+      Some(unit) if !self.units.is_empty() => self.units.iter().any(|glob| glob.matches(unit)),
+      _ => true,
+    }
+  }
+}
+
+/// Items of a comma separated query parameter, a missing one has none
+pub(crate) fn split_list(list: Option<&str>) -> impl Iterator<Item = &str> {
+  list
+    .unwrap_or_default()
+    .split(',')
+    .map(str::trim)
+    .filter(|item| !item.is_empty())
+}
+
+/// Pushes matching events to a Server-Sent Events client until it disconnects
+pub async fn stream_events(
+  mut receiver: broadcast::Receiver<SystemdEvent>,
+  filter: EventFilter,
+  sender: SseSender,
+) {
+  loop {
+    let connected = match tokio::time::timeout(sse::KEEPALIVE_INTERVAL, receiver.recv()).await {
+      Err(_) => sender.keepalive().await,
+      Ok(Ok(event)) if filter.matches(&event) => {
+        let data = serde_json::to_string(&event).unwrap_or("{}".to_owned());
+        sender.send(event.event_type(), None, &data).await
+      }
+      Ok(Ok(_)) => true,
+      Ok(Err(broadcast::error::RecvError::Lagged(missed))) => {
+        warn!("Event stream client missed {} systemd events", missed);
+        true
+      }
+      Ok(Err(broadcast::error::RecvError::Closed)) => false,
+    };
+
+    if !connected {
+      break;
+    }
+  }
+}
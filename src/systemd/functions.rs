@@ -1,10 +1,18 @@
-use std::ops::Deref;
+use std::{collections::BTreeMap, ops::Deref};
 
-use crate::dbus_interface::DBusInterface;
+use dbus::{arg::PropMap, blocking::stdintf::org_freedesktop_dbus::Properties};
+
+use crate::{api_errors::ApiError, dbus_interface::DBusInterface};
 
 use super::{
   dbus::manager::OrgFreedesktopSystemd1Manager,
-  dto::{ServiceDto, UnitDto, UnitListEntry},
+  dto::{
+    AutomountDto, DefaultTargetRequest, DeviceDto, JobDto, JobListEntry, JobMode, LogLevel,
+    LogTarget, ManagerDto, MountDto, PathDto, ScopeDto, ServiceDto, ShowStatus, SliceDto,
+    SocketDto, SwapDto, TargetDto, TimerDto, TimerListEntry, UnitDto, UnitFileChangesDto,
+    UnitFileListEntry, UnitFileStateDto, UnitFilesRequest, UnitListEntry, UnitTypeDto,
+  },
+  unit_file,
 };
 
 pub fn load_unit_data(dbus: &DBusInterface, unit_name: &str) -> Result<UnitDto, dbus::Error> {
@@ -13,16 +21,388 @@ pub fn load_unit_data(dbus: &DBusInterface, unit_name: &str) -> Result<UnitDto,
   let unit_proxy = dbus.systemd_unit(unit_path.deref());
   let mut unit = UnitDto::create_from_proxy(&unit_proxy)?;
 
-  if unit_name.ends_with(".service") {
-    let service_proxy = dbus.systemd_service(unit_path.deref());
-    unit.add_service(ServiceDto::create_from_proxy(&service_proxy)?);
-  }
+  // Unit name could be an alias, id has the real suffix
+  let path = unit_path.deref();
+  let properties = match unit_file::unit_type(&unit.id) {
+    Some("service") => {
+      UnitTypeDto::Service(ServiceDto::create_from_proxy(&dbus.systemd_service(path))?)
+    }
+    Some("timer") => UnitTypeDto::Timer(TimerDto::create_from_proxy(&dbus.systemd_timer(path))?),
+    Some("socket") => {
+      UnitTypeDto::Socket(SocketDto::create_from_proxy(&dbus.systemd_socket(path))?)
+    }
+    Some("mount") => UnitTypeDto::Mount(MountDto::create_from_proxy(&dbus.systemd_mount(path))?),
+    Some("automount") => UnitTypeDto::Automount(AutomountDto::create_from_proxy(
+      &dbus.systemd_automount(path),
+    )?),
+    Some("path") => UnitTypeDto::Path(PathDto::create_from_proxy(&dbus.systemd_path(path))?),
+    Some("swap") => UnitTypeDto::Swap(SwapDto::create_from_proxy(&dbus.systemd_swap(path))?),
+    Some("scope") => UnitTypeDto::Scope(ScopeDto::create_from_proxy(&dbus.systemd_scope(path))?),
+    Some("slice") => UnitTypeDto::Slice(SliceDto::create_from_proxy(&dbus.systemd_slice(path))?),
+    Some("device") => {
+      UnitTypeDto::Device(DeviceDto::create_from_proxy(&dbus.systemd_device(path))?)
+    }
+    Some("target") => UnitTypeDto::Target(TargetDto::create_from_proxy(&unit_proxy)?),
+    _ => return Ok(unit),
+  };
 
+  unit.add_type_properties(properties);
   Ok(unit)
 }
 
+/// All properties of the Unit interface, in one call instead of one per property
+pub fn unit_properties(dbus: &DBusInterface, unit_path: &str) -> Result<PropMap, dbus::Error> {
+  dbus
+    .systemd_proxy_for_path(unit_path)
+    .get_all("org.freedesktop.systemd1.Unit")
+}
+
+pub fn manager_overview(dbus: &DBusInterface) -> Result<ManagerDto, dbus::Error> {
+  ManagerDto::create_from_proxy(dbus.systemd_manager())
+}
+
 pub fn list_units(dbus: &DBusInterface) -> Result<Vec<UnitListEntry>, dbus::Error> {
   let manager = dbus.systemd_manager();
   let unit_paths = manager.list_units()?;
-  return Ok(unit_paths.into_iter().map(UnitListEntry::from).collect());
+  Ok(unit_paths.into_iter().map(UnitListEntry::from).collect())
+}
+
+/// Jobs that can be enqueued for a unit through the manager
+#[derive(Clone, Copy)]
+pub enum UnitJobKind {
+  Start,
+  Stop,
+  Reload,
+  Restart,
+  TryRestart,
+  ReloadOrRestart,
+}
+
+impl UnitJobKind {
+  pub fn as_str(&self) -> &'static str {
+    match self {
+      UnitJobKind::Start => "start",
+      UnitJobKind::Stop => "stop",
+      UnitJobKind::Reload => "reload",
+      UnitJobKind::Restart => "restart",
+      UnitJobKind::TryRestart => "try-restart",
+      UnitJobKind::ReloadOrRestart => "reload-or-restart",
+    }
+  }
+}
+
+/// Loaded timer units sorted by next elapse, like `systemctl list-timers`. Inactive timers
+/// are only included with `all`.
+pub fn list_timers(dbus: &DBusInterface, all: bool) -> Result<Vec<TimerListEntry>, dbus::Error> {
+  let units = list_units(dbus)?;
+  let mut timers = Vec::new();
+
+  for unit in units {
+    if !unit.name.ends_with(".timer") || (!all && unit.active_state == "inactive") {
+      continue;
+    }
+
+    let timer_proxy = dbus.systemd_timer(&unit.object_path);
+    timers.push(TimerListEntry {
+      timer: TimerDto::create_from_proxy(&timer_proxy)?,
+      name: unit.name,
+      description: unit.description,
+      active_state: unit.active_state,
+      sub_state: unit.sub_state,
+    });
+  }
+
+  // Timers which won't elapse go last
+  timers.sort_by_key(|entry| (entry.timer.next_elapse.is_none(), entry.timer.next_elapse));
+  Ok(timers)
+}
+
+/// Enqueues a start/stop/... job for a unit. Returns as soon as systemd accepted the job,
+/// not when it finished.
+pub fn unit_job(
+  dbus: &DBusInterface,
+  kind: UnitJobKind,
+  unit_name: &str,
+  mode: JobMode,
+) -> Result<JobDto, dbus::Error> {
+  let manager = dbus.systemd_manager();
+  let mode = mode.as_str();
+
+  let job_path = match kind {
+    UnitJobKind::Start => manager.start_unit(unit_name, mode)?,
+    UnitJobKind::Stop => manager.stop_unit(unit_name, mode)?,
+    UnitJobKind::Reload => manager.reload_unit(unit_name, mode)?,
+    UnitJobKind::Restart => manager.restart_unit(unit_name, mode)?,
+    UnitJobKind::TryRestart => manager.try_restart_unit(unit_name, mode)?,
+    UnitJobKind::ReloadOrRestart => manager.reload_or_restart_unit(unit_name, mode)?,
+  };
+
+  Ok(JobDto::new(job_path, unit_name, kind.as_str()))
+}
+
+pub fn list_jobs(dbus: &DBusInterface) -> Result<Vec<JobListEntry>, dbus::Error> {
+  let manager = dbus.systemd_manager();
+  let jobs = manager.list_jobs()?;
+  Ok(jobs.into_iter().map(JobListEntry::from).collect())
+}
+
+pub fn get_job(dbus: &DBusInterface, id: u32) -> Result<JobListEntry, dbus::Error> {
+  let manager = dbus.systemd_manager();
+  let job_path = manager.get_job(id)?;
+  let job_proxy = dbus.systemd_job(job_path.deref());
+  JobListEntry::create_from_proxy(&job_proxy, job_path.deref())
+}
+
+pub fn cancel_job(dbus: &DBusInterface, id: u32) -> Result<(), dbus::Error> {
+  dbus.systemd_manager().cancel_job(id)
+}
+
+/// Cancels all queued jobs
+pub fn clear_jobs(dbus: &DBusInterface) -> Result<(), dbus::Error> {
+  dbus.systemd_manager().clear_jobs()
+}
+
+/// Reloads unit files and configuration, like `systemctl daemon-reload`
+pub fn daemon_reload(dbus: &DBusInterface) -> Result<(), dbus::Error> {
+  dbus.systemd_manager().reload()
+}
+
+/// Reexecutes systemd, like `systemctl daemon-reexec`. systemd drops the connection while
+/// reexecuting, so a missing reply means it worked.
+pub fn daemon_reexec(dbus: &DBusInterface) -> Result<(), dbus::Error> {
+  match dbus.systemd_manager().reexecute() {
+    Err(err) if err.name() == Some("org.freedesktop.DBus.Error.NoReply") => Ok(()),
+    result => result,
+  }
+}
+
+pub fn set_log_level(dbus: &DBusInterface, level: LogLevel) -> Result<(), dbus::Error> {
+  dbus
+    .systemd_manager()
+    .set_log_level(level.as_str().to_owned())
+}
+
+pub fn set_log_target(dbus: &DBusInterface, target: LogTarget) -> Result<(), dbus::Error> {
+  dbus
+    .systemd_manager()
+    .set_log_target(target.as_str().to_owned())
+}
+
+pub fn set_show_status(dbus: &DBusInterface, mode: ShowStatus) -> Result<(), dbus::Error> {
+  dbus.systemd_manager().set_show_status_(mode.as_str())
+}
+
+/// Clears the failed state of all units, like `systemctl reset-failed`
+pub fn reset_failed(dbus: &DBusInterface) -> Result<(), dbus::Error> {
+  dbus.systemd_manager().reset_failed()
+}
+
+/// Points default.target to another target, like `systemctl set-default`
+pub fn set_default_target(
+  dbus: &DBusInterface,
+  request: &DefaultTargetRequest,
+) -> Result<UnitFileChangesDto, ApiError> {
+  if unit_file::unit_type(&request.target) != Some("target")
+    || !unit_file::is_valid_unit_name(&request.target)
+  {
+    return Err(ApiError::Validation(format!(
+      "Invalid target {}, expected a target unit name like multi-user.target",
+      request.target
+    )));
+  }
+
+  let manager = dbus.systemd_manager();
+  let changes = manager.set_default_target(&request.target, request.force)?;
+  if !changes.is_empty() && !request.no_reload {
+    manager.reload()?;
+  }
+
+  Ok(UnitFileChangesDto::new(None, changes))
+}
+
+/// Sets variables in the environment systemd passes to every spawned process, like
+/// `systemctl set-environment`
+pub fn set_environment(
+  dbus: &DBusInterface,
+  variables: &BTreeMap<String, String>,
+) -> Result<(), ApiError> {
+  if variables.is_empty() {
+    return Err(ApiError::Validation("No variables given".to_owned()));
+  }
+
+  let assignments = environment_assignments(variables)?;
+  let assignments: Vec<&str> = assignments.iter().map(String::as_str).collect();
+  Ok(dbus.systemd_manager().set_environment_(assignments)?)
+}
+
+/// Removes variables from the environment systemd passes to every spawned process, like
+/// `systemctl unset-environment`
+pub fn unset_environment(dbus: &DBusInterface, names: &[&str]) -> Result<(), ApiError> {
+  if names.is_empty() {
+    return Err(ApiError::Validation("No variables given".to_owned()));
+  }
+  for name in names {
+    validate_environment_name(name).map_err(ApiError::Validation)?;
+  }
+
+  Ok(dbus.systemd_manager().unset_environment(names.to_vec())?)
+}
+
+/// Validates variables and turns them into `NAME=value` assignments systemd takes
+pub fn environment_assignments(
+  variables: &BTreeMap<String, String>,
+) -> Result<Vec<String>, ApiError> {
+  let mut assignments = Vec::new();
+  for (name, value) in variables {
+    validate_environment_name(name).map_err(ApiError::Validation)?;
+    if value
+      .chars()
+      .any(|c| c.is_control() && c != '\n' && c != '\t')
+    {
+      return Err(ApiError::Validation(format!(
+        "Value of {} can't contain control characters",
+        name
+      )));
+    }
+    assignments.push(format!("{}={}", name, value));
+  }
+
+  Ok(assignments)
+}
+
+/// Same rules as systemd uses, letters, digits and underscores, not starting with a digit
+fn validate_environment_name(name: &str) -> Result<(), String> {
+  let valid = name
+    .chars()
+    .next()
+    .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+
+  match valid {
+    true => Ok(()),
+    false => Err(format!("Invalid environment variable name {}", name)),
+  }
+}
+
+/// Enablement states a unit file can be in, see `is-enabled` in `man systemctl`
+pub const UNIT_FILE_STATES: [&str; 14] = [
+  "enabled",
+  "enabled-runtime",
+  "linked",
+  "linked-runtime",
+  "alias",
+  "masked",
+  "masked-runtime",
+  "static",
+  "disabled",
+  "invalid",
+  "indirect",
+  "generated",
+  "transient",
+  "bad",
+];
+
+/// Lists installed unit files, loaded or not. Only files in one of `states` and with a name
+/// matching one of `patterns` globs are listed, empty lists mean no filtering.
+pub fn list_unit_files(
+  dbus: &DBusInterface,
+  states: &[&str],
+  patterns: &[&str],
+) -> Result<Vec<UnitFileListEntry>, dbus::Error> {
+  let manager = dbus.systemd_manager();
+
+  let files = match states.is_empty() && patterns.is_empty() {
+    true => manager.list_unit_files()?,
+    false => manager.list_unit_files_by_patterns(states.to_vec(), patterns.to_vec())?,
+  };
+
+  let mut files: Vec<UnitFileListEntry> = files.into_iter().map(UnitFileListEntry::from).collect();
+  files.sort_by(|a, b| a.name.cmp(&b.name));
+  Ok(files)
+}
+
+pub fn get_unit_file_state(
+  dbus: &DBusInterface,
+  unit_name: &str,
+) -> Result<UnitFileStateDto, dbus::Error> {
+  let state = dbus.systemd_manager().get_unit_file_state(unit_name)?;
+
+  Ok(UnitFileStateDto {
+    name: unit_name.to_owned(),
+    state,
+  })
+}
+
+/// Operations changing which unit files are enabled
+#[derive(Clone, Copy)]
+pub enum UnitFileChangeKind {
+  Enable,
+  Disable,
+  Reenable,
+  Link,
+  Preset,
+  Mask,
+  Unmask,
+  Revert,
+}
+
+/// Changes unit file symlinks, then reloads systemd so it sees the changes, like systemctl
+/// does. Returns symlinks which were created or removed.
+pub fn change_unit_files(
+  dbus: &DBusInterface,
+  kind: UnitFileChangeKind,
+  request: &UnitFilesRequest,
+) -> Result<UnitFileChangesDto, dbus::Error> {
+  let manager = dbus.systemd_manager();
+  let files: Vec<&str> = request.files.iter().map(String::as_str).collect();
+  let (runtime, force) = (request.runtime, request.force);
+
+  let (carries_install_info, changes) = match kind {
+    UnitFileChangeKind::Enable => {
+      let (install_info, changes) = manager.enable_unit_files(files, runtime, force)?;
+      (Some(install_info), changes)
+    }
+    UnitFileChangeKind::Disable => (None, manager.disable_unit_files(files, runtime)?),
+    UnitFileChangeKind::Reenable => {
+      let (install_info, changes) = manager.reenable_unit_files(files, runtime, force)?;
+      (Some(install_info), changes)
+    }
+    UnitFileChangeKind::Link => (None, manager.link_unit_files(files, runtime, force)?),
+    UnitFileChangeKind::Preset => {
+      let mode = request.mode.as_str();
+      let (install_info, changes) =
+        manager.preset_unit_files_with_mode(files, mode, runtime, force)?;
+      (Some(install_info), changes)
+    }
+    UnitFileChangeKind::Mask => (None, manager.mask_unit_files(files, runtime, force)?),
+    UnitFileChangeKind::Unmask => (None, manager.unmask_unit_files(files, runtime)?),
+    UnitFileChangeKind::Revert => (None, manager.revert_unit_files(files)?),
+  };
+
+  if !changes.is_empty() && !request.no_reload {
+    manager.reload()?;
+  }
+
+  Ok(UnitFileChangesDto::new(carries_install_info, changes))
+}
+
+/// Reverses the escaping systemd does when putting unit names in object paths, i.e.
+/// `/org/freedesktop/systemd1/unit/dbus_2eservice` becomes `dbus.service`
+pub fn unit_name_from_path(path: &str) -> Option<String> {
+  let escaped = path.rsplit('/').next()?.as_bytes();
+  let mut name = Vec::with_capacity(escaped.len());
+  let mut i = 0;
+
+  while i < escaped.len() {
+    if escaped[i] == b'_' && i + 2 < escaped.len() {
+      let hex = std::str::from_utf8(&escaped[i + 1..i + 3]).ok()?;
+      name.push(u8::from_str_radix(hex, 16).ok()?);
+      i += 3;
+    } else {
+      name.push(escaped[i]);
+      i += 1;
+    }
+  }
+
+  String::from_utf8(name).ok()
 }
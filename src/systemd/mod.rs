@@ -1,4 +1,11 @@
+pub mod analyze;
 pub mod dbus;
+pub mod dependencies;
 pub mod dto;
+pub mod editor;
+pub mod events;
 pub mod functions;
 pub mod routes;
+pub mod transient;
+pub mod unit_file;
+pub mod unit_keys;
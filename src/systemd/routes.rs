@@ -1,5 +1,25 @@
-use crate::{api_errors::ApiError, systemd::functions, AppState};
-use actix_web::{get, http::header::ContentType, web, HttpResponse, Responder};
+use std::{collections::BTreeMap, time::Duration};
+
+use crate::{
+  api_errors::ApiError,
+  sse,
+  systemd::{
+    analyze,
+    dependencies::{self, DependencyDirection, DependencyType},
+    dto::{
+      DefaultTargetRequest, DropInRequest, JobMode, LogLevelRequest, LogTargetRequest,
+      NewUnitFileRequest, RunRequest, ShowStatusRequest, UnitFileValidationDto, UnitFilesRequest,
+      ValidateUnitFileRequest,
+    },
+    editor, events, functions,
+    functions::{UnitFileChangeKind, UnitJobKind},
+    transient, unit_file,
+  },
+  AppState,
+};
+use actix_web::{
+  delete, get, http::header::ContentType, post, put, web, web::Query, HttpResponse, Responder,
+};
 
 #[get("/load-unit/{name}")]
 async fn load_unit(
@@ -19,6 +39,123 @@ async fn load_unit(
   )
 }
 
+#[get("/manager")]
+async fn manager(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let manager = functions::manager_overview(&dbus)?;
+
+  let serialized = serde_json::to_string(&manager).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[post("/manager/reload")]
+async fn daemon_reload(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  functions::daemon_reload(&dbus)?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/manager/reexecute")]
+async fn daemon_reexec(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  functions::daemon_reexec(&dbus)?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+#[put("/manager/log-level")]
+async fn set_log_level(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<LogLevelRequest>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  functions::set_log_level(&dbus, request.level)?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+#[put("/manager/log-target")]
+async fn set_log_target(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<LogTargetRequest>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  functions::set_log_target(&dbus, request.target)?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+#[put("/manager/show-status")]
+async fn set_show_status(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<ShowStatusRequest>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  functions::set_show_status(&dbus, request.mode)?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+#[put("/manager/default-target")]
+async fn set_default_target(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<DefaultTargetRequest>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let changes = functions::set_default_target(&dbus, &request)?;
+
+  let serialized = serde_json::to_string(&changes).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[put("/manager/environment")]
+async fn set_environment(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<BTreeMap<String, String>>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  functions::set_environment(&dbus, &request)?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Deserialize)]
+struct UnsetEnvironmentQuery {
+  /// Comma separated variable names
+  names: Option<String>,
+}
+
+#[delete("/manager/environment")]
+async fn unset_environment(
+  state: web::Data<AppState<'static>>,
+  query: Query<UnsetEnvironmentQuery>,
+) -> Result<impl Responder, ApiError> {
+  let names: Vec<&str> = events::split_list(query.names.as_deref()).collect();
+  let dbus = state.dbus.lock().unwrap();
+  functions::unset_environment(&dbus, &names)?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/manager/reset-failed")]
+async fn reset_failed(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  functions::reset_failed(&dbus)?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
 #[get("/list-units")]
 async fn list_units(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
   let dbus = state.dbus.lock().unwrap();
@@ -32,3 +169,644 @@ async fn list_units(state: web::Data<AppState<'static>>) -> Result<impl Responde
       .body(serialized),
   )
 }
+
+/// Upper limit for `wait`, so a request can't hang forever
+const MAX_JOB_WAIT_SECS: u64 = 600;
+
+#[derive(Deserialize)]
+struct UnitJobQuery {
+  #[serde(default)]
+  mode: JobMode,
+
+  /// If set, waits up to this many seconds for the job to finish and includes its result
+  wait: Option<u64>,
+}
+
+async fn unit_job_response(
+  state: &AppState<'static>,
+  name: &str,
+  kind: UnitJobKind,
+  query: UnitJobQuery,
+) -> Result<HttpResponse, ApiError> {
+  if query.wait.is_some_and(|wait| wait > MAX_JOB_WAIT_SECS) {
+    return Err(ApiError::Validation(format!(
+      "wait can't be longer than {} seconds",
+      MAX_JOB_WAIT_SECS
+    )));
+  }
+
+  // Subscribing before enqueuing the job, so its JobRemoved signal can't slip through
+  let receiver = state.systemd_events.subscribe();
+
+  let mut job = {
+    let dbus = state.dbus.lock().unwrap();
+    functions::unit_job(&dbus, kind, name, query.mode)?
+  };
+
+  if let Some(wait) = query.wait {
+    let result = events::wait_for_job(receiver, &job.object_path, Duration::from_secs(wait)).await;
+
+    match result {
+      Some(result) => job.result = Some(result),
+      None => {
+        return Err(ApiError::Timeout(format!(
+          "Job {} didn't finish within {} seconds",
+          job.object_path, wait
+        )))
+      }
+    }
+  }
+
+  let serialized = serde_json::to_string(&job).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[post("/start-unit/{name}")]
+async fn start_unit(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+  query: Query<UnitJobQuery>,
+) -> Result<impl Responder, ApiError> {
+  unit_job_response(&state, &path, UnitJobKind::Start, query.into_inner()).await
+}
+
+#[post("/stop-unit/{name}")]
+async fn stop_unit(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+  query: Query<UnitJobQuery>,
+) -> Result<impl Responder, ApiError> {
+  unit_job_response(&state, &path, UnitJobKind::Stop, query.into_inner()).await
+}
+
+#[post("/reload-unit/{name}")]
+async fn reload_unit(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+  query: Query<UnitJobQuery>,
+) -> Result<impl Responder, ApiError> {
+  unit_job_response(&state, &path, UnitJobKind::Reload, query.into_inner()).await
+}
+
+#[post("/restart-unit/{name}")]
+async fn restart_unit(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+  query: Query<UnitJobQuery>,
+) -> Result<impl Responder, ApiError> {
+  unit_job_response(&state, &path, UnitJobKind::Restart, query.into_inner()).await
+}
+
+#[post("/try-restart-unit/{name}")]
+async fn try_restart_unit(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+  query: Query<UnitJobQuery>,
+) -> Result<impl Responder, ApiError> {
+  unit_job_response(&state, &path, UnitJobKind::TryRestart, query.into_inner()).await
+}
+
+#[post("/reload-or-restart-unit/{name}")]
+async fn reload_or_restart_unit(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+  query: Query<UnitJobQuery>,
+) -> Result<impl Responder, ApiError> {
+  unit_job_response(
+    &state,
+    &path,
+    UnitJobKind::ReloadOrRestart,
+    query.into_inner(),
+  )
+  .await
+}
+
+#[derive(Deserialize)]
+struct UnitFilesQuery {
+  /// Comma separated list of states, see `functions::UNIT_FILE_STATES`
+  states: Option<String>,
+
+  /// Comma separated list of unit name globs
+  patterns: Option<String>,
+}
+
+#[get("/unit-files")]
+async fn list_unit_files(
+  state: web::Data<AppState<'static>>,
+  query: Query<UnitFilesQuery>,
+) -> Result<impl Responder, ApiError> {
+  let states: Vec<&str> = events::split_list(query.states.as_deref()).collect();
+  if let Some(unknown) = states
+    .iter()
+    .find(|state| !functions::UNIT_FILE_STATES.contains(state))
+  {
+    return Err(ApiError::Validation(format!(
+      "Unknown unit file state {}, expected one of: {}",
+      unknown,
+      functions::UNIT_FILE_STATES.join(", ")
+    )));
+  }
+  let patterns: Vec<&str> = events::split_list(query.patterns.as_deref()).collect();
+
+  let dbus = state.dbus.lock().unwrap();
+  let files = functions::list_unit_files(&dbus, &states, &patterns)?;
+
+  let serialized = serde_json::to_string(&files).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[get("/unit-files/{name}")]
+async fn get_unit_file_state(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let file_state = functions::get_unit_file_state(&dbus, &path)?;
+
+  let serialized = serde_json::to_string(&file_state).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+fn unit_files_response(
+  state: &AppState<'static>,
+  kind: UnitFileChangeKind,
+  request: &UnitFilesRequest,
+) -> Result<HttpResponse, ApiError> {
+  if request.files.is_empty() {
+    return Err(ApiError::Validation("files can't be empty".to_owned()));
+  }
+
+  let dbus = state.dbus.lock().unwrap();
+  let changes = functions::change_unit_files(&dbus, kind, request)?;
+
+  let serialized = serde_json::to_string(&changes).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[post("/enable-unit-files")]
+async fn enable_unit_files(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<UnitFilesRequest>,
+) -> Result<impl Responder, ApiError> {
+  unit_files_response(&state, UnitFileChangeKind::Enable, &request)
+}
+
+#[post("/disable-unit-files")]
+async fn disable_unit_files(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<UnitFilesRequest>,
+) -> Result<impl Responder, ApiError> {
+  unit_files_response(&state, UnitFileChangeKind::Disable, &request)
+}
+
+#[post("/reenable-unit-files")]
+async fn reenable_unit_files(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<UnitFilesRequest>,
+) -> Result<impl Responder, ApiError> {
+  unit_files_response(&state, UnitFileChangeKind::Reenable, &request)
+}
+
+#[post("/link-unit-files")]
+async fn link_unit_files(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<UnitFilesRequest>,
+) -> Result<impl Responder, ApiError> {
+  unit_files_response(&state, UnitFileChangeKind::Link, &request)
+}
+
+#[post("/preset-unit-files")]
+async fn preset_unit_files(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<UnitFilesRequest>,
+) -> Result<impl Responder, ApiError> {
+  unit_files_response(&state, UnitFileChangeKind::Preset, &request)
+}
+
+#[post("/mask-unit-files")]
+async fn mask_unit_files(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<UnitFilesRequest>,
+) -> Result<impl Responder, ApiError> {
+  unit_files_response(&state, UnitFileChangeKind::Mask, &request)
+}
+
+#[post("/unmask-unit-files")]
+async fn unmask_unit_files(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<UnitFilesRequest>,
+) -> Result<impl Responder, ApiError> {
+  unit_files_response(&state, UnitFileChangeKind::Unmask, &request)
+}
+
+#[post("/revert-unit-files")]
+async fn revert_unit_files(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<UnitFilesRequest>,
+) -> Result<impl Responder, ApiError> {
+  unit_files_response(&state, UnitFileChangeKind::Revert, &request)
+}
+
+/// Unit file and drop-ins of a unit, with settings they add up to
+#[get("/units/{name}/config")]
+async fn get_unit_config(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let config = editor::read_config(&dbus, &path)?;
+
+  let serialized = serde_json::to_string(&config).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[put("/units/{name}/drop-ins/{drop_in}")]
+async fn put_drop_in(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<(String, String)>,
+  request: web::Json<DropInRequest>,
+) -> Result<impl Responder, ApiError> {
+  let (name, drop_in) = path.into_inner();
+  let dbus = state.dbus.lock().unwrap();
+  let config = editor::write_drop_in(&dbus, &name, &drop_in, &request.content, request.runtime)?;
+
+  let serialized = serde_json::to_string(&config).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[derive(Deserialize)]
+struct DropInQuery {
+  /// Whether the drop-in is in `/run` instead of `/etc`
+  #[serde(default)]
+  runtime: bool,
+}
+
+#[delete("/units/{name}/drop-ins/{drop_in}")]
+async fn delete_drop_in(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<(String, String)>,
+  query: Query<DropInQuery>,
+) -> Result<impl Responder, ApiError> {
+  let (name, drop_in) = path.into_inner();
+  let dbus = state.dbus.lock().unwrap();
+  editor::delete_drop_in(&dbus, &name, &drop_in, query.runtime)?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+#[post("/unit-files")]
+async fn create_unit_file(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<NewUnitFileRequest>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let config = editor::create_unit_file(&dbus, &request)?;
+
+  let serialized = serde_json::to_string(&config).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Created()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+/// Lints unit file content without saving it. Invalid content is still a successful
+/// request, the result says what's wrong.
+#[post("/unit-files/validate")]
+async fn validate_unit_file(
+  request: web::Json<ValidateUnitFileRequest>,
+) -> Result<impl Responder, ApiError> {
+  let unit_type = match &request.name {
+    Some(name) if !unit_file::is_valid_unit_name(name) => {
+      return Err(ApiError::Validation(format!("Invalid unit name {}", name)))
+    }
+    Some(name) => unit_file::unit_type(name),
+    None => None,
+  };
+
+  let validation = UnitFileValidationDto::from(unit_file::validate(&request.content, unit_type));
+
+  let serialized = serde_json::to_string(&validation).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+/// Runs a command in a transient unit, like `systemd-run`
+#[post("/run")]
+async fn run(
+  state: web::Data<AppState<'static>>,
+  request: web::Json<RunRequest>,
+) -> Result<impl Responder, ApiError> {
+  // Scopes wait for systemd to move the command, which mustn't hold up the worker
+  let request = request.into_inner();
+  let unit = web::block(move || transient::run(&state.dbus, &request))
+    .await
+    .map_err(|err| ApiError::Io(std::io::Error::other(err.to_string())))??;
+
+  let serialized = serde_json::to_string(&unit).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[derive(Deserialize)]
+struct TimersQuery {
+  /// Include inactive timers
+  #[serde(default)]
+  all: bool,
+}
+
+#[get("/timers")]
+async fn list_timers(
+  state: web::Data<AppState<'static>>,
+  query: Query<TimersQuery>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let timers = functions::list_timers(&dbus, query.all)?;
+
+  let serialized = serde_json::to_string(&timers).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+/// Upper limit for `depth`, graphs stop growing long before that
+const MAX_DEPENDENCY_DEPTH: usize = 64;
+
+#[derive(Deserialize)]
+struct DependenciesQuery {
+  #[serde(default)]
+  direction: DependencyDirection,
+
+  /// How many steps to walk away from the unit, 1 lists only direct dependencies
+  depth: Option<usize>,
+
+  /// Comma separated dependency types, i.e. `requires,wants,after`. Defaults to the ones
+  /// `systemctl list-dependencies` shows.
+  types: Option<String>,
+
+  /// "json" or "dot"
+  format: Option<String>,
+}
+
+#[get("/units/{name}/dependencies")]
+async fn unit_dependencies(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+  query: Query<DependenciesQuery>,
+) -> Result<impl Responder, ApiError> {
+  let depth = query.depth.unwrap_or(1);
+  if depth > MAX_DEPENDENCY_DEPTH {
+    return Err(ApiError::Validation(format!(
+      "depth can't be more than {}",
+      MAX_DEPENDENCY_DEPTH
+    )));
+  }
+
+  let types = match events::split_list(query.types.as_deref()).collect::<Vec<_>>() {
+    names if names.is_empty() => DependencyType::DEFAULT.to_vec(),
+    names => names
+      .into_iter()
+      .map(|name| {
+        DependencyType::parse(name).ok_or_else(|| {
+          let known: Vec<&str> = DependencyType::ALL.iter().map(|t| t.as_str()).collect();
+          ApiError::Validation(format!(
+            "Unknown dependency type {}, expected one of: {}",
+            name,
+            known.join(", ")
+          ))
+        })
+      })
+      .collect::<Result<_, _>>()?,
+  };
+
+  let dot = match query.format.as_deref() {
+    None | Some("json") => false,
+    Some("dot") => true,
+    Some(format) => {
+      return Err(ApiError::Validation(format!(
+        "Unknown format {}, expected json or dot",
+        format
+      )))
+    }
+  };
+
+  let dbus = state.dbus.lock().unwrap();
+  let graph = dependencies::dependency_graph(&dbus, &path, query.direction, &types, depth)?;
+
+  if dot {
+    return Ok(
+      HttpResponse::Ok()
+        .content_type("text/vnd.graphviz; charset=utf-8")
+        .body(dependencies::to_dot(&graph)),
+    );
+  }
+
+  let serialized = serde_json::to_string(&graph).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[get("/jobs")]
+async fn list_jobs(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let jobs = functions::list_jobs(&dbus)?;
+
+  let serialized = serde_json::to_string(&jobs).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[get("/jobs/{id}")]
+async fn get_job(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<u32>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let job = functions::get_job(&dbus, path.into_inner())?;
+
+  let serialized = serde_json::to_string(&job).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[delete("/jobs/{id}")]
+async fn cancel_job(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<u32>,
+) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  functions::cancel_job(&dbus, path.into_inner())?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+#[delete("/jobs")]
+async fn clear_jobs(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  functions::clear_jobs(&dbus)?;
+
+  Ok(HttpResponse::NoContent().finish())
+}
+
+#[derive(Deserialize)]
+struct EventsQuery {
+  /// Comma separated unit name globs, i.e. `app-*.service,nginx.service`
+  units: Option<String>,
+
+  /// Comma separated event types, i.e. `job-new,job-removed`
+  types: Option<String>,
+}
+
+/// Streams systemd signals as Server-Sent Events, event name is the event type
+#[get("/events")]
+async fn stream_events(
+  state: web::Data<AppState<'static>>,
+  query: Query<EventsQuery>,
+) -> Result<impl Responder, ApiError> {
+  let filter = events::EventFilter::parse(query.units.as_deref(), query.types.as_deref())
+    .map_err(ApiError::Validation)?;
+
+  let receiver = state.systemd_events.subscribe();
+  let (sender, response) = sse::channel();
+  actix_web::rt::spawn(events::stream_events(receiver, filter, sender));
+
+  Ok(response)
+}
+
+#[get("/analyze/time")]
+async fn analyze_time(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let times = analyze::boot_times(&dbus)?;
+
+  let serialized = serde_json::to_string(&times).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[get("/analyze/blame")]
+async fn analyze_blame(state: web::Data<AppState<'static>>) -> Result<impl Responder, ApiError> {
+  let dbus = state.dbus.lock().unwrap();
+  let entries = analyze::blame(&dbus)?;
+
+  let serialized = serde_json::to_string(&entries).unwrap_or("[]".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[derive(Deserialize)]
+struct CriticalChainQuery {
+  /// Also follow dependencies which activated up to this many microseconds before the last one
+  #[serde(default)]
+  fuzz: u64,
+}
+
+impl CriticalChainQuery {
+  fn fuzz(&self) -> Result<u64, ApiError> {
+    match self.fuzz > analyze::MAX_CHAIN_FUZZ_USEC {
+      true => Err(ApiError::Validation(format!(
+        "fuzz can't be more than {} microseconds",
+        analyze::MAX_CHAIN_FUZZ_USEC
+      ))),
+      false => Ok(self.fuzz),
+    }
+  }
+}
+
+#[get("/analyze/critical-chain")]
+async fn analyze_critical_chain(
+  state: web::Data<AppState<'static>>,
+  query: Query<CriticalChainQuery>,
+) -> Result<impl Responder, ApiError> {
+  let fuzz = query.fuzz()?;
+  let dbus = state.dbus.lock().unwrap();
+  let chain = analyze::critical_chain(&dbus, None, fuzz)?;
+
+  let serialized = serde_json::to_string(&chain).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
+
+#[get("/analyze/critical-chain/{unit}")]
+async fn analyze_unit_critical_chain(
+  state: web::Data<AppState<'static>>,
+  path: web::Path<String>,
+  query: Query<CriticalChainQuery>,
+) -> Result<impl Responder, ApiError> {
+  let fuzz = query.fuzz()?;
+  let dbus = state.dbus.lock().unwrap();
+  let chain = analyze::critical_chain(&dbus, Some(&path), fuzz)?;
+
+  let serialized = serde_json::to_string(&chain).unwrap_or("{}".to_owned());
+
+  Ok(
+    HttpResponse::Ok()
+      .append_header(ContentType::json())
+      .body(serialized),
+  )
+}
@@ -0,0 +1,450 @@
+//! Running commands in transient units, like `systemd-run` does
+
+use std::{
+  fs,
+  io::{self, Read, Write},
+  os::{
+    fd::AsRawFd,
+    unix::{fs::PermissionsExt, process::CommandExt},
+  },
+  path::Path,
+  process::{Child, Command, Stdio},
+  sync::Mutex,
+  thread,
+  time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use dbus::arg::{RefArg, Variant};
+
+use crate::{api_errors::ApiError, dbus_interface::DBusInterface};
+
+use super::{
+  dbus::manager::OrgFreedesktopSystemd1Manager,
+  dto::{JobDto, RunRequest, TransientKind, TransientUnitDto},
+  functions, unit_file,
+};
+
+/// Used to find programs when dragond's own PATH isn't set, and the only variable commands in
+/// scopes get besides the requested ones, same as systemd gives services
+const DEFAULT_PATH: &str = "/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin";
+
+/// How long to wait for systemd to move a command into its scope
+const SCOPE_TIMEOUT: Duration = Duration::from_secs(5);
+
+type Property = (&'static str, Variant<Box<dyn RefArg>>);
+
+/// Starts a transient unit running the command. Returns as soon as systemd accepted the
+/// start job. Blocks while a command is moved into its scope, but holds the D-Bus lock only
+/// while talking to systemd.
+pub fn run(
+  dbus: &Mutex<DBusInterface>,
+  request: &RunRequest,
+) -> Result<TransientUnitDto, ApiError> {
+  let (program, args) = match request.command.split_first() {
+    Some((program, args)) if !program.is_empty() => (program, args),
+    _ => return Err(ApiError::Validation("command can't be empty".to_owned())),
+  };
+  let program = find_program(program).map_err(ApiError::Validation)?;
+
+  let scope = request.kind == TransientKind::Scope;
+  if scope && request.on_calendar.is_some() {
+    return Err(ApiError::Validation(
+      "onCalendar can't be used with a scope, scopes can only run right away".to_owned(),
+    ));
+  }
+  if scope && (request.user.is_some() || request.group.is_some() || request.remain_after_exit) {
+    return Err(ApiError::Validation(
+      "user, group and remainAfterExit can only be used with a service".to_owned(),
+    ));
+  }
+
+  let base_name = base_name(request.unit.as_deref()).map_err(ApiError::Validation)?;
+  let environment = functions::environment_assignments(&request.environment)?;
+  let mut argv = vec![program.clone()];
+  argv.extend(args.iter().cloned());
+
+  let mut properties: Vec<Property> = vec![(
+    "Description",
+    variant(
+      request
+        .description
+        .clone()
+        .unwrap_or_else(|| format!("dragond run: {}", argv.join(" "))),
+    ),
+  )];
+  if let Some(memory_max) = &request.memory_max {
+    let bytes = parse_size(memory_max).map_err(ApiError::Validation)?;
+    properties.push(("MemoryMax", variant(bytes)));
+  }
+  if let Some(cpu_quota) = &request.cpu_quota {
+    let usec = parse_cpu_quota(cpu_quota).map_err(ApiError::Validation)?;
+    properties.push(("CPUQuotaPerSecUSec", variant(usec)));
+  }
+
+  let mode = request.mode.as_str();
+
+  if scope {
+    let unit = format!("{}.scope", base_name);
+    let (mut child, job) = spawn_in_scope(&program, args, request, &unit, |pid| {
+      properties.push(("PIDs", variant(vec![pid])));
+      let dbus = dbus.lock().unwrap();
+      let job = dbus
+        .systemd_manager()
+        .start_transient_unit(&unit, mode, properties, Vec::new())?;
+      Ok(job)
+    })?;
+    // Reaped from a separate thread, so it doesn't stay a zombie after exiting
+    thread::spawn(move || child.wait());
+
+    return Ok(TransientUnitDto {
+      job: JobDto::new(job, &unit, "start"),
+      command_unit: unit.clone(),
+      unit,
+    });
+  }
+
+  properties.push(("ExecStart", variant(vec![(program, argv, false)])));
+  if let Some(working_directory) = &request.working_directory {
+    properties.push(("WorkingDirectory", variant(working_directory.clone())));
+  }
+  if !environment.is_empty() {
+    properties.push(("Environment", variant(environment)));
+  }
+  if let Some(user) = &request.user {
+    properties.push(("User", variant(user.clone())));
+  }
+  if let Some(group) = &request.group {
+    properties.push(("Group", variant(group.clone())));
+  }
+  if request.remain_after_exit {
+    properties.push(("RemainAfterExit", variant(true)));
+  }
+
+  let service = format!("{}.service", base_name);
+  let dbus = dbus.lock().unwrap();
+  let manager = dbus.systemd_manager();
+  let (unit, job) = match &request.on_calendar {
+    Some(on_calendar) => {
+      let timer = format!("{}.timer", base_name);
+      let timer_properties: Vec<Property> = vec![
+        ("Description", variant(format!("Timer for {}", service))),
+        (
+          "TimersCalendar",
+          variant(vec![("OnCalendar".to_owned(), on_calendar.clone())]),
+        ),
+      ];
+
+      let job = manager.start_transient_unit(
+        &timer,
+        mode,
+        timer_properties,
+        vec![(service.as_str(), properties)],
+      )?;
+      (timer, job)
+    }
+    None => {
+      let job = manager.start_transient_unit(&service, mode, properties, Vec::new())?;
+      (service.clone(), job)
+    }
+  };
+
+  Ok(TransientUnitDto {
+    job: JobDto::new(job, &unit, "start"),
+    unit,
+    command_unit: service,
+  })
+}
+
+fn variant<T: RefArg + 'static>(value: T) -> Variant<Box<dyn RefArg>> {
+  Variant(Box::new(value))
+}
+
+/// Unit name without the type suffix. Generated names are unique enough, since systemd refuses
+/// to create a unit which already exists anyway.
+fn base_name(unit: Option<&str>) -> Result<String, String> {
+  let unit = match unit {
+    Some(unit) => unit,
+    None => {
+      let micros = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_micros())
+        .unwrap_or_default();
+      return Ok(format!("run-dragond-{}", micros));
+    }
+  };
+
+  let base = match unit_file::unit_type(unit) {
+    Some("service" | "scope" | "timer") => &unit[..unit.rfind('.').unwrap_or(unit.len())],
+    Some(unit_type) => {
+      return Err(format!(
+        "Transient units can't be of type {}, expected service, scope or timer",
+        unit_type
+      ))
+    }
+    None => unit,
+  };
+
+  match unit_file::is_valid_unit_name(&format!("{}.service", base)) && !base.contains('@') {
+    true => Ok(base.to_owned()),
+    false => Err(format!("Invalid unit name {}", unit)),
+  }
+}
+
+/// Absolute path of a program, looked up in PATH if it's just a name
+fn find_program(program: &str) -> Result<String, String> {
+  let is_executable = |path: &Path| {
+    path
+      .metadata()
+      .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+  };
+
+  if program.contains('/') {
+    return match Path::new(program).is_absolute() {
+      true => Ok(program.to_owned()),
+      false => Err(format!("Program path {} must be absolute", program)),
+    };
+  }
+
+  let path = std::env::var("PATH").unwrap_or_else(|_| DEFAULT_PATH.to_owned());
+  path
+    .split(':')
+    .map(|directory| Path::new(directory).join(program))
+    .find(|candidate| is_executable(candidate))
+    .map(|candidate| candidate.to_string_lossy().into_owned())
+    .ok_or_else(|| format!("Program {} not found in {}", program, path))
+}
+
+/// Starts the command as a child of dragond, which `start` puts into the scope. The child
+/// waits before executing the command until it's in the scope, so the command never runs
+/// outside of it, and exits without running it if the scope couldn't be started.
+fn spawn_in_scope<T>(
+  program: &str,
+  args: &[String],
+  request: &RunRequest,
+  unit: &str,
+  start: impl FnOnce(u32) -> Result<T, ApiError>,
+) -> Result<(Child, T), ApiError> {
+  let (mut pid_reader, pid_writer) = io::pipe()?;
+  let (start_reader, mut start_writer) = io::pipe()?;
+  let (pid_fd, start_fd, start_writer_fd) = (
+    pid_writer.as_raw_fd(),
+    start_reader.as_raw_fd(),
+    start_writer.as_raw_fd(),
+  );
+
+  let mut command = Command::new(program);
+  command
+    .args(args)
+    .env_clear()
+    .env("PATH", DEFAULT_PATH)
+    .envs(&request.environment)
+    .stdin(Stdio::null())
+    .stdout(Stdio::null())
+    .stderr(Stdio::null());
+  if let Some(working_directory) = &request.working_directory {
+    command.current_dir(working_directory);
+  }
+
+  // SAFETY: runs in the forked child, only calls async-signal-safe functions on file
+  // descriptors which stay open until the child execs, see below
+  unsafe {
+    command.pre_exec(move || {
+      // Otherwise the child would keep the pipe open and never see dragond giving up
+      libc::close(start_writer_fd);
+
+      let pid = libc::getpid().to_ne_bytes();
+      if libc::write(pid_fd, pid.as_ptr().cast(), pid.len()) != pid.len() as isize {
+        return Err(io::Error::last_os_error());
+      }
+
+      let mut byte = 0u8;
+      loop {
+        match libc::read(start_fd, (&mut byte as *mut u8).cast(), 1) {
+          1 => return Ok(()),
+          -1 if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted => continue,
+          _ => return Err(io::Error::from_raw_os_error(libc::ECANCELED)),
+        }
+      }
+    });
+  }
+
+  // Spawning returns only once the child executed the command, which it doesn't do before
+  // being let go below. The pipe ends the child uses are closed once spawning is done.
+  let spawner = thread::spawn(move || {
+    let child = command.spawn();
+    drop((pid_writer, start_reader));
+    child
+  });
+  let spawned = || match spawner.join() {
+    Ok(child) => child.map_err(ApiError::from),
+    Err(_) => Err(ApiError::Io(io::Error::other("Spawning thread panicked"))),
+  };
+
+  let mut pid = [0u8; 4];
+  if pid_reader.read_exact(&mut pid).is_err() {
+    // The child didn't get to run, the spawning error tells why
+    spawned()?;
+    return Err(ApiError::Io(io::Error::other("Command exited too early")));
+  }
+  let pid = u32::from_ne_bytes(pid);
+
+  // Dropping the pipe without writing to it makes the child exit
+  let started = start(pid).and_then(|value| {
+    wait_for_cgroup(pid, unit)?;
+    Ok(value)
+  });
+  let value = match started {
+    Ok(value) => value,
+    Err(err) => {
+      drop(start_writer);
+      let _ = spawned();
+      return Err(err);
+    }
+  };
+
+  start_writer.write_all(&[1])?;
+  drop(start_writer);
+  Ok((spawned()?, value))
+}
+
+/// Waits until systemd moved the process into the cgroup of the unit. Starting a scope only
+/// queues a job, the process is moved when it runs.
+fn wait_for_cgroup(pid: u32, unit: &str) -> Result<(), ApiError> {
+  let suffix = format!("/{}", unit);
+  let deadline = Instant::now() + SCOPE_TIMEOUT;
+
+  loop {
+    let cgroups = fs::read_to_string(format!("/proc/{}/cgroup", pid))?;
+    if cgroups.lines().any(|line| line.ends_with(&suffix)) {
+      return Ok(());
+    }
+    if Instant::now() > deadline {
+      return Err(ApiError::Io(io::Error::other(format!(
+        "Command wasn't moved into {} in time",
+        unit
+      ))));
+    }
+    thread::sleep(Duration::from_millis(10));
+  }
+}
+
+/// Bytes from a size like "512M", suffixes are base 1024 like in systemd
+fn parse_size(size: &str) -> Result<u64, String> {
+  let size = size.trim();
+  if size == "infinity" {
+    return Ok(u64::MAX);
+  }
+
+  let (number, multiplier) = match size.char_indices().last() {
+    Some((index, suffix)) if suffix.is_ascii_alphabetic() => {
+      let exponent = match suffix.to_ascii_uppercase() {
+        'K' => 1,
+        'M' => 2,
+        'G' => 3,
+        'T' => 4,
+        _ => return Err(format!("Invalid size suffix {} in {}", suffix, size)),
+      };
+      (&size[..index], 1024u64.pow(exponent))
+    }
+    _ => (size, 1),
+  };
+
+  number
+    .parse::<u64>()
+    .ok()
+    .and_then(|number| number.checked_mul(multiplier))
+    .ok_or_else(|| format!("Invalid size {}", size))
+}
+
+/// CPU time per second of wall clock time, in microseconds, from a percentage like "50%"
+fn parse_cpu_quota(quota: &str) -> Result<u64, String> {
+  quota
+    .trim()
+    .strip_suffix('%')
+    .and_then(|percent| percent.parse::<u64>().ok())
+    .filter(|percent| *percent > 0)
+    .map(|percent| percent * 10_000)
+    .ok_or_else(|| format!("Invalid CPU quota {}, expected percentage like 50%", quota))
+}
+
+#[cfg(test)]
+mod tests {
+  use std::{env, path::PathBuf};
+
+  use super::*;
+
+  fn request(command: &[&str]) -> RunRequest {
+    serde_json::from_value(serde_json::json!({
+      "command": command,
+      "environment": { "FIXTURE": "value" },
+    }))
+    .unwrap()
+  }
+
+  /// Stands in for the scope, the child already is in the cgroup of the tests
+  fn own_cgroup() -> String {
+    let cgroups = fs::read_to_string("/proc/self/cgroup").unwrap();
+    let path = cgroups.lines().last().unwrap().rsplit(':').next().unwrap();
+    path.rsplit('/').next().unwrap().to_owned()
+  }
+
+  fn output_file(name: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("dragond-{}-{}", name, std::process::id()));
+    let _ = fs::remove_file(&path);
+    path
+  }
+
+  #[test]
+  fn runs_command_with_minimal_environment_once_in_scope() {
+    let output = output_file("scope-env");
+    let script = format!("env > {}", output.display());
+    let request = request(&["/bin/sh", "-c", &script]);
+
+    let (mut child, pid) = spawn_in_scope(
+      "/bin/sh",
+      &request.command[1..],
+      &request,
+      &own_cgroup(),
+      Ok,
+    )
+    .unwrap();
+    assert_eq!(child.id(), pid);
+    assert!(child.wait().unwrap().success());
+
+    let environment = fs::read_to_string(&output).unwrap();
+    fs::remove_file(&output).unwrap();
+    assert!(environment.contains("FIXTURE=value\n"));
+    assert!(environment.contains(&format!("PATH={}\n", DEFAULT_PATH)));
+    assert!(!environment.contains("CARGO"));
+  }
+
+  #[test]
+  fn doesnt_run_command_if_scope_fails() {
+    let output = output_file("scope-failed");
+    let request = request(&["/bin/touch", output.to_str().unwrap()]);
+
+    let result = spawn_in_scope(
+      "/bin/touch",
+      &request.command[1..],
+      &request,
+      &own_cgroup(),
+      |_| Err::<(), _>(ApiError::Validation("no scope".to_owned())),
+    );
+    assert!(matches!(result, Err(ApiError::Validation(_))));
+    thread::sleep(Duration::from_millis(50));
+    assert!(!output.exists());
+  }
+
+  #[test]
+  fn parses_sizes_and_quotas() {
+    assert_eq!(parse_size("512"), Ok(512));
+    assert_eq!(parse_size("2K"), Ok(2048));
+    assert_eq!(parse_size("1g"), Ok(1 << 30));
+    assert_eq!(parse_size("infinity"), Ok(u64::MAX));
+    assert!(parse_size("1P").is_err());
+    assert!(parse_size("99999999999T").is_err());
+    assert_eq!(parse_cpu_quota("50%"), Ok(500_000));
+    assert!(parse_cpu_quota("0%").is_err());
+    assert!(parse_cpu_quota("50").is_err());
+  }
+}
@@ -0,0 +1,500 @@
+//! Parser for the INI-like syntax of unit files, see `man systemd.syntax`
+
+use serde::Serialize;
+
+use super::unit_keys;
+
+/// Suffixes of unit types systemd knows
+pub const UNIT_TYPES: [&str; 11] = [
+  "service",
+  "socket",
+  "target",
+  "device",
+  "mount",
+  "automount",
+  "swap",
+  "timer",
+  "path",
+  "slice",
+  "scope",
+];
+
+/// Characters allowed after `%`, see "SPECIFIERS" in `man systemd.unit`
+const SPECIFIERS: &str = "aAbBCdEfgGhHiIjJlLmMnNopPqsStTuUvVwWyY%";
+
+#[derive(Serialize)]
+pub struct UnitFile {
+  pub sections: Vec<Section>,
+}
+
+/// A section header with assignments following it. A section can be present more than once,
+/// its assignments add up.
+#[derive(Serialize)]
+pub struct Section {
+  pub name: String,
+
+  /// Line of the section header, starting with 1
+  pub line: usize,
+  pub entries: Vec<Entry>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Entry {
+  pub key: String,
+
+  /// Value with continuation lines joined and specifiers left as they are
+  pub value: String,
+
+  /// Where the key starts, starting with 1
+  pub line: usize,
+  pub column: usize,
+
+  /// Empty assignment, which removes everything assigned to the key before it
+  pub reset: bool,
+
+  /// Specifiers used in the value, i.e. `%n`. `%%` isn't listed, it's an escaped `%`.
+  pub specifiers: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+  /// Starting with 1
+  pub line: usize,
+
+  /// Starting with 1
+  pub column: usize,
+  pub message: String,
+}
+
+impl std::fmt::Display for Diagnostic {
+  fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    write!(
+      f,
+      "line {}, column {}: {}",
+      self.line, self.column, self.message
+    )
+  }
+}
+
+/// Result of `validate`. Errors make systemd refuse or misread the file, warnings are
+/// settings it ignores.
+pub struct Validation {
+  pub file: UnitFile,
+
+  /// Given or guessed unit type, `None` if it couldn't be guessed
+  pub unit_type: Option<&'static str>,
+  pub errors: Vec<Diagnostic>,
+  pub warnings: Vec<Diagnostic>,
+}
+
+/// A line with continuation lines joined, remembering where each part came from
+struct LogicalLine<'a> {
+  text: String,
+
+  /// Offset in `text`, line number, the original line and offset in it, for each joined part
+  parts: Vec<(usize, usize, &'a str, usize)>,
+}
+
+impl LogicalLine<'_> {
+  /// Line and column of a byte offset in `text`
+  fn position(&self, offset: usize) -> (usize, usize) {
+    let (start, line, raw, raw_offset) = self
+      .parts
+      .iter()
+      .rev()
+      .find(|(start, ..)| *start <= offset)
+      .copied()
+      .unwrap_or((0, 1, "", 0));
+
+    let byte = (raw_offset + offset - start).min(raw.len());
+    let column = raw.get(..byte).map_or(byte, |text| text.chars().count());
+    (line, column + 1)
+  }
+
+  fn diagnostic(&self, offset: usize, message: String) -> Diagnostic {
+    let (line, column) = self.position(offset);
+    Diagnostic {
+      line,
+      column,
+      message,
+    }
+  }
+}
+
+/// Parses unit file content. Lines which can't be parsed are skipped and reported, so the
+/// result holds everything that could be read.
+pub fn parse(content: &str) -> (UnitFile, Vec<Diagnostic>) {
+  let mut sections: Vec<Section> = Vec::new();
+  let mut errors = Vec::new();
+
+  for line in logical_lines(content) {
+    let text = line.text.trim_end();
+    let start = text.len() - text.trim_start().len();
+    let text = text.trim_start();
+
+    if text.starts_with('[') {
+      match text.strip_suffix(']').map(|name| &name[1..]) {
+        Some(name) if !name.is_empty() && !name.contains(['[', ']']) => sections.push(Section {
+          name: name.to_owned(),
+          line: line.position(start).0,
+          entries: Vec::new(),
+        }),
+        _ => errors.push(line.diagnostic(start, "Invalid section header".to_owned())),
+      }
+      continue;
+    }
+
+    let (key, value) = match text.split_once('=') {
+      Some((key, value)) => (key.trim_end(), value.trim_start()),
+      None => {
+        errors.push(line.diagnostic(start, "Missing '=', expected Key=value".to_owned()));
+        continue;
+      }
+    };
+
+    if key.is_empty() {
+      errors.push(line.diagnostic(start, "Missing key before '='".to_owned()));
+      continue;
+    }
+    if key.contains(char::is_whitespace) {
+      errors.push(line.diagnostic(start, format!("Invalid key \"{}\"", key)));
+      continue;
+    }
+
+    let section = match sections.last_mut() {
+      Some(section) => section,
+      None => {
+        errors.push(line.diagnostic(start, "Assignment outside of a section".to_owned()));
+        continue;
+      }
+    };
+
+    let value_start = start + (text.len() - value.len());
+    let mut specifiers: Vec<String> = Vec::new();
+    let mut chars = value.char_indices();
+    while let Some((index, c)) = chars.next() {
+      if c != '%' {
+        continue;
+      }
+
+      match chars.next() {
+        Some((_, '%')) => {}
+        Some((_, specifier)) if SPECIFIERS.contains(specifier) => {
+          let specifier = format!("%{}", specifier);
+          if !specifiers.contains(&specifier) {
+            specifiers.push(specifier);
+          }
+        }
+        Some((_, specifier)) => errors.push(line.diagnostic(
+          value_start + index,
+          format!("Unknown specifier %{}, use %% for a literal %", specifier),
+        )),
+        None => errors.push(line.diagnostic(
+          value_start + index,
+          "Value ends with an incomplete specifier, use %% for a literal %".to_owned(),
+        )),
+      }
+    }
+
+    let (line_number, column) = line.position(start);
+    section.entries.push(Entry {
+      key: key.to_owned(),
+      value: value.to_owned(),
+      line: line_number,
+      column,
+      reset: value.is_empty(),
+      specifiers,
+    });
+  }
+
+  (UnitFile { sections }, errors)
+}
+
+/// Parses unit file content and checks sections and keys against what systemd knows for the
+/// unit type. Without a unit type, it's guessed from the sections.
+pub fn validate(content: &str, unit_type: Option<&'static str>) -> Validation {
+  let (file, errors) = parse(content);
+  let mut warnings = Vec::new();
+
+  let unit_type = unit_type.or_else(|| {
+    file
+      .sections
+      .iter()
+      .find_map(|section| unit_keys::type_of_section(&section.name))
+  });
+
+  for section in &file.sections {
+    // Sections and keys starting with X- are left for other programs
+    if section.name.starts_with("X-") {
+      continue;
+    }
+
+    // Without a type, only sections all unit types have can be checked
+    let unit_type = match (unit_type, section.name.as_str()) {
+      (Some(unit_type), _) => unit_type,
+      (None, "Unit" | "Install") => "",
+      (None, _) => {
+        warnings.push(Diagnostic {
+          line: section.line,
+          column: 1,
+          message: format!("Unknown section [{}]", section.name),
+        });
+        continue;
+      }
+    };
+
+    if !unit_keys::is_known_section(unit_type, &section.name) {
+      warnings.push(Diagnostic {
+        line: section.line,
+        column: 1,
+        message: format!(
+          "Section [{}] isn't used by {} units, it will be ignored",
+          section.name, unit_type
+        ),
+      });
+      continue;
+    }
+
+    for entry in &section.entries {
+      if entry.key.starts_with("X-")
+        || unit_keys::is_known_key(unit_type, &section.name, &entry.key)
+      {
+        continue;
+      }
+
+      warnings.push(Diagnostic {
+        line: entry.line,
+        column: entry.column,
+        message: format!(
+          "Unknown key {} in section [{}], it will be ignored",
+          entry.key, section.name
+        ),
+      });
+    }
+  }
+
+  Validation {
+    file,
+    unit_type,
+    errors,
+    warnings,
+  }
+}
+
+/// Joins lines ending with a backslash with the lines after them and drops comments and
+/// empty lines. Comments between continued lines are skipped.
+fn logical_lines(content: &str) -> Vec<LogicalLine<'_>> {
+  let mut lines = Vec::new();
+  let mut current: Option<LogicalLine> = None;
+
+  for (index, raw) in content.lines().enumerate() {
+    let trimmed = raw.trim_start();
+    let is_comment = trimmed.starts_with('#') || trimmed.starts_with(';');
+
+    let mut line = match current.take() {
+      Some(line) if is_comment => {
+        current = Some(line);
+        continue;
+      }
+      Some(mut line) => {
+        let offset = raw.len() - trimmed.len();
+        line.parts.push((line.text.len(), index + 1, raw, offset));
+        line.text.push_str(trimmed);
+        line
+      }
+      None if is_comment || trimmed.is_empty() => continue,
+      None => LogicalLine {
+        text: raw.to_owned(),
+        parts: vec![(0, index + 1, raw, 0)],
+      },
+    };
+
+    match line.text.trim_end().strip_suffix('\\') {
+      Some(text) => {
+        let length = text.len();
+        line.text.truncate(length);
+        line.text.push(' ');
+        current = Some(line);
+      }
+      None => lines.push(line),
+    }
+  }
+
+  lines.extend(current);
+  lines
+}
+
+/// Type of a unit, from its name suffix
+pub fn unit_type(name: &str) -> Option<&'static str> {
+  let suffix = name.rsplit_once('.')?.1;
+  UNIT_TYPES
+    .into_iter()
+    .find(|unit_type| *unit_type == suffix)
+}
+
+/// Valid unit name with a known type suffix, i.e. `sshd.service` or `getty@tty1.service`
+pub fn is_valid_unit_name(name: &str) -> bool {
+  let (prefix, suffix) = match name.rsplit_once('.') {
+    Some(parts) => parts,
+    None => return false,
+  };
+
+  name.len() <= 255
+    && UNIT_TYPES.contains(&suffix)
+    && !prefix.is_empty()
+    && prefix.matches('@').count() <= 1
+    && !prefix.starts_with('@')
+    && prefix
+      .chars()
+      .all(|c| c.is_ascii_alphanumeric() || ":-_.\\@".contains(c))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn positions(diagnostics: &[Diagnostic]) -> Vec<(usize, usize)> {
+    diagnostics
+      .iter()
+      .map(|diagnostic| (diagnostic.line, diagnostic.column))
+      .collect()
+  }
+
+  #[test]
+  fn parses_valid_unit_file() {
+    let content = "\
+# Comment
+[Unit]
+Description=Test %n
+
+[Service]
+  ExecStart=/bin/true\\
+    --flag
+; another comment
+Environment=
+";
+    let (file, errors) = parse(content);
+    assert!(errors.is_empty());
+
+    let sections: Vec<(&str, usize)> = file
+      .sections
+      .iter()
+      .map(|section| (section.name.as_str(), section.line))
+      .collect();
+    assert_eq!(sections, vec![("Unit", 2), ("Service", 5)]);
+
+    let description = &file.sections[0].entries[0];
+    assert_eq!(description.value, "Test %n");
+    assert_eq!(description.specifiers, vec!["%n"]);
+    assert_eq!((description.line, description.column), (3, 1));
+
+    let exec_start = &file.sections[1].entries[0];
+    assert_eq!(exec_start.value, "/bin/true --flag");
+    assert_eq!((exec_start.line, exec_start.column), (6, 3));
+
+    let environment = &file.sections[1].entries[1];
+    assert!(environment.reset);
+    assert_eq!(environment.line, 9);
+  }
+
+  #[test]
+  fn reports_syntax_errors_where_they_are() {
+    let content = "\
+Description=outside
+[Unit
+[Unit]
+NoEquals
+  =value
+Two Words=value
+Description=100% done
+Description=ends with %
+";
+    let (file, errors) = parse(content);
+    assert_eq!(
+      positions(&errors),
+      vec![(1, 1), (2, 1), (4, 1), (5, 3), (6, 1), (7, 16), (8, 23)]
+    );
+    assert!(errors[0].message.contains("outside of a section"));
+    assert!(errors[1].message.contains("section header"));
+    assert!(errors[2].message.contains("Missing '='"));
+    assert!(errors[5].message.contains("%"));
+    // Lines with bad specifiers are still read
+    assert_eq!(file.sections[0].entries.len(), 2);
+  }
+
+  #[test]
+  fn reports_errors_in_continuation_lines() {
+    let content = "[Service]\nExecStart=/bin/echo \\\n  50%x\n";
+    let (_, errors) = parse(content);
+    assert_eq!(positions(&errors), vec![(3, 5)]);
+  }
+
+  #[test]
+  fn counts_columns_in_characters() {
+    let (_, errors) = parse("[Unit]\nDescription=żółć %x\n");
+    assert_eq!(positions(&errors), vec![(2, 18)]);
+  }
+
+  #[test]
+  fn warns_about_unknown_sections_and_keys() {
+    let content = "\
+[Unit]
+Description=test
+ConditionPathExists=/etc
+Colour=blue
+[Service]
+ExecStart=/bin/true
+ListenStream=80
+X-Custom=yes
+[Timer]
+OnCalendar=daily
+[X-Vendor]
+Anything=goes
+";
+    let validation = validate(content, Some("service"));
+    assert!(validation.errors.is_empty());
+    assert_eq!(
+      positions(&validation.warnings),
+      vec![(4, 1), (7, 1), (9, 1)]
+    );
+    assert!(validation.warnings[2].message.contains("[Timer]"));
+  }
+
+  #[test]
+  fn guesses_unit_type_from_sections() {
+    let validation = validate("[Unit]\nDescription=x\n[Socket]\nListenStream=80\n", None);
+    assert_eq!(validation.unit_type, Some("socket"));
+    assert!(validation.warnings.is_empty());
+
+    let validation = validate("[Unit]\nDescription=x\n[Whatever]\nKey=value\n", None);
+    assert_eq!(validation.unit_type, None);
+    assert_eq!(positions(&validation.warnings), vec![(3, 1)]);
+  }
+
+  #[test]
+  fn checks_unit_names() {
+    for name in [
+      "sshd.service",
+      "getty@tty1.service",
+      "getty@.service",
+      "a-b_c:d.mount",
+    ] {
+      assert!(is_valid_unit_name(name), "{}", name);
+    }
+    for name in [
+      "sshd",
+      ".service",
+      "sshd.unknown",
+      "@tty1.service",
+      "a@b@c.service",
+      "../etc.service",
+      "with space.service",
+    ] {
+      assert!(!is_valid_unit_name(name), "{}", name);
+    }
+    assert_eq!(unit_type("getty@tty1.service"), Some("service"));
+    assert_eq!(unit_type("sshd"), None);
+  }
+}
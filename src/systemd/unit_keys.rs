@@ -0,0 +1,716 @@
+//! Settings systemd understands in each section of each unit type, see `man systemd.directives`
+
+/// `[Unit]` section, shared by all unit types. Conditions and asserts are matched separately.
+const UNIT: &[&str] = &[
+  "Description",
+  "Documentation",
+  "Wants",
+  "Requires",
+  "Requisite",
+  "BindsTo",
+  "PartOf",
+  "Upholds",
+  "Conflicts",
+  "Before",
+  "After",
+  "OnFailure",
+  "OnSuccess",
+  "PropagatesReloadTo",
+  "ReloadPropagatedFrom",
+  "PropagatesStopTo",
+  "StopPropagatedFrom",
+  "JoinsNamespaceOf",
+  "RequiresMountsFor",
+  "WantsMountsFor",
+  "OnFailureJobMode",
+  "IgnoreOnIsolate",
+  "StopWhenUnneeded",
+  "RefuseManualStart",
+  "RefuseManualStop",
+  "AllowIsolate",
+  "DefaultDependencies",
+  "SurviveFinalKillSignal",
+  "CollectMode",
+  "FailureAction",
+  "SuccessAction",
+  "FailureActionExitStatus",
+  "SuccessActionExitStatus",
+  "JobTimeoutSec",
+  "JobRunningTimeoutSec",
+  "JobTimeoutAction",
+  "JobTimeoutRebootArgument",
+  "StartLimitIntervalSec",
+  "StartLimitBurst",
+  "StartLimitAction",
+  "RebootArgument",
+  "SourcePath",
+];
+
+/// Checked after `Condition` or `Assert` prefix
+const CONDITIONS: &[&str] = &[
+  "Architecture",
+  "Firmware",
+  "Virtualization",
+  "Host",
+  "KernelCommandLine",
+  "KernelVersion",
+  "Credential",
+  "Environment",
+  "Security",
+  "Capability",
+  "ACPower",
+  "NeedsUpdate",
+  "FirstBoot",
+  "PathExists",
+  "PathExistsGlob",
+  "PathIsDirectory",
+  "PathIsSymbolicLink",
+  "PathIsMountPoint",
+  "PathIsReadWrite",
+  "PathIsEncrypted",
+  "DirectoryNotEmpty",
+  "FileNotEmpty",
+  "FileIsExecutable",
+  "User",
+  "Group",
+  "ControlGroupController",
+  "Memory",
+  "CPUs",
+  "CPUFeature",
+  "OSRelease",
+  "MemoryPressure",
+  "CPUPressure",
+  "IOPressure",
+];
+
+const INSTALL: &[&str] = &[
+  "Alias",
+  "WantedBy",
+  "RequiredBy",
+  "UpheldBy",
+  "Also",
+  "DefaultInstance",
+];
+
+/// `man systemd.exec`, for units which spawn processes
+const EXEC: &[&str] = &[
+  "WorkingDirectory",
+  "RootDirectory",
+  "RootImage",
+  "RootImageOptions",
+  "RootEphemeral",
+  "RootHash",
+  "RootHashSignature",
+  "RootVerity",
+  "RootImagePolicy",
+  "MountImagePolicy",
+  "ExtensionImagePolicy",
+  "MountAPIVFS",
+  "ProtectProc",
+  "ProcSubset",
+  "BindPaths",
+  "BindReadOnlyPaths",
+  "MountImages",
+  "ExtensionImages",
+  "ExtensionDirectories",
+  "User",
+  "Group",
+  "DynamicUser",
+  "SupplementaryGroups",
+  "SetLoginEnvironment",
+  "PAMName",
+  "CapabilityBoundingSet",
+  "AmbientCapabilities",
+  "NoNewPrivileges",
+  "SecureBits",
+  "SELinuxContext",
+  "AppArmorProfile",
+  "SmackProcessLabel",
+  "LimitCPU",
+  "LimitFSIZE",
+  "LimitDATA",
+  "LimitSTACK",
+  "LimitCORE",
+  "LimitRSS",
+  "LimitNOFILE",
+  "LimitAS",
+  "LimitNPROC",
+  "LimitMEMLOCK",
+  "LimitLOCKS",
+  "LimitSIGPENDING",
+  "LimitMSGQUEUE",
+  "LimitNICE",
+  "LimitRTPRIO",
+  "LimitRTTIME",
+  "UMask",
+  "CoredumpFilter",
+  "KeyringMode",
+  "OOMScoreAdjust",
+  "TimerSlackNSec",
+  "Personality",
+  "IgnoreSIGPIPE",
+  "Nice",
+  "CPUSchedulingPolicy",
+  "CPUSchedulingPriority",
+  "CPUSchedulingResetOnFork",
+  "CPUAffinity",
+  "NUMAPolicy",
+  "NUMAMask",
+  "IOSchedulingClass",
+  "IOSchedulingPriority",
+  "ProtectSystem",
+  "ProtectHome",
+  "RuntimeDirectory",
+  "StateDirectory",
+  "CacheDirectory",
+  "LogsDirectory",
+  "ConfigurationDirectory",
+  "RuntimeDirectoryMode",
+  "StateDirectoryMode",
+  "CacheDirectoryMode",
+  "LogsDirectoryMode",
+  "ConfigurationDirectoryMode",
+  "RuntimeDirectoryPreserve",
+  "TimeoutCleanSec",
+  "ReadWritePaths",
+  "ReadOnlyPaths",
+  "InaccessiblePaths",
+  "ExecPaths",
+  "NoExecPaths",
+  "TemporaryFileSystem",
+  "PrivateTmp",
+  "PrivateDevices",
+  "PrivateNetwork",
+  "NetworkNamespacePath",
+  "PrivateIPC",
+  "IPCNamespacePath",
+  "MemoryKSM",
+  "PrivateUsers",
+  "ProtectHostname",
+  "ProtectClock",
+  "ProtectKernelTunables",
+  "ProtectKernelModules",
+  "ProtectKernelLogs",
+  "ProtectControlGroups",
+  "RestrictAddressFamilies",
+  "RestrictFileSystems",
+  "RestrictNamespaces",
+  "LockPersonality",
+  "MemoryDenyWriteExecute",
+  "RestrictRealtime",
+  "RestrictSUIDSGID",
+  "RemoveIPC",
+  "PrivateMounts",
+  "MountFlags",
+  "SystemCallFilter",
+  "SystemCallErrorNumber",
+  "SystemCallArchitectures",
+  "SystemCallLog",
+  "Environment",
+  "EnvironmentFile",
+  "PassEnvironment",
+  "UnsetEnvironment",
+  "StandardInput",
+  "StandardOutput",
+  "StandardError",
+  "StandardInputText",
+  "StandardInputData",
+  "LogLevelMax",
+  "LogExtraFields",
+  "LogRateLimitIntervalSec",
+  "LogRateLimitBurst",
+  "LogFilterPatterns",
+  "LogNamespace",
+  "SyslogIdentifier",
+  "SyslogFacility",
+  "SyslogLevel",
+  "SyslogLevelPrefix",
+  "TTYPath",
+  "TTYReset",
+  "TTYVHangup",
+  "TTYRows",
+  "TTYColumns",
+  "TTYVTDisallocate",
+  "LoadCredential",
+  "LoadCredentialEncrypted",
+  "ImportCredential",
+  "SetCredential",
+  "SetCredentialEncrypted",
+  "UtmpIdentifier",
+  "UtmpMode",
+];
+
+/// `man systemd.kill`
+const KILL: &[&str] = &[
+  "KillMode",
+  "KillSignal",
+  "RestartKillSignal",
+  "SendSIGHUP",
+  "SendSIGKILL",
+  "FinalKillSignal",
+  "WatchdogSignal",
+];
+
+/// `man systemd.resource-control`, deprecated settings included since systemd still reads them
+const RESOURCE_CONTROL: &[&str] = &[
+  "CPUAccounting",
+  "CPUWeight",
+  "StartupCPUWeight",
+  "CPUQuota",
+  "CPUQuotaPeriodSec",
+  "AllowedCPUs",
+  "StartupAllowedCPUs",
+  "AllowedMemoryNodes",
+  "StartupAllowedMemoryNodes",
+  "MemoryAccounting",
+  "MemoryMin",
+  "MemoryLow",
+  "StartupMemoryLow",
+  "DefaultStartupMemoryLow",
+  "MemoryHigh",
+  "StartupMemoryHigh",
+  "MemoryMax",
+  "StartupMemoryMax",
+  "MemorySwapMax",
+  "StartupMemorySwapMax",
+  "MemoryZSwapMax",
+  "StartupMemoryZSwapMax",
+  "MemoryZSwapWriteback",
+  "TasksAccounting",
+  "TasksMax",
+  "IOAccounting",
+  "IOWeight",
+  "StartupIOWeight",
+  "IODeviceWeight",
+  "IOReadBandwidthMax",
+  "IOWriteBandwidthMax",
+  "IOReadIOPSMax",
+  "IOWriteIOPSMax",
+  "IODeviceLatencyTargetSec",
+  "IPAccounting",
+  "IPAddressAllow",
+  "IPAddressDeny",
+  "SocketBindAllow",
+  "SocketBindDeny",
+  "RestrictNetworkInterfaces",
+  "NFTSet",
+  "IPIngressFilterPath",
+  "IPEgressFilterPath",
+  "BPFProgram",
+  "DeviceAllow",
+  "DevicePolicy",
+  "Slice",
+  "Delegate",
+  "DelegateSubgroup",
+  "DisableControllers",
+  "ManagedOOMSwap",
+  "ManagedOOMMemoryPressure",
+  "ManagedOOMMemoryPressureLimit",
+  "ManagedOOMMemoryPressureDurationSec",
+  "ManagedOOMPreference",
+  "MemoryPressureWatch",
+  "MemoryPressureThresholdSec",
+  "CoredumpReceive",
+  "CPUShares",
+  "StartupCPUShares",
+  "MemoryLimit",
+  "BlockIOAccounting",
+  "BlockIOWeight",
+  "StartupBlockIOWeight",
+  "BlockIODeviceWeight",
+  "BlockIOReadBandwidth",
+  "BlockIOWriteBandwidth",
+];
+
+const SERVICE: &[&str] = &[
+  "Type",
+  "ExitType",
+  "RemainAfterExit",
+  "GuessMainPID",
+  "PIDFile",
+  "BusName",
+  "ExecStart",
+  "ExecStartPre",
+  "ExecStartPost",
+  "ExecCondition",
+  "ExecReload",
+  "ExecStop",
+  "ExecStopPost",
+  "RestartSec",
+  "RestartSteps",
+  "RestartMaxDelaySec",
+  "TimeoutStartSec",
+  "TimeoutStopSec",
+  "TimeoutAbortSec",
+  "TimeoutSec",
+  "TimeoutStartFailureMode",
+  "TimeoutStopFailureMode",
+  "RuntimeMaxSec",
+  "RuntimeRandomizedExtraSec",
+  "WatchdogSec",
+  "Restart",
+  "RestartMode",
+  "SuccessExitStatus",
+  "RestartPreventExitStatus",
+  "RestartForceExitStatus",
+  "RootDirectoryStartOnly",
+  "NonBlocking",
+  "NotifyAccess",
+  "Sockets",
+  "FileDescriptorStoreMax",
+  "FileDescriptorStorePreserve",
+  "USBFunctionDescriptors",
+  "USBFunctionStrings",
+  "OOMPolicy",
+  "OpenFile",
+  "ReloadSignal",
+];
+
+const SOCKET: &[&str] = &[
+  "ListenStream",
+  "ListenDatagram",
+  "ListenSequentialPacket",
+  "ListenFIFO",
+  "ListenSpecial",
+  "ListenNetlink",
+  "ListenMessageQueue",
+  "ListenUSBFunction",
+  "SocketProtocol",
+  "BindIPv6Only",
+  "Backlog",
+  "BindToDevice",
+  "SocketUser",
+  "SocketGroup",
+  "SocketMode",
+  "DirectoryMode",
+  "Accept",
+  "Writable",
+  "FlushPending",
+  "MaxConnections",
+  "MaxConnectionsPerSource",
+  "KeepAlive",
+  "KeepAliveTimeSec",
+  "KeepAliveIntervalSec",
+  "KeepAliveProbes",
+  "NoDelay",
+  "Priority",
+  "DeferAcceptSec",
+  "ReceiveBuffer",
+  "SendBuffer",
+  "IPTOS",
+  "IPTTL",
+  "Mark",
+  "ReusePort",
+  "SmackLabel",
+  "SmackLabelIPIn",
+  "SmackLabelIPOut",
+  "SELinuxContextFromNet",
+  "PipeSize",
+  "MessageQueueMaxMessages",
+  "MessageQueueMessageSize",
+  "FreeBind",
+  "Transparent",
+  "Broadcast",
+  "PassCredentials",
+  "PassSecurity",
+  "PassPacketInfo",
+  "Timestamping",
+  "TCPCongestion",
+  "ExecStartPre",
+  "ExecStartPost",
+  "ExecStopPre",
+  "ExecStopPost",
+  "TimeoutSec",
+  "Service",
+  "RemoveOnStop",
+  "Symlinks",
+  "FileDescriptorName",
+  "TriggerLimitIntervalSec",
+  "TriggerLimitBurst",
+  "PollLimitIntervalSec",
+  "PollLimitBurst",
+];
+
+const MOUNT: &[&str] = &[
+  "What",
+  "Where",
+  "Type",
+  "Options",
+  "SloppyOptions",
+  "LazyUnmount",
+  "ReadWriteOnly",
+  "ForceUnmount",
+  "DirectoryMode",
+  "TimeoutSec",
+];
+
+const AUTOMOUNT: &[&str] = &["Where", "ExtraOptions", "DirectoryMode", "TimeoutIdleSec"];
+
+const SWAP: &[&str] = &["What", "Priority", "Options", "TimeoutSec"];
+
+const PATH: &[&str] = &[
+  "PathExists",
+  "PathExistsGlob",
+  "PathChanged",
+  "PathModified",
+  "DirectoryNotEmpty",
+  "Unit",
+  "MakeDirectory",
+  "DirectoryMode",
+  "TriggerLimitIntervalSec",
+  "TriggerLimitBurst",
+];
+
+const TIMER: &[&str] = &[
+  "OnActiveSec",
+  "OnBootSec",
+  "OnStartupSec",
+  "OnUnitActiveSec",
+  "OnUnitInactiveSec",
+  "OnCalendar",
+  "AccuracySec",
+  "RandomizedDelaySec",
+  "RandomizedOffsetSec",
+  "FixedRandomDelay",
+  "OnClockChange",
+  "OnTimezoneChange",
+  "Unit",
+  "Persistent",
+  "WakeSystem",
+  "RemainAfterElapse",
+  "DeferReactivation",
+];
+
+const SCOPE: &[&str] = &["RuntimeMaxSec", "RuntimeRandomizedExtraSec", "OOMPolicy"];
+
+/// Settings every assignment adds a value to, all others keep just the last one. Conditions
+/// and asserts are lists as well.
+const LIST_KEYS: &[&str] = &[
+  // [Unit]
+  "Documentation",
+  "Wants",
+  "Requires",
+  "Requisite",
+  "BindsTo",
+  "PartOf",
+  "Upholds",
+  "Conflicts",
+  "Before",
+  "After",
+  "OnFailure",
+  "OnSuccess",
+  "PropagatesReloadTo",
+  "ReloadPropagatedFrom",
+  "PropagatesStopTo",
+  "StopPropagatedFrom",
+  "JoinsNamespaceOf",
+  "RequiresMountsFor",
+  "WantsMountsFor",
+  // [Install]
+  "Alias",
+  "WantedBy",
+  "RequiredBy",
+  "UpheldBy",
+  "Also",
+  // systemd.exec
+  "Environment",
+  "EnvironmentFile",
+  "PassEnvironment",
+  "UnsetEnvironment",
+  "SupplementaryGroups",
+  "CapabilityBoundingSet",
+  "AmbientCapabilities",
+  "ReadWritePaths",
+  "ReadOnlyPaths",
+  "InaccessiblePaths",
+  "ExecPaths",
+  "NoExecPaths",
+  "BindPaths",
+  "BindReadOnlyPaths",
+  "TemporaryFileSystem",
+  "MountImages",
+  "ExtensionImages",
+  "ExtensionDirectories",
+  "SystemCallFilter",
+  "SystemCallArchitectures",
+  "SystemCallLog",
+  "RestrictAddressFamilies",
+  "RestrictFileSystems",
+  "RuntimeDirectory",
+  "StateDirectory",
+  "CacheDirectory",
+  "LogsDirectory",
+  "ConfigurationDirectory",
+  "LoadCredential",
+  "LoadCredentialEncrypted",
+  "SetCredential",
+  "SetCredentialEncrypted",
+  "ImportCredential",
+  "LogExtraFields",
+  "LogFilterPatterns",
+  // systemd.resource-control
+  "DeviceAllow",
+  "IPAddressAllow",
+  "IPAddressDeny",
+  "IPIngressFilterPath",
+  "IPEgressFilterPath",
+  "SocketBindAllow",
+  "SocketBindDeny",
+  "BPFProgram",
+  // [Service]
+  "ExecStart",
+  "ExecStartPre",
+  "ExecStartPost",
+  "ExecCondition",
+  "ExecReload",
+  "ExecStop",
+  "ExecStopPost",
+  "RestartPreventExitStatus",
+  "RestartForceExitStatus",
+  "SuccessExitStatus",
+  "Sockets",
+  "OpenFile",
+  // [Socket]
+  "ListenStream",
+  "ListenDatagram",
+  "ListenSequentialPacket",
+  "ListenFIFO",
+  "ListenSpecial",
+  "ListenNetlink",
+  "ListenMessageQueue",
+  "ListenUSBFunction",
+  "Symlinks",
+  "ExecStopPre",
+  // [Timer]
+  "OnActiveSec",
+  "OnBootSec",
+  "OnStartupSec",
+  "OnUnitActiveSec",
+  "OnUnitInactiveSec",
+  "OnCalendar",
+  // [Path]
+  "PathExists",
+  "PathExistsGlob",
+  "PathChanged",
+  "PathModified",
+  "DirectoryNotEmpty",
+];
+
+/// Section holding settings of the unit type, `None` for types without one
+pub fn type_section(unit_type: &str) -> Option<&'static str> {
+  match unit_type {
+    "service" => Some("Service"),
+    "socket" => Some("Socket"),
+    "mount" => Some("Mount"),
+    "automount" => Some("Automount"),
+    "swap" => Some("Swap"),
+    "timer" => Some("Timer"),
+    "path" => Some("Path"),
+    "slice" => Some("Slice"),
+    "scope" => Some("Scope"),
+    _ => None,
+  }
+}
+
+/// Unit type whose own section is named `section`, i.e. "service" for "Service"
+pub fn type_of_section(section: &str) -> Option<&'static str> {
+  super::unit_file::UNIT_TYPES
+    .into_iter()
+    .find(|unit_type| type_section(unit_type) == Some(section))
+}
+
+/// Lists of keys allowed in a section of a unit type, `None` if the section doesn't belong
+/// to the unit type
+fn section_keys(unit_type: &str, section: &str) -> Option<&'static [&'static [&'static str]]> {
+  if section == "Unit" {
+    return Some(&[UNIT]);
+  }
+  if section == "Install" {
+    return Some(&[INSTALL]);
+  }
+  if type_section(unit_type) != Some(section) {
+    return None;
+  }
+
+  let keys: &[&[&str]] = match unit_type {
+    "service" => &[SERVICE, EXEC, KILL, RESOURCE_CONTROL],
+    "socket" => &[SOCKET, EXEC, KILL, RESOURCE_CONTROL],
+    "mount" => &[MOUNT, EXEC, KILL, RESOURCE_CONTROL],
+    "swap" => &[SWAP, EXEC, KILL, RESOURCE_CONTROL],
+    "automount" => &[AUTOMOUNT],
+    "timer" => &[TIMER],
+    "path" => &[PATH],
+    "slice" => &[RESOURCE_CONTROL],
+    "scope" => &[SCOPE, KILL, RESOURCE_CONTROL],
+    _ => &[],
+  };
+  Some(keys)
+}
+
+/// Whether the section belongs to the unit type
+pub fn is_known_section(unit_type: &str, section: &str) -> bool {
+  section_keys(unit_type, section).is_some()
+}
+
+/// Whether systemd understands the key in a section of the unit type
+pub fn is_known_key(unit_type: &str, section: &str, key: &str) -> bool {
+  if section == "Unit" {
+    let condition = key
+      .strip_prefix("Condition")
+      .or_else(|| key.strip_prefix("Assert"));
+    if condition.is_some_and(|condition| CONDITIONS.contains(&condition)) {
+      return true;
+    }
+  }
+
+  section_keys(unit_type, section).is_some_and(|lists| lists.iter().any(|keys| keys.contains(&key)))
+}
+
+/// Whether every assignment of the key adds a value instead of replacing the previous one
+pub fn is_list_key(key: &str) -> bool {
+  key.starts_with("Condition") || key.starts_with("Assert") || LIST_KEYS.contains(&key)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn knows_keys_of_unit_types() {
+    assert!(is_known_key("service", "Service", "ExecStart"));
+    assert!(is_known_key("service", "Service", "MemoryMax"));
+    assert!(is_known_key("service", "Service", "User"));
+    assert!(is_known_key("timer", "Unit", "After"));
+    assert!(is_known_key("", "Install", "WantedBy"));
+    assert!(!is_known_key("timer", "Timer", "ExecStart"));
+    assert!(!is_known_key("service", "Service", "OnCalendar"));
+    assert!(!is_known_key("service", "Socket", "ListenStream"));
+  }
+
+  #[test]
+  fn knows_conditions_and_asserts() {
+    assert!(is_known_key("service", "Unit", "ConditionPathExists"));
+    assert!(is_known_key("service", "Unit", "AssertPathExists"));
+    assert!(!is_known_key("service", "Unit", "ConditionColour"));
+    assert!(!is_known_key("service", "Service", "ConditionPathExists"));
+  }
+
+  #[test]
+  fn knows_sections_of_unit_types() {
+    assert!(is_known_section("service", "Service"));
+    assert!(is_known_section("target", "Unit"));
+    assert!(!is_known_section("target", "Service"));
+    assert_eq!(type_section("slice"), Some("Slice"));
+    assert_eq!(type_section("target"), None);
+    assert_eq!(type_of_section("Socket"), Some("socket"));
+    assert_eq!(type_of_section("Install"), None);
+  }
+
+  #[test]
+  fn knows_list_keys() {
+    assert!(is_list_key("ExecStartPre"));
+    assert!(is_list_key("ConditionPathExists"));
+    assert!(!is_list_key("ExecMainPID"));
+    assert!(!is_list_key("Type"));
+  }
+}
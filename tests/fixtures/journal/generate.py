@@ -0,0 +1,264 @@
+#!/usr/bin/env python3
+"""Regenerates the journal files used by the tests of src/journald.
+
+Runs a private journald namespace, so the files are written by journald itself. Needs root,
+systemd-journald and the name=systemd cgroup hierarchy, which is what puts the fixture
+processes into units. Boot ids are faked by bind mounting over boot_id in a mount namespace.
+
+Files:
+  plain.journal                one boot, closed cleanly
+  machine/system@*.journal     rotated away while boot A was running, archived
+  machine/system.journal       rest of boot A and all of boot B
+  machine/system@*.journal~    corrupted file journald put aside, readers skip it
+  truncated.journal            plain.journal cut in the middle of the arena
+  corrupt.journal              plain.journal with a huge entry count and a broken entry array
+"""
+
+import os
+import shutil
+import signal
+import socket
+import struct
+import subprocess
+import sys
+import time
+
+NAMESPACE = "dragond-fixtures"
+BOOT_A = "0a0a0a0a-0a0a-4a0a-8a0a-0a0a0a0a0a0a"
+BOOT_B = "0b0b0b0b-0b0b-4b0b-8b0b-0b0b0b0b0b0b"
+CONFIG = f"/etc/systemd/journald@{NAMESPACE}.conf"
+SOCKET = f"/run/systemd/journal.{NAMESPACE}/socket"
+CGROUP_ROOTS = ["/sys/fs/cgroup/systemd", "/sys/fs/cgroup/unified"]
+OUTPUT = os.path.dirname(os.path.abspath(__file__))
+
+# uid of the worker process of fixture-app.service
+NOBODY = 65534
+
+
+def journal_directory():
+  machine_id = open("/etc/machine-id").read().strip()
+  return f"/run/log/journal/{machine_id}.{NAMESPACE}"
+
+
+def start_journald(boot_id):
+  boot_file = f"/tmp/{NAMESPACE}-boot-id"
+  with open(boot_file, "w") as file:
+    file.write(boot_id + "\n")
+
+  process = subprocess.Popen(
+    [
+      "unshare",
+      "--mount",
+      "sh",
+      "-c",
+      f"mount --bind {boot_file} /proc/sys/kernel/random/boot_id"
+      f" && exec /lib/systemd/systemd-journald {NAMESPACE}",
+    ]
+  )
+  for _ in range(50):
+    if os.path.exists(SOCKET):
+      break
+    time.sleep(0.1)
+  time.sleep(0.5)
+  return process
+
+
+def stop_journald(process):
+  process.send_signal(signal.SIGTERM)
+  process.wait()
+  os.unlink(SOCKET)
+
+
+def rotate(process):
+  process.send_signal(signal.SIGUSR2)
+  time.sleep(0.5)
+
+
+def field(name, value):
+  if isinstance(value, str):
+    value = value.encode()
+  if b"\n" in value:
+    return name.encode() + b"\n" + struct.pack("<Q", len(value)) + value + b"\n"
+  return name.encode() + b"=" + value + b"\n"
+
+
+def send(step, message, priority=6, identifier="fixture-app", **fields):
+  datagram = field("MESSAGE", message)
+  datagram += field("PRIORITY", str(priority))
+  datagram += field("SYSLOG_IDENTIFIER", identifier)
+  datagram += field("FIXTURE_STEP", str(step))
+  for name, value in fields.items():
+    datagram += field(name, value)
+
+  sock = socket.socket(socket.AF_UNIX, socket.SOCK_DGRAM)
+  sock.sendto(datagram, SOCKET)
+  sock.close()
+  # Keeps wall clock timestamps of entries apart
+  time.sleep(0.01)
+
+
+def in_process(unit, uid, messages):
+  """Sends messages from a new process in the cgroup of the unit, running as uid"""
+  pid = os.fork()
+  if pid == 0:
+    if unit is not None:
+      for root in CGROUP_ROOTS:
+        path = f"{root}/system.slice/{unit}"
+        os.makedirs(path, exist_ok=True)
+        with open(f"{path}/cgroup.procs", "w") as procs:
+          procs.write(str(os.getpid()))
+    os.setuid(uid)
+    for args, kwargs in messages:
+      send(*args, **kwargs)
+    os._exit(0)
+  os.waitpid(pid, 0)
+
+
+def message(*args, **kwargs):
+  return (args, kwargs)
+
+
+def write_boot(first_step, boot):
+  """Messages of one boot: two processes of fixture-app.service, one of another unit and the
+  manager logging about fixture-app.service"""
+  step = first_step
+  in_process(
+    "fixture-app.service",
+    0,
+    [
+      message(step, f"app started on boot {boot}"),
+      message(step + 1, "app is low on disk space", priority=4),
+      message(step + 2, "app dumped its state:\n" + "state line\n" * 100, priority=7),
+    ],
+  )
+  in_process(
+    "fixture-app.service",
+    NOBODY,
+    [
+      message(step + 3, "worker ready", identifier="fixture-worker"),
+      message(step + 4, "worker failed to connect", priority=3, identifier="fixture-worker"),
+    ],
+  )
+  in_process(
+    "fixture-other.service",
+    0,
+    [message(step + 5, "other unit says hi", identifier="fixture-other")],
+  )
+  in_process(
+    None,
+    0,
+    [
+      message(
+        step + 6,
+        "fixture-app.service: Consumed 1.000s CPU time.",
+        identifier="fixture-manager",
+        OBJECT_SYSTEMD_UNIT="fixture-app.service",
+      ),
+      message(
+        step + 7,
+        "binary payload",
+        identifier="fixture-manager",
+        FIXTURE_BINARY=b"\x00\xff\xfe",
+      ),
+    ],
+  )
+  return step + 8
+
+
+def compact(path):
+  """journald preallocates files, cuts the unused space off so the fixtures stay small"""
+  with open(path, "r+b") as file:
+    header = file.read(272)
+    header_size = struct.unpack_from("<Q", header, 88)[0]
+    tail_object_offset = struct.unpack_from("<Q", header, 136)[0]
+    file.seek(tail_object_offset + 8)
+    tail_object_size = struct.unpack("<Q", file.read(8))[0]
+
+    end = (tail_object_offset + tail_object_size + 7) // 8 * 8
+    file.seek(96)
+    file.write(struct.pack("<Q", end - header_size))
+    file.truncate(end)
+
+
+def collect(directory, target):
+  os.makedirs(target, exist_ok=True)
+  for name in os.listdir(directory):
+    shutil.copyfile(os.path.join(directory, name), os.path.join(target, name))
+    compact(os.path.join(target, name))
+    os.chmod(os.path.join(target, name), 0o644)
+
+
+def main():
+  if os.geteuid() != 0:
+    sys.exit("Has to run as root")
+
+  with open(CONFIG, "w") as config:
+    config.write("[Journal]\nStorage=volatile\nCompress=yes\nSeal=no\nSplitMode=none\nRuntimeMaxFileSize=512K\n")
+  directory = journal_directory()
+
+  try:
+    for name in ["plain.journal", "truncated.journal", "corrupt.journal", "machine"]:
+      path = os.path.join(OUTPUT, name)
+      if os.path.isdir(path):
+        shutil.rmtree(path)
+      elif os.path.exists(path):
+        os.unlink(path)
+
+    # plain.journal
+    shutil.rmtree(directory, ignore_errors=True)
+    journald = start_journald(BOOT_A)
+    write_boot(1, "A")
+    stop_journald(journald)
+    shutil.copyfile(f"{directory}/system.journal", f"{OUTPUT}/plain.journal")
+    compact(f"{OUTPUT}/plain.journal")
+
+    # machine/, rotated once during boot A, then rebooted into boot B
+    shutil.rmtree(directory, ignore_errors=True)
+    journald = start_journald(BOOT_A)
+    step = write_boot(1, "A")
+    rotate(journald)
+    step = write_boot(step, "A")
+    stop_journald(journald)
+    journald = start_journald(BOOT_B)
+    write_boot(step, "B")
+    stop_journald(journald)
+    collect(directory, f"{OUTPUT}/machine")
+
+    with open(f"{OUTPUT}/plain.journal", "rb") as file:
+      plain = bytearray(file.read())
+    header_size = struct.unpack_from("<Q", plain, 88)[0]
+    entry_array_offset = struct.unpack_from("<Q", plain, 176)[0]
+    compact_items = struct.unpack_from("<I", plain, 12)[0] & (1 << 4) != 0
+
+    with open(f"{OUTPUT}/machine/system@0000000000000000-0000000000000000.journal~", "wb") as file:
+      file.write(plain[: header_size // 2])
+
+    with open(f"{OUTPUT}/truncated.journal", "wb") as file:
+      file.write(plain[: header_size + (len(plain) - header_size) // 2])
+
+    corrupt = bytearray(plain)
+    # n_entries
+    struct.pack_into("<Q", corrupt, 152, 1 << 60)
+    # Second item of the first entry array points into the middle of an object
+    if compact_items:
+      struct.pack_into("<I", corrupt, entry_array_offset + 16 + 8 + 4, header_size + 8)
+    else:
+      struct.pack_into("<Q", corrupt, entry_array_offset + 16 + 8 + 8, header_size + 8)
+    with open(f"{OUTPUT}/corrupt.journal", "wb") as file:
+      file.write(corrupt)
+
+    for name in ["plain.journal", "truncated.journal", "corrupt.journal"]:
+      os.chmod(os.path.join(OUTPUT, name), 0o644)
+  finally:
+    os.unlink(CONFIG)
+    shutil.rmtree(directory, ignore_errors=True)
+    for root in CGROUP_ROOTS:
+      for unit in ["fixture-app.service", "fixture-other.service"]:
+        try:
+          os.rmdir(f"{root}/system.slice/{unit}")
+        except OSError:
+          pass
+
+
+if __name__ == "__main__":
+  main()